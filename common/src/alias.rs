@@ -1,172 +1,433 @@
 //! エイリアス変換モジュール
 //!
 //! 写真区分や工種の表記ゆれを正規化する。
+//!
+//! 旧形式の項目ごとの対応表（`photo_category` などの `{"変換元": "変換先"}`）に加えて、
+//! `rules` に条件・正規表現・優先度つきの規則を書ける。
+//!
+//! ```json
+//! {
+//!   "work_type": { "舗装": "舗装工" },
+//!   "rules": [
+//!     { "field": "station", "from": "re:^No\\.?\\s*(\\d+)$", "to": "No.$1" },
+//!     { "name": "乳剤散布", "field": "remarks", "from": "タックコート", "to": "乳剤散布状況",
+//!       "when": { "work_type": "舗装工" }, "priority": 10 }
+//!   ]
+//! }
+//! ```
+//!
+//! - `from` は部分一致（一致したら値全体を `to` にする）、`re:` で始まれば正規表現
+//!   （最初に一致した部分を `to` に置き換え、`$1` などで括弧の中身を使える）
+//! - `when` は項目ごとの条件（完全一致、`re:` で正規表現）。すべて満たすときだけ適用する。
+//!   qc の規格・正規化の規則ファイルも同じ条件（[`Conditions`]）を使う
+//! - 項目は 写真区分 → 工種 → 種別 → 作業段階 → 備考 → 測点 → 写真説明 の順に変換し、
+//!   条件は変換済みの値で判定する
+//! - 1つの項目には最初に一致した規則だけを適用する。規則は `priority` の高い順、
+//!   同じ優先度では `rules` の記載順 → 旧形式の対応表（長い変換元から）の順
 
 use crate::types::AnalysisResult;
-use crate::error::Result;
+use crate::error::{Error, Result};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use crate::alias_preset::AliasRegistry;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// 変換・条件の項目（日付・計測値は条件にだけ使える）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AliasField {
+    #[serde(alias = "photoCategory")]
+    PhotoCategory,
+    #[serde(alias = "workType")]
+    WorkType,
+    Variety,
+    #[serde(alias = "detail")]
+    Subphase,
+    Remarks,
+    Station,
+    Description,
+    Date,
+    Measurements,
+}
+
+impl AliasField {
+    /// 変換する順（変換できる項目）
+    pub const ALL: [AliasField; 7] = [
+        AliasField::PhotoCategory,
+        AliasField::WorkType,
+        AliasField::Variety,
+        AliasField::Subphase,
+        AliasField::Remarks,
+        AliasField::Station,
+        AliasField::Description,
+    ];
+
+    /// 解析結果のこの項目の値
+    pub fn get(self, result: &AnalysisResult) -> &str {
+        match self {
+            AliasField::PhotoCategory => &result.photo_category,
+            AliasField::WorkType => &result.work_type,
+            AliasField::Variety => &result.variety,
+            AliasField::Subphase => &result.subphase,
+            AliasField::Remarks => &result.remarks,
+            AliasField::Station => &result.station,
+            AliasField::Description => &result.description,
+            AliasField::Date => &result.date,
+            AliasField::Measurements => &result.measurements,
+        }
+    }
+
+    fn get_mut(self, result: &mut AnalysisResult) -> &mut String {
+        match self {
+            AliasField::PhotoCategory => &mut result.photo_category,
+            AliasField::WorkType => &mut result.work_type,
+            AliasField::Variety => &mut result.variety,
+            AliasField::Subphase => &mut result.subphase,
+            AliasField::Remarks => &mut result.remarks,
+            AliasField::Station => &mut result.station,
+            AliasField::Description => &mut result.description,
+            AliasField::Date => &mut result.date,
+            AliasField::Measurements => &mut result.measurements,
+        }
+    }
+}
+
+impl fmt::Display for AliasField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AliasField::PhotoCategory => "写真区分",
+            AliasField::WorkType => "工種",
+            AliasField::Variety => "種別",
+            AliasField::Subphase => "作業段階",
+            AliasField::Remarks => "備考",
+            AliasField::Station => "測点",
+            AliasField::Description => "写真説明",
+            AliasField::Date => "日付",
+            AliasField::Measurements => "計測値",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 条件・正規表現・優先度つきの規則
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AliasRule {
+    /// 報告に使う名前（省略時は「項目: 変換元 → 変換先」）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    pub field: AliasField,
+    /// 部分一致する文字、または `re:` で始まる正規表現
+    pub from: String,
+    pub to: String,
+    /// 適用する条件（項目ごとに完全一致、`re:` で正規表現）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub when: BTreeMap<AliasField, String>,
+    /// 大きいほど先に判定する（既定は0）
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl AliasRule {
+    /// 報告に使う名前
+    pub fn label(&self) -> String {
+        if self.name.is_empty() {
+            format!("{}: {} → {}", self.field, self.from, self.to)
+        } else {
+            self.name.clone()
+        }
+    }
+}
 
 /// エイリアス定義
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AliasConfig {
     /// 写真区分のエイリアス
-    #[serde(default)]
-    pub photo_category: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub photo_category: BTreeMap<String, String>,
     /// 工種のエイリアス
-    #[serde(default)]
-    pub work_type: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub work_type: BTreeMap<String, String>,
     /// 種別のエイリアス
-    #[serde(default)]
-    pub variety: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variety: BTreeMap<String, String>,
     /// 作業段階のエイリアス
-    #[serde(default)]
-    pub subphase: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub subphase: BTreeMap<String, String>,
+    /// 条件・正規表現・優先度つきの規則
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<AliasRule>,
 }
 
 impl AliasConfig {
-    /// 組み込みプリセットを取得
+    /// 組み込みプリセットを取得（[`crate::alias_preset`]）
     pub fn from_preset(name: &str) -> Option<Self> {
-        match name.to_lowercase().as_str() {
-            "pavement" | "舗装" => Some(Self::pavement_preset()),
-            "marking" | "区画線" => Some(Self::marking_preset()),
-            "general" | "汎用" => Some(Self::general_preset()),
-            _ => None,
-        }
+        AliasRegistry::builtin().get(name).map(|preset| preset.config.clone())
     }
 
     /// JSONファイルから読み込み（非WASM環境のみ）
     #[cfg(not(feature = "wasm"))]
     pub fn from_file(path: &std::path::Path) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
-        let config: Self = serde_json::from_str(&content)?;
-        Ok(config)
+        Self::from_json(&content)
     }
 
-    /// JSON文字列から読み込み
+    /// JSON文字列から読み込み（不正な規則があればエラー）
     pub fn from_json(json: &str) -> Result<Self> {
-        let config: Self = serde_json::from_str(json)?;
+        let config: Self = serde_json::from_str(json.trim_start_matches('\u{feff}'))?;
+        if let Some(error) = config.errors().into_iter().next() {
+            return Err(error);
+        }
         Ok(config)
     }
 
-    /// 舗装工事用プリセット
-    fn pavement_preset() -> Self {
-        let mut config = Self::default();
+    /// 不正な規則（正規表現の誤り・空の変換元）のエラー一覧
+    pub fn errors(&self) -> Vec<Error> {
+        self.rules.iter().filter_map(|rule| CompiledRule::new(rule).err()).collect()
+    }
 
-        // 写真区分
-        config.photo_category.insert("品質".into(), "品質管理写真".into());
-        config.photo_category.insert("品質管理".into(), "品質管理写真".into());
-        config.photo_category.insert("出来形".into(), "出来形管理写真".into());
-        config.photo_category.insert("出来形管理".into(), "出来形管理写真".into());
-        config.photo_category.insert("施工状況".into(), "施工状況写真".into());
-        config.photo_category.insert("施工中".into(), "施工状況写真".into());
-        config.photo_category.insert("安全".into(), "安全管理写真".into());
-        config.photo_category.insert("安全管理".into(), "安全管理写真".into());
-        config.photo_category.insert("材料".into(), "使用材料写真".into());
-        config.photo_category.insert("使用材料".into(), "使用材料写真".into());
+    /// 適用順に並べた規則に変換（不正な規則は読み飛ばす）
+    pub fn compile(&self) -> AliasRules {
+        let mut rules: Vec<CompiledRule> = self.rules.iter().filter_map(|rule| CompiledRule::new(rule).ok()).collect();
 
-        // 工種
-        config.work_type.insert("舗装".into(), "舗装工".into());
-        config.work_type.insert("As".into(), "舗装工".into());
-        config.work_type.insert("アスファルト".into(), "舗装工".into());
+        let legacy = [
+            (AliasField::PhotoCategory, &self.photo_category),
+            (AliasField::WorkType, &self.work_type),
+            (AliasField::Variety, &self.variety),
+            (AliasField::Subphase, &self.subphase),
+        ];
+        for (field, aliases) in legacy {
+            // 長い変換元から判定する（完全一致・最長一致が優先される）
+            let mut entries: Vec<(&String, &String)> = aliases.iter().collect();
+            entries.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(b.0)));
+            rules.extend(entries.into_iter().map(|(from, to)| CompiledRule {
+                label: format!("{}: {} → {}", field, from, to),
+                field,
+                matcher: AliasMatcher::Text(from.clone()),
+                to: to.clone(),
+                when: Conditions::default(),
+                priority: 0,
+                skip_empty: true,
+            }));
+        }
 
-        // 種別
-        config.variety.insert("打換え".into(), "舗装打換え工".into());
-        config.variety.insert("打換".into(), "舗装打換え工".into());
-        config.variety.insert("オーバーレイ".into(), "舗装オーバーレイ工".into());
+        // 安定ソートなので同じ優先度は rules → 旧形式の順のまま
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        AliasRules { rules }
+    }
 
-        // 作業段階
-        config.subphase.insert("表層".into(), "表層工".into());
-        config.subphase.insert("基層".into(), "基層工".into());
-        config.subphase.insert("上層路盤".into(), "上層路盤工".into());
-        config.subphase.insert("下層路盤".into(), "下層路盤工".into());
+    /// 解析結果にエイリアス変換を適用
+    pub fn apply(&self, result: &AnalysisResult) -> AnalysisResult {
+        let mut updated = result.clone();
+        self.compile().apply(&mut updated);
+        updated
+    }
 
-        config
+    /// 設定をマージ（後から追加した設定が優先）
+    pub fn merge(&mut self, other: &AliasConfig) {
+        self.photo_category.extend(other.photo_category.clone());
+        self.work_type.extend(other.work_type.clone());
+        self.variety.extend(other.variety.clone());
+        self.subphase.extend(other.subphase.clone());
+        let mut rules = other.rules.clone();
+        rules.append(&mut self.rules);
+        self.rules = rules;
     }
+}
 
-    /// 区画線工事用プリセット
-    fn marking_preset() -> Self {
-        let mut config = Self::default();
+/// 変換元・条件の照合方法（`re:` で始まれば正規表現）
+#[derive(Debug, Clone)]
+pub enum AliasMatcher {
+    Text(String),
+    Regex(Regex),
+}
 
-        // 写真区分
-        config.photo_category.insert("品質".into(), "品質管理写真".into());
-        config.photo_category.insert("出来形".into(), "出来形管理写真".into());
-        config.photo_category.insert("施工状況".into(), "施工状況写真".into());
+impl AliasMatcher {
+    /// 解釈する（`label` はエラーの先頭に付ける規則の名前。例: `規則「a」`）
+    pub fn parse(source: &str, label: &str) -> Result<Self> {
+        match source.strip_prefix("re:") {
+            Some(expr) => Regex::new(expr)
+                .map(AliasMatcher::Regex)
+                .map_err(|e| Error::Parse(format!("{}: 正規表現が不正です（{}）", label, e))),
+            None => Ok(AliasMatcher::Text(source.to_string())),
+        }
+    }
 
-        // 工種
-        config.work_type.insert("区画線".into(), "区画線工".into());
-        config.work_type.insert("ライン".into(), "区画線工".into());
-        config.work_type.insert("白線".into(), "区画線工".into());
+    /// 条件として判定（文字は完全一致）
+    pub fn is_satisfied(&self, value: &str) -> bool {
+        match self {
+            AliasMatcher::Text(expected) => value == expected,
+            AliasMatcher::Regex(re) => re.is_match(value),
+        }
+    }
+}
 
-        // 種別
-        config.variety.insert("溶融式".into(), "溶融式区画線".into());
-        config.variety.insert("ペイント".into(), "ペイント式区画線".into());
+/// 項目ごとの条件（`when`）。すべて満たす写真に一致する
+#[derive(Debug, Clone, Default)]
+pub struct Conditions {
+    conditions: Vec<(AliasField, AliasMatcher)>,
+}
 
-        config
+impl Conditions {
+    /// 解釈する（`label` はエラーの先頭に付ける規則の名前）
+    pub fn parse(when: &BTreeMap<AliasField, String>, label: &str) -> Result<Self> {
+        let conditions = when
+            .iter()
+            .map(|(field, value)| Ok((*field, AliasMatcher::parse(value, label)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { conditions })
     }
 
-    /// 汎用プリセット
-    fn general_preset() -> Self {
-        let mut config = Self::default();
-
-        // 写真区分のみ
-        config.photo_category.insert("品質".into(), "品質管理写真".into());
-        config.photo_category.insert("出来形".into(), "出来形管理写真".into());
-        config.photo_category.insert("施工".into(), "施工状況写真".into());
-        config.photo_category.insert("安全".into(), "安全管理写真".into());
-        config.photo_category.insert("材料".into(), "使用材料写真".into());
-        config.photo_category.insert("着工".into(), "着工前写真".into());
-        config.photo_category.insert("完成".into(), "完成写真".into());
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
 
-        config
+    /// 解析結果がすべての条件を満たすか
+    pub fn matches(&self, result: &AnalysisResult) -> bool {
+        self.conditions.iter().all(|(field, condition)| condition.is_satisfied(field.get(result)))
     }
+}
 
-    /// フィールドを変換（部分一致で最長マッチ）
-    fn transform_field(&self, value: &str, aliases: &HashMap<String, String>) -> String {
-        if value.is_empty() {
-            return value.to_string();
-        }
+/// 照合できる形にした規則
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    label: String,
+    field: AliasField,
+    matcher: AliasMatcher,
+    to: String,
+    when: Conditions,
+    priority: i32,
+    /// 空の値には適用しない（旧形式）
+    skip_empty: bool,
+}
 
-        // 完全一致を優先
-        if let Some(replacement) = aliases.get(value) {
-            return replacement.clone();
+impl CompiledRule {
+    fn new(rule: &AliasRule) -> Result<Self> {
+        let label = rule.label();
+        let prefix = format!("エイリアス「{}」", label);
+        if !AliasField::ALL.contains(&rule.field) {
+            return Err(Error::Parse(format!("{}: {}は変換できません（条件にだけ使えます）", prefix, rule.field)));
         }
-
-        // 部分一致（最長マッチ）
-        let mut best_match: Option<(&str, &str)> = None;
-        for (pattern, replacement) in aliases {
-            if value.contains(pattern.as_str())
-                && (best_match.is_none() || pattern.len() > best_match.unwrap().0.len())
-            {
-                best_match = Some((pattern.as_str(), replacement.as_str()));
-            }
+        let matcher = AliasMatcher::parse(&rule.from, &prefix)?;
+        if matches!(&matcher, AliasMatcher::Text(text) if text.is_empty()) {
+            return Err(Error::Parse(format!("{}: 変換元が空です", prefix)));
         }
+        let when = Conditions::parse(&rule.when, &prefix)?;
+        Ok(Self { label, field: rule.field, matcher, to: rule.to.clone(), when, priority: rule.priority, skip_empty: false })
+    }
 
-        if let Some((_, replacement)) = best_match {
-            replacement.to_string()
-        } else {
-            value.to_string()
+    /// 変換後の値（一致しなければ `None`）
+    fn replace(&self, value: &str) -> Option<String> {
+        match &self.matcher {
+            AliasMatcher::Text(from) => value.contains(from.as_str()).then(|| self.to.clone()),
+            AliasMatcher::Regex(re) => re.is_match(value).then(|| re.replace(value, self.to.as_str()).into_owned()),
         }
     }
+}
 
-    /// 解析結果にエイリアス変換を適用
-    pub fn apply(&self, result: &AnalysisResult) -> AnalysisResult {
-        let mut updated = result.clone();
+/// 適用したエイリアス1件
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasHit {
+    /// 規則の名前
+    pub rule: String,
+    pub file_name: String,
+    pub field: AliasField,
+    pub before: String,
+    pub after: String,
+}
 
-        updated.photo_category = self.transform_field(&result.photo_category, &self.photo_category);
-        updated.work_type = self.transform_field(&result.work_type, &self.work_type);
-        updated.variety = self.transform_field(&result.variety, &self.variety);
-        updated.subphase = self.transform_field(&result.subphase, &self.subphase);
+/// エイリアスの適用結果の報告
+#[derive(Debug, Clone, Default)]
+pub struct AliasReport {
+    /// すべての規則の名前（適用順）
+    pub rules: Vec<String>,
+    pub hits: Vec<AliasHit>,
+}
 
-        updated
+impl AliasReport {
+    /// 適用された規則と件数（規則の適用順）
+    pub fn fired(&self) -> Vec<(&str, usize)> {
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                let count = self.hits.iter().filter(|hit| hit.rule == *rule).count();
+                (count > 0).then_some((rule.as_str(), count))
+            })
+            .collect()
     }
 
-    /// 設定をマージ（後から追加した設定が優先）
-    pub fn merge(&mut self, other: &AliasConfig) {
-        self.photo_category.extend(other.photo_category.clone());
-        self.work_type.extend(other.work_type.clone());
-        self.variety.extend(other.variety.clone());
-        self.subphase.extend(other.subphase.clone());
+    /// 一度も適用されなかった規則
+    pub fn unused(&self) -> Vec<&str> {
+        self.rules
+            .iter()
+            .filter(|rule| !self.hits.iter().any(|hit| hit.rule == **rule))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// 適用順に並べた規則
+#[derive(Debug, Clone, Default)]
+pub struct AliasRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl AliasRules {
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 解析結果1件を変換し、値が変わった規則を返す
+    pub fn apply(&self, result: &mut AnalysisResult) -> Vec<AliasHit> {
+        let mut hits = Vec::new();
+        for field in AliasField::ALL {
+            for rule in self.rules.iter().filter(|rule| rule.field == field) {
+                let value = field.get(result);
+                if rule.skip_empty && value.is_empty() {
+                    continue;
+                }
+                if !rule.when.matches(result) {
+                    continue;
+                }
+                let Some(after) = rule.replace(value) else {
+                    continue;
+                };
+                // 1つの項目には最初に一致した規則だけを適用する
+                if after != value {
+                    hits.push(AliasHit {
+                        rule: rule.label.clone(),
+                        file_name: result.file_name.clone(),
+                        field,
+                        before: value.to_string(),
+                        after: after.clone(),
+                    });
+                    *field.get_mut(result) = after;
+                }
+                break;
+            }
+        }
+        hits
+    }
+
+    /// 解析結果をまとめて変換し、報告を返す
+    pub fn apply_all(&self, results: &[AnalysisResult]) -> (Vec<AnalysisResult>, AliasReport) {
+        let mut report = AliasReport {
+            rules: self.rules.iter().map(|rule| rule.label.clone()).collect(),
+            hits: Vec::new(),
+        };
+        let transformed = results
+            .iter()
+            .map(|result| {
+                let mut updated = result.clone();
+                report.hits.extend(self.apply(&mut updated));
+                updated
+            })
+            .collect();
+        (transformed, report)
     }
 }
 
@@ -180,12 +441,13 @@ pub fn apply_aliases(
     let mut config = AliasConfig::default();
 
     // プリセットを適用
+    // 不明なプリセットは警告して続ける
     if let Some(preset_name) = preset {
-        if let Some(preset_config) = AliasConfig::from_preset(preset_name) {
-            config.merge(&preset_config);
-        } else {
-            eprintln!("警告: 不明なプリセット '{}' (pavement/marking/general)", preset_name);
+        let (preset_config, warnings) = AliasRegistry::builtin().stack_known(&[preset_name]);
+        for warning in warnings {
+            eprintln!("警告: {}", warning);
         }
+        config.merge(&preset_config);
     }
 
     // カスタムエイリアスJSONを適用（プリセットを上書き）
@@ -195,10 +457,7 @@ pub fn apply_aliases(
     }
 
     // 変換を適用
-    let transformed: Vec<AnalysisResult> = results
-        .iter()
-        .map(|r| config.apply(r))
-        .collect();
+    let (transformed, _) = config.compile().apply_all(results);
 
     Ok(transformed)
 }
@@ -216,7 +475,7 @@ mod tests {
 
     #[test]
     fn test_transform_exact_match() {
-        let config = AliasConfig::pavement_preset();
+        let config = AliasConfig::from_preset("pavement").unwrap();
 
         let result = AnalysisResult {
             file_name: "test.jpg".to_string(),
@@ -232,7 +491,7 @@ mod tests {
 
     #[test]
     fn test_transform_partial_match() {
-        let config = AliasConfig::pavement_preset();
+        let config = AliasConfig::from_preset("pavement").unwrap();
 
         let result = AnalysisResult {
             file_name: "test.jpg".to_string(),
@@ -263,5 +522,93 @@ mod tests {
 
         assert_eq!(transformed[0].photo_category, "品質管理写真");
         assert_eq!(transformed[1].photo_category, "出来形管理写真");
+
+        // 不明なプリセットは警告して変換しない
+        let unchanged = apply_aliases(&results, Some("unknown"), None).unwrap();
+        assert_eq!(unchanged[0].photo_category, "品質");
+    }
+
+    #[test]
+    fn test_regex_condition_and_priority_rules() {
+        let config = AliasConfig::from_json(r#"{
+            "work_type": { "舗装": "舗装工" },
+            "rules": [
+                { "field": "station", "from": "re:^No\\.?\\s*(\\d+)$", "to": "No.$1" },
+                { "name": "区画線の乳剤", "field": "remarks", "from": "乳剤", "to": "プライマー塗布状況",
+                  "when": { "work_type": "区画線工" } },
+                { "name": "舗装の乳剤", "field": "remarks", "from": "乳剤", "to": "乳剤散布状況",
+                  "when": { "work_type": "re:^舗装" } },
+                { "name": "散布全般", "field": "remarks", "from": "散布", "to": "散布状況", "priority": 10 },
+                { "field": "description", "from": "re:As", "to": "アスファルト" }
+            ]
+        }"#).unwrap();
+        let rules = config.compile();
+        let results = vec![
+            AnalysisResult {
+                file_name: "a.jpg".to_string(),
+                work_type: "舗装".to_string(),
+                remarks: "乳剤".to_string(),
+                station: "No 12".to_string(),
+                description: "As舗装".to_string(),
+                ..Default::default()
+            },
+            AnalysisResult {
+                file_name: "b.jpg".to_string(),
+                work_type: "舗装".to_string(),
+                remarks: "乳剤散布".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let (transformed, report) = rules.apply_all(&results);
+        // 条件は変換後の工種で判定
+        assert_eq!(transformed[0].work_type, "舗装工");
+        assert_eq!(transformed[0].remarks, "乳剤散布状況");
+        assert_eq!(transformed[0].station, "No.12");
+        assert_eq!(transformed[0].description, "アスファルト舗装");
+        // 優先度の高い規則が先
+        assert_eq!(transformed[1].remarks, "散布状況");
+
+        assert_eq!(report.fired(), vec![
+            ("散布全般", 1),
+            ("測点: re:^No\\.?\\s*(\\d+)$ → No.$1", 1),
+            ("舗装の乳剤", 1),
+            ("写真説明: re:As → アスファルト", 1),
+            ("工種: 舗装 → 舗装工", 2),
+        ]);
+        assert_eq!(report.unused(), vec!["区画線の乳剤"]);
+    }
+
+    #[test]
+    fn test_invalid_rule_is_rejected() {
+        let error = AliasConfig::from_json(r#"{ "rules": [{ "field": "station", "from": "re:(", "to": "" }] }"#).unwrap_err();
+        assert!(error.to_string().contains("正規表現が不正です"));
+        assert!(AliasConfig::from_json(r#"{ "rules": [{ "field": "remarks", "from": "", "to": "x" }] }"#).is_err());
+        // 計測値は条件にだけ使える
+        let error = AliasConfig::from_json(r#"{ "rules": [{ "field": "measurements", "from": "a", "to": "b" }] }"#).unwrap_err();
+        assert!(error.to_string().contains("計測値は変換できません"));
+    }
+
+    #[test]
+    fn test_conditions() {
+        let when: BTreeMap<AliasField, String> = serde_json::from_str(
+            r#"{ "remarks": "到着温度", "measurements": "re:\\d+℃", "date": "re:^2025-" }"#,
+        )
+        .unwrap();
+        let conditions = Conditions::parse(&when, "規則「a」").unwrap();
+        let mut result = AnalysisResult {
+            remarks: "到着温度".to_string(),
+            measurements: "162℃".to_string(),
+            date: "2025-01-18".to_string(),
+            ..Default::default()
+        };
+        assert!(conditions.matches(&result));
+        result.remarks = "到着温度測定".to_string();
+        assert!(!conditions.matches(&result));
+        assert!(Conditions::default().matches(&result));
+
+        let bad: BTreeMap<AliasField, String> = [(AliasField::Station, "re:(".to_string())].into();
+        let error = Conditions::parse(&bad, "規則「b」").unwrap_err();
+        assert!(error.to_string().contains("規則「b」: 正規表現が不正です"));
     }
 }
@@ -0,0 +1,190 @@
+//! エイリアスのプリセット
+//!
+//! プリセットはエイリアスJSON（[`crate::alias`]）に名前・別名・説明を足したファイル。
+//!
+//! ```json
+//! { "name": "pavement", "names": ["舗装"], "description": "舗装工事用",
+//!   "work_type": { "舗装": "舗装工" } }
+//! ```
+//!
+//! 組み込みのプリセット（`common/aliases/*.json`、CLIとWebで同じファイル）に、
+//! 利用者・工事ごとのファイルを重ねて登録する。同じ名前は後から登録したものに置き換わる。
+
+use crate::alias::AliasConfig;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+/// 組み込みのプリセット（ファイル名, 内容）
+const BUILTIN: [(&str, &str); 3] = [
+    ("pavement", include_str!("../aliases/pavement.json")),
+    ("marking", include_str!("../aliases/marking.json")),
+    ("general", include_str!("../aliases/general.json")),
+];
+
+/// プリセットの読み込み元
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PresetSource {
+    #[default]
+    BuiltIn,
+    File(PathBuf),
+}
+
+impl fmt::Display for PresetSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetSource::BuiltIn => f.write_str("組み込み"),
+            PresetSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// エイリアスのプリセット1件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AliasPreset {
+    #[serde(default)]
+    pub name: String,
+    /// 別名（`--preset 舗装` など）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub names: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(flatten)]
+    pub config: AliasConfig,
+    #[serde(skip)]
+    pub source: PresetSource,
+}
+
+impl AliasPreset {
+    /// JSON文字列から読み込み（`name` がなければ `fallback_name`、不正な規則があればエラー）
+    pub fn from_json(json: &str, fallback_name: &str, source: PresetSource) -> Result<Self> {
+        let mut preset: Self = serde_json::from_str(json.trim_start_matches('\u{feff}'))?;
+        if let Some(error) = preset.config.errors().into_iter().next() {
+            return Err(error);
+        }
+        if preset.name.trim().is_empty() {
+            preset.name = fallback_name.to_string();
+        }
+        preset.source = source;
+        Ok(preset)
+    }
+
+    /// 名前・別名のどれかと一致するか（英字の大文字・小文字は区別しない）
+    pub fn is_named(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+        std::iter::once(&self.name).chain(&self.names).any(|n| n.to_lowercase() == name)
+    }
+}
+
+/// 名前で引けるプリセットの一覧
+#[derive(Debug, Clone, Default)]
+pub struct AliasRegistry {
+    presets: Vec<AliasPreset>,
+}
+
+impl AliasRegistry {
+    /// 組み込みのプリセット
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        for (name, json) in BUILTIN {
+            let preset = AliasPreset::from_json(json, name, PresetSource::BuiltIn)
+                .unwrap_or_else(|e| panic!("組み込みプリセット {} が不正です: {}", name, e));
+            registry.register(preset);
+        }
+        registry
+    }
+
+    /// 登録する（同じ名前のプリセットは置き換える）
+    pub fn register(&mut self, preset: AliasPreset) {
+        match self.presets.iter_mut().find(|p| p.name.eq_ignore_ascii_case(&preset.name)) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn presets(&self) -> &[AliasPreset] {
+        &self.presets
+    }
+
+    /// 名前・別名で検索
+    pub fn get(&self, name: &str) -> Option<&AliasPreset> {
+        self.presets.iter().find(|p| p.is_named(name))
+    }
+
+    /// 名前・別名で検索（見つからなければ登録済みの名前を添えたエラー）
+    pub fn find(&self, name: &str) -> Result<&AliasPreset> {
+        self.get(name).ok_or_else(|| Error::Config(self.unknown_message(name)))
+    }
+
+    fn unknown_message(&self, name: &str) -> String {
+        let names: Vec<&str> = self.presets.iter().map(|p| p.name.as_str()).collect();
+        format!("不明なエイリアスプリセット「{}」（{}）", name, names.join("/"))
+    }
+
+    /// 指定順に重ねた設定（後に指定したプリセットが優先）
+    pub fn stack<S: AsRef<str>>(&self, names: &[S]) -> Result<AliasConfig> {
+        let mut config = AliasConfig::default();
+        for name in names {
+            config.merge(&self.find(name.as_ref())?.config);
+        }
+        Ok(config)
+    }
+
+    /// 指定順に重ねた設定と、飛ばした不明な名前の警告（出力を止めないため）
+    pub fn stack_known<S: AsRef<str>>(&self, names: &[S]) -> (AliasConfig, Vec<String>) {
+        let mut config = AliasConfig::default();
+        let mut warnings = Vec::new();
+        for name in names {
+            match self.get(name.as_ref()) {
+                Some(preset) => config.merge(&preset.config),
+                None => warnings.push(self.unknown_message(name.as_ref())),
+            }
+        }
+        (config, warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AnalysisResult;
+
+    #[test]
+    fn test_builtin_presets_load() {
+        let registry = AliasRegistry::builtin();
+        let names: Vec<&str> = registry.presets().iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["pavement", "marking", "general"]);
+        assert_eq!(registry.get("舗装").unwrap().name, "pavement");
+        assert_eq!(registry.get("Marking").unwrap().config.work_type.get("白線").unwrap(), "区画線工");
+        assert!(registry.find("unknown").unwrap_err().to_string().contains("pavement/marking/general"));
+    }
+
+    #[test]
+    fn test_register_overrides_and_stack_order() {
+        let mut registry = AliasRegistry::builtin();
+        let project = AliasPreset::from_json(
+            r#"{ "description": "工事用", "photo_category": { "品質": "品質写真" } }"#,
+            "project",
+            PresetSource::File(PathBuf::from(".photo-ai/aliases/project.json")),
+        )
+        .unwrap();
+        assert_eq!(project.name, "project");
+        registry.register(project);
+        let user = AliasPreset::from_json(r#"{ "name": "GENERAL", "photo_category": { "完成": "竣工写真" } }"#, "x", PresetSource::BuiltIn).unwrap();
+        registry.register(user);
+        assert_eq!(registry.presets().len(), 4);
+        assert!(registry.get("汎用").is_none());
+
+        let result = AnalysisResult { photo_category: "品質".to_string(), ..Default::default() };
+        let later_wins = registry.stack(&["pavement", "project"]).unwrap();
+        assert_eq!(later_wins.apply(&result).photo_category, "品質写真");
+        let earlier = registry.stack(&["project", "pavement"]).unwrap();
+        assert_eq!(earlier.apply(&result).photo_category, "品質管理写真");
+        assert!(registry.stack(&["pavement", "nope"]).is_err());
+        let (skipped, warnings) = registry.stack_known(&["pavement", "nope"]);
+        assert_eq!(skipped.compile().len(), registry.stack(&["pavement"]).unwrap().compile().len());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("「nope」"));
+    }
+}
@@ -0,0 +1,132 @@
+//! 検索パターンによる候補の順位付けと備考候補
+//!
+//! 解析結果の文字（OCR・情景説明・写真種別）をマスタ各行の検索パターンと照合し、
+//! 一致したパターンの重みの合計で順位を付ける。上位の備考は `remarks_candidates` に加え、
+//! 確認時に1クリックで切り替えられるようにする（[`crate::switch_remarks`]）。
+
+use crate::hierarchy::{HierarchyMaster, HierarchyRow};
+use crate::pattern::{PatternMatch, PatternSet, PatternText};
+use crate::types::AnalysisResult;
+
+/// 備考候補の既定数
+pub const DEFAULT_CANDIDATES: usize = 3;
+
+/// 検索パターンを持つマスタ行（パターンは解釈済み）
+#[derive(Debug, Clone)]
+pub struct MatchEntry<'a> {
+    pub row: &'a HierarchyRow,
+    pub patterns: &'a PatternSet,
+}
+
+/// 順位付けした候補1件
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedRow<'a> {
+    pub row: &'a HierarchyRow,
+    /// 一致した検索パターンの重みの合計
+    pub score: u32,
+    /// 一致した検索パターン（元の表記）
+    pub hits: Vec<String>,
+}
+
+/// 検索パターンを持つ行を収集
+pub fn match_entries(master: &HierarchyMaster) -> Vec<MatchEntry<'_>> {
+    master
+        .rows_with_patterns()
+        .filter(|(_, patterns)| !patterns.is_empty())
+        .map(|(row, patterns)| MatchEntry { row, patterns })
+        .collect()
+}
+
+/// 解析結果と照合し、点数の高い順に最大 `limit` 件を返す（同点はマスタの行順）
+pub fn rank_entries<'a>(result: &AnalysisResult, entries: &[MatchEntry<'a>], limit: usize) -> Vec<RankedRow<'a>> {
+    // 検索対象テキスト（OCR + 説明文 + 写真区分、`ocr:`・`scene:` は各項目のみ）
+    let search_text = PatternText::from_result(result);
+
+    let mut ranked: Vec<RankedRow<'a>> = entries
+        .iter()
+        .filter_map(|entry| {
+            // 写真区分が一致するかチェック（部分一致）
+            let row = entry.row;
+            let category_match = result.photo_category.is_empty()
+                || row.photo_type.contains(&result.photo_category)
+                || result.photo_category.contains(&row.photo_type);
+            if !category_match {
+                return None;
+            }
+
+            // パターンマッチング（重みの合計、否定パターンが一致した行は除外）
+            let PatternMatch { score, hits } = entry.patterns.find(&search_text)?;
+            Some(RankedRow { row, score, hits })
+        })
+        .collect();
+
+    // 安定ソートなので同点はマスタの行順のまま
+    ranked.sort_by_key(|r| std::cmp::Reverse(r.score));
+    ranked.truncate(limit);
+    ranked
+}
+
+/// 上位 [`DEFAULT_CANDIDATES`] 件の備考を `remarks_candidates` の先頭に加える（現在の備考・重複は除く）
+pub fn add_remarks_candidates(result: &mut AnalysisResult, ranked: &[RankedRow<'_>]) {
+    let matched = ranked
+        .iter()
+        .map(|r| &r.row.remarks)
+        .filter(|remarks| **remarks != result.remarks)
+        .take(DEFAULT_CANDIDATES);
+    let mut candidates: Vec<String> = Vec::new();
+    for remarks in matched.chain(result.remarks_candidates.iter()) {
+        if !remarks.is_empty() && *remarks != result.remarks && !candidates.contains(remarks) {
+            candidates.push(remarks.clone());
+        }
+    }
+    result.remarks_candidates = candidates;
+}
+
+/// 検索パターンで照合した上位の備考を候補に加える（分類は変更しない）
+pub fn fill_remarks_candidates(results: &mut [AnalysisResult], master: &HierarchyMaster) {
+    let entries = match_entries(master);
+    for result in results.iter_mut() {
+        // 現在の備考が上位に入っても候補数が減らないよう1件多く取る
+        let ranked = rank_entries(result, &entries, DEFAULT_CANDIDATES + 1);
+        add_remarks_candidates(result, &ranked);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASTER_CSV: &str = "写真区分,写真種別,工種,種別,細別,備考,検索パターン
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,到着温度測定,温度
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,敷均し温度測定,温度|敷均し^2
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,初期締固め前温度測定,温度
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,出来形測定,
+直接工事費,施工状況写真,舗装工,舗装打換え工,表層工,舗設状況,温度
+";
+
+    #[test]
+    fn test_rank_and_fill_remarks_candidates() {
+        let master = HierarchyMaster::from_csv_str(MASTER_CSV).unwrap();
+        let entries = match_entries(&master);
+        assert_eq!(entries.len(), 4);
+
+        let mut result = AnalysisResult {
+            detected_text: "敷均し温度 145℃".to_string(),
+            photo_category: "品質管理写真".to_string(),
+            remarks: "敷均し温度測定".to_string(),
+            remarks_candidates: vec!["出来形測定".to_string(), "到着温度測定".to_string()],
+            ..Default::default()
+        };
+
+        // 点数の高い順、同点はマスタの行順（写真種別の違う行は除く）
+        let ranked = rank_entries(&result, &entries, 5);
+        let remarks: Vec<&str> = ranked.iter().map(|r| r.row.remarks.as_str()).collect();
+        assert_eq!(remarks, vec!["敷均し温度測定", "到着温度測定", "初期締固め前温度測定"]);
+        assert_eq!(ranked[0].score, 3);
+        assert_eq!(ranked[0].hits, vec!["温度", "敷均し^2"]);
+
+        // 現在の備考を除いて照合の候補を先頭に、AIの候補を重複なく後ろに
+        fill_remarks_candidates(std::slice::from_mut(&mut result), &master);
+        assert_eq!(result.remarks_candidates, vec!["到着温度測定", "初期締固め前温度測定", "出来形測定"]);
+    }
+}
@@ -0,0 +1,172 @@
+//! CSV読み込み（RFC 4180）
+//!
+//! Excel で保存した CSV を想定し、BOM 付き UTF-8 と Shift_JIS を受け付ける。
+//! 引用符内のカンマ・改行・`""` のエスケープに対応し、各レコードの開始行番号を保持する。
+
+/// CSVの1レコード
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvRecord {
+    /// 開始行番号（1始まり）
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+/// CSVの構文エラー
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvError {
+    /// 行番号（1始まり）
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for CsvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}行目: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// バイト列を文字列に変換（UTF-8 の BOM を除去、UTF-8 でなければ Shift_JIS とみなす）
+pub fn decode_text(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::SHIFT_JIS.decode(bytes).0.into_owned(),
+    }
+}
+
+/// CSV全体を解析
+///
+/// 空行は読み飛ばす。構文エラーのあるレコードは `Err` として位置を返し、続きの解析は継続する。
+pub fn parse(content: &str) -> Vec<Result<CsvRecord, CsvError>> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut records = Vec::new();
+    let mut chars = content.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start_line = line;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut quoted = false;
+        let mut error: Option<String> = None;
+
+        loop {
+            let Some(c) = chars.next() else {
+                if in_quotes {
+                    error.get_or_insert_with(|| "引用符が閉じていません".to_string());
+                }
+                break;
+            };
+            if in_quotes {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    '"' => in_quotes = false,
+                    '\n' => {
+                        line += 1;
+                        field.push('\n');
+                    }
+                    _ => field.push(c),
+                }
+                continue;
+            }
+            match c {
+                ',' => {
+                    fields.push(std::mem::take(&mut field));
+                    quoted = false;
+                }
+                '\r' if chars.peek() == Some(&'\n') => {}
+                '\n' | '\r' => {
+                    line += 1;
+                    break;
+                }
+                '"' if field.is_empty() && !quoted => {
+                    in_quotes = true;
+                    quoted = true;
+                }
+                '"' => {
+                    error.get_or_insert_with(|| format!("引用符の位置が不正です（{}列目）", fields.len() + 1));
+                    field.push(c);
+                }
+                _ if quoted && !c.is_whitespace() => {
+                    error.get_or_insert_with(|| format!("閉じ引用符の後に文字があります（{}列目）", fields.len() + 1));
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+        }
+        fields.push(field);
+
+        if fields.len() == 1 && fields[0].trim().is_empty() && error.is_none() {
+            continue;
+        }
+        records.push(match error {
+            Some(message) => Err(CsvError { line: start_line, message }),
+            None => Ok(CsvRecord { line: start_line, fields }),
+        });
+    }
+    records
+}
+
+/// CSVの1フィールドを書き出し用に整形
+///
+/// `always_quote` が偽なら、カンマ・引用符・改行を含む場合のみ引用符で囲む。
+pub fn escape_field(value: &str, always_quote: bool) -> String {
+    if always_quote || value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(content: &str) -> Vec<Vec<String>> {
+        parse(content).into_iter().map(|r| r.unwrap().fields).collect()
+    }
+
+    #[test]
+    fn test_parse_quotes_and_newlines() {
+        let csv = "a,\"b,c\",\"say \"\"hi\"\"\"\r\n\"multi\nline\",,x\n\n";
+        assert_eq!(
+            fields(csv),
+            vec![
+                vec!["a", "b,c", "say \"hi\""],
+                vec!["multi\nline", "", "x"],
+            ]
+        );
+        let lines: Vec<_> = parse(csv).into_iter().map(|r| r.unwrap().line).collect();
+        assert_eq!(lines, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_parse_errors_keep_line_numbers() {
+        let records = parse("ok,1\nbad\"quote,2\n\"open,3\n");
+        assert!(records[0].is_ok());
+        assert_eq!(records[1].as_ref().unwrap_err().line, 2);
+        assert_eq!(records[2].as_ref().unwrap_err().line, 3);
+        assert_eq!(parse("\"a\"b,1\n")[0].as_ref().unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_decode_bom_and_shift_jis() {
+        assert_eq!(decode_text("\u{feff}工種".as_bytes()), "工種");
+        let (sjis, _, _) = encoding_rs::SHIFT_JIS.encode("工種,舗装工");
+        assert_eq!(decode_text(&sjis), "工種,舗装工");
+    }
+
+    #[test]
+    fn test_escape_field() {
+        assert_eq!(escape_field("舗装工", false), "舗装工");
+        assert_eq!(escape_field("舗装工", true), "\"舗装工\"");
+        assert_eq!(escape_field("a,b", false), "\"a,b\"");
+        assert_eq!(escape_field("5\"", false), "\"5\"\"\"");
+    }
+}
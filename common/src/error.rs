@@ -16,6 +16,9 @@ pub enum Error {
 
     #[error("Parse error: {0}")]
     Parse(String),
+
+    #[error("Master error: {0}")]
+    Master(String),
 }
 
 /// Result型エイリアス
@@ -66,13 +66,19 @@ pub struct PdfInfoField {
     pub label: &'static str,
     pub value: String,
     pub row_span: u8,
+    /// 規格外などで強調表示する
+    pub alert: bool,
 }
 
+/// 規格（qc）を満たさない計測値の前に付ける目印
+pub const QC_FAILURE_MARK: &str = "【規格外】";
+
 /// 情報欄フィールドを構築
 pub fn build_pdf_info_fields(result: &AnalysisResult) -> Vec<PdfInfoField> {
     LAYOUT_FIELDS
         .iter()
         .map(|field| {
+            let mut alert = false;
             let value = match field.key {
                 "date" => format_date(&result.date),
                 "remarks" => {
@@ -81,7 +87,13 @@ pub fn build_pdf_info_fields(result: &AnalysisResult) -> Vec<PdfInfoField> {
                 }
                 "measurements" => {
                     // 測定値は別行として表示
-                    if result.measurements.is_empty() { "-".to_string() } else { result.measurements.clone() }
+                    let value = if result.measurements.is_empty() { "-".to_string() } else { result.measurements.clone() };
+                    if result.qc_failures.is_empty() {
+                        value
+                    } else {
+                        alert = true;
+                        format!("{}{}", QC_FAILURE_MARK, value)
+                    }
                 }
                 _ => {
                     let raw = get_field_value(result, field.key);
@@ -92,6 +104,7 @@ pub fn build_pdf_info_fields(result: &AnalysisResult) -> Vec<PdfInfoField> {
                 label: field.label,
                 value,
                 row_span: field.row_span,
+                alert,
             }
         })
         .collect()
@@ -159,5 +172,20 @@ mod tests {
         assert_eq!(fields.len(), LAYOUT_FIELDS.len());
         assert_eq!(fields[0].label, "日時");
         assert_eq!(fields[0].value, "-");
+        assert!(fields.iter().all(|f| !f.alert));
+    }
+
+    #[test]
+    fn test_build_info_fields_marks_qc_failures() {
+        let result = AnalysisResult {
+            measurements: "145℃".to_string(),
+            qc_failures: vec!["到着温度: 145℃（規格 150℃以上）".to_string()],
+            ..Default::default()
+        };
+        let fields = build_pdf_info_fields(&result);
+        let measurements = fields.iter().find(|f| f.label == "測定値").unwrap();
+        assert!(measurements.alert);
+        assert_eq!(measurements.value, "【規格外】145℃");
+        assert_eq!(fields.iter().filter(|f| f.alert).count(), 1);
     }
 }
@@ -1,14 +1,18 @@
 //! 工種階層マスタモジュール
 //!
 //! 工事写真の分類に使用する階層マスタデータを管理する。
-//! CSVから読み込み、Step2のAI解析でマスタ照合を行う。
+//! CSV・Excel・JSONから読み込み（[`crate::master_format`]）、Step2のAI解析でマスタ照合を行う。
 
+use crate::csv;
+use crate::pattern::{self, PatternSet, PatternText};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::OnceLock;
 
-/// CSVの1行を表す構造体
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// マスタの1行を表す構造体
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct HierarchyRow {
     /// 写真区分（直接工事費など）
     pub photo_division: String,
@@ -26,6 +30,13 @@ pub struct HierarchyRow {
     pub search_patterns: String,
 }
 
+impl HierarchyRow {
+    /// 検索パターンを解釈する
+    pub fn patterns(&self) -> PatternSet {
+        PatternSet::parse(&self.search_patterns)
+    }
+}
+
 /// 階層マスタ全体を管理する構造体
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct HierarchyMaster {
@@ -37,40 +48,46 @@ pub struct HierarchyMaster {
     work_type_to_varieties: HashMap<String, HashSet<String>>,
     /// (工種, 種別)→作業段階のマッピング
     variety_to_subphases: HashMap<(String, String), HashSet<String>>,
+    /// 行ごとの解釈済み検索パターン（初回の照合時に1度だけ解釈する）
+    #[serde(skip)]
+    patterns: OnceLock<Vec<PatternSet>>,
 }
 
 impl HierarchyMaster {
-    /// CSVファイルから読み込み
+    /// CSVファイルから読み込み（BOM付きUTF-8・Shift_JIS対応）
     pub fn from_csv(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
-        let content = std::fs::read_to_string(path)?;
-        Self::from_csv_str(&content)
+        let bytes = std::fs::read(path)?;
+        Self::from_csv_str(&csv::decode_text(&bytes))
     }
 
     /// CSV文字列から読み込み
+    ///
+    /// 列はヘッダー名で対応付ける。不正な行が1つでもあればエラー（内容は [`Self::load_csv_str`] で確認できる）
     pub fn from_csv_str(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut rows = Vec::new();
+        Ok(Self::load_csv_str(content)?.into_master()?)
+    }
+
+    /// CSV文字列を読み込み、行ごとの問題も返す
+    ///
+    /// 不正な行は読み飛ばし、重複・階層の欠けは警告として報告したうえで読み込む。
+    /// ヘッダーがない・必須列（工種・種別・細別）がない場合はエラー
+    pub fn load_csv_str(content: &str) -> crate::Result<MasterLoad> {
+        let mut records = csv::parse(content).into_iter();
+        let header = match records.next() {
+            Some(Ok(header)) => header,
+            Some(Err(e)) => return Err(crate::Error::Parse(format!("マスタCSVのヘッダーが不正: {}", e))),
+            None => return Err(crate::Error::Parse("マスタCSVが空です".to_string())),
+        };
+        load_table(&header, records)
+    }
+
+    /// 行データから構築
+    pub fn from_rows(rows: Vec<HierarchyRow>) -> Self {
         let mut work_types = HashSet::new();
         let mut work_type_to_varieties: HashMap<String, HashSet<String>> = HashMap::new();
         let mut variety_to_subphases: HashMap<(String, String), HashSet<String>> = HashMap::new();
 
-        // ヘッダーをスキップ
-        for line in content.lines().skip(1) {
-            let fields: Vec<&str> = parse_csv_line(line);
-            if fields.len() < 7 {
-                continue;
-            }
-
-            let row = HierarchyRow {
-                photo_division: fields[0].to_string(),
-                photo_type: fields[1].to_string(),
-                work_type: fields[2].to_string(),
-                variety: fields[3].to_string(),
-                subphase: fields[4].to_string(),
-                remarks: fields[5].to_string(),
-                search_patterns: fields[6].to_string(),
-            };
-
-            // インデックス構築
+        for row in &rows {
             if !row.work_type.is_empty() {
                 work_types.insert(row.work_type.clone());
 
@@ -88,16 +105,15 @@ impl HierarchyMaster {
                     }
                 }
             }
-
-            rows.push(row);
         }
 
-        Ok(Self {
+        Self {
             rows,
             work_types,
             work_type_to_varieties,
             variety_to_subphases,
-        })
+            patterns: OnceLock::new(),
+        }
     }
 
     /// 工種一覧を取得
@@ -184,21 +200,21 @@ impl HierarchyMaster {
         v
     }
 
-    /// 検索パターンでマッチする行を検索
+    /// 検索パターンでマッチする行を検索（否定パターンが一致した行は除く、構文は [`crate::pattern`]）
     pub fn find_by_pattern(&self, text: &str) -> Vec<&HierarchyRow> {
-        self.rows
-            .iter()
-            .filter(|row| {
-                if row.search_patterns.is_empty() {
-                    return false;
-                }
-                row.search_patterns
-                    .split('|')
-                    .any(|pattern| text.contains(pattern))
-            })
+        let text = PatternText::plain(text);
+        self.rows_with_patterns()
+            .filter(|(_, patterns)| patterns.find(&text).is_some())
+            .map(|(row, _)| row)
             .collect()
     }
 
+    /// 全行を解釈済みの検索パターンとともに行順で返す
+    pub fn rows_with_patterns(&self) -> impl Iterator<Item = (&HierarchyRow, &PatternSet)> {
+        let patterns = self.patterns.get_or_init(|| self.rows.iter().map(HierarchyRow::patterns).collect());
+        self.rows.iter().zip(patterns)
+    }
+
     /// 全行を取得
     pub fn rows(&self) -> &[HierarchyRow] {
         &self.rows
@@ -216,36 +232,7 @@ impl HierarchyMaster {
             .cloned()
             .collect();
 
-        let mut work_types_set = HashSet::new();
-        let mut work_type_to_varieties: HashMap<String, HashSet<String>> = HashMap::new();
-        let mut variety_to_subphases: HashMap<(String, String), HashSet<String>> = HashMap::new();
-
-        for row in &filtered_rows {
-            if !row.work_type.is_empty() {
-                work_types_set.insert(row.work_type.clone());
-
-                if !row.variety.is_empty() {
-                    work_type_to_varieties
-                        .entry(row.work_type.clone())
-                        .or_default()
-                        .insert(row.variety.clone());
-
-                    if !row.subphase.is_empty() {
-                        variety_to_subphases
-                            .entry((row.work_type.clone(), row.variety.clone()))
-                            .or_default()
-                            .insert(row.subphase.clone());
-                    }
-                }
-            }
-        }
-
-        Self {
-            rows: filtered_rows,
-            work_types: work_types_set,
-            work_type_to_varieties,
-            variety_to_subphases,
-        }
+        Self::from_rows(filtered_rows)
     }
 
     /// 指定した工種・種別のみに絞ったマスタを返す
@@ -264,85 +251,279 @@ impl HierarchyMaster {
             .cloned()
             .collect();
 
-        let mut work_types_set = HashSet::new();
-        let mut work_type_to_varieties: HashMap<String, HashSet<String>> = HashMap::new();
-        let mut variety_to_subphases: HashMap<(String, String), HashSet<String>> = HashMap::new();
+        Self::from_rows(filtered_rows)
+    }
+}
 
-        for row in &filtered_rows {
-            if !row.work_type.is_empty() {
-                work_types_set.insert(row.work_type.clone());
+/// 表形式（CSV・Excel）のマスタを読み込む
+///
+/// 列はヘッダー名で対応付け、ヘッダーと列数が合わない行は読み飛ばして報告する
+pub(crate) fn load_table(
+    header: &csv::CsvRecord,
+    records: impl Iterator<Item = Result<csv::CsvRecord, csv::CsvError>>,
+) -> crate::Result<MasterLoad> {
+    let mut issues = Vec::new();
+    let columns = ColumnMap::from_header(header, &mut issues)?;
+
+    let mut rows = Vec::new();
+    for record in records {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                issues.push(MasterIssue::new(e.line, MasterIssueKind::Malformed, e.message));
+                continue;
+            }
+        };
+        let extra_filled = record.fields.iter().skip(header.fields.len()).any(|f| !f.trim().is_empty());
+        if record.fields.len() < header.fields.len() || extra_filled {
+            issues.push(MasterIssue::new(
+                record.line,
+                MasterIssueKind::Malformed,
+                format!("列数が{}（ヘッダーは{}列）", record.fields.len(), header.fields.len()),
+            ));
+            continue;
+        }
+        rows.push((record.line, columns.row(&record.fields)));
+    }
+    Ok(check_rows(rows, issues))
+}
 
-                if !row.variety.is_empty() {
-                    work_type_to_varieties
-                        .entry(row.work_type.clone())
-                        .or_default()
-                        .insert(row.variety.clone());
+/// 行ごとの検査（階層の欠け・重複）を行い、読み込み結果にまとめる
+pub(crate) fn check_rows(rows: Vec<(usize, HierarchyRow)>, mut issues: Vec<MasterIssue>) -> MasterLoad {
+    let mut seen: HashMap<[&str; 6], usize> = HashMap::new();
+    for (line, row) in &rows {
+        check_levels(row, *line, &mut issues);
+        for error in PatternSet::errors(&row.search_patterns) {
+            issues.push(MasterIssue::new(*line, MasterIssueKind::InvalidPattern, error.to_string()));
+        }
 
-                    if !row.subphase.is_empty() {
-                        variety_to_subphases
-                            .entry((row.work_type.clone(), row.variety.clone()))
-                            .or_default()
-                            .insert(row.subphase.clone());
-                    }
-                }
+        let key = [
+            row.photo_division.as_str(),
+            row.photo_type.as_str(),
+            row.work_type.as_str(),
+            row.variety.as_str(),
+            row.subphase.as_str(),
+            row.remarks.as_str(),
+        ];
+        match seen.get(&key) {
+            Some(first) => issues.push(MasterIssue::new(
+                *line,
+                MasterIssueKind::Duplicate,
+                format!("{}行目と重複", first),
+            )),
+            None => {
+                seen.insert(key, *line);
             }
         }
+    }
 
-        Self {
-            rows: filtered_rows,
-            work_types: work_types_set,
-            work_type_to_varieties,
-            variety_to_subphases,
+    issues.sort_by_key(|issue| issue.line);
+    MasterLoad {
+        master: HierarchyMaster::from_rows(rows.into_iter().map(|(_, row)| row).collect()),
+        issues,
+    }
+}
+
+/// 列名と別名（先頭が正式名）
+const COLUMNS: [(&str, &[&str]); 7] = [
+    ("写真区分", &[]),
+    ("写真種別", &[]),
+    ("工種", &[]),
+    ("種別", &[]),
+    ("細別", &["作業段階"]),
+    ("備考", &["撮影内容"]),
+    ("検索パターン", &["検索キーワード", "matchPatterns", "マッチパターン", "パターン"]),
+];
+
+/// 必須列（`COLUMNS` の位置）
+const REQUIRED_COLUMNS: [usize; 3] = [2, 3, 4];
+
+/// 旧Excel形式（写真種別の列がない）の検索パターン列の名前
+const LEGACY_PATTERN_COLUMNS: [&str; 3] = ["matchPatterns", "マッチパターン", "パターン"];
+
+/// ヘッダー名 → 列位置
+struct ColumnMap {
+    /// `COLUMNS` の順に、CSV上の列位置
+    positions: [Option<usize>; 7],
+}
+
+impl ColumnMap {
+    fn from_header(header: &csv::CsvRecord, issues: &mut Vec<MasterIssue>) -> crate::Result<Self> {
+        let mut positions = [None; 7];
+        for (index, name) in header.fields.iter().enumerate() {
+            let name = name.trim();
+            match COLUMNS.iter().position(|(col, aliases)| *col == name || aliases.contains(&name)) {
+                Some(col) if positions[col].is_none() => positions[col] = Some(index),
+                Some(col) => issues.push(MasterIssue::new(
+                    header.line,
+                    MasterIssueKind::UnknownColumn,
+                    format!("列「{}」が重複（{}列目は無視）", COLUMNS[col].0, index + 1),
+                )),
+                None if name.is_empty() => {}
+                None => issues.push(MasterIssue::new(
+                    header.line,
+                    MasterIssueKind::UnknownColumn,
+                    format!("不明な列「{}」（{}列目は無視）", name, index + 1),
+                )),
+            }
+        }
+
+        // 旧Excel形式（写真種別の列がなく、パターン列が matchPatterns 等）では、
+        // 写真区分の列に写真種別（品質管理写真等）が入っている
+        let legacy_patterns = positions[6]
+            .and_then(|i| header.fields.get(i))
+            .is_some_and(|name| LEGACY_PATTERN_COLUMNS.contains(&name.trim()));
+        if positions[1].is_none() && positions[0].is_some() && legacy_patterns {
+            positions.swap(0, 1);
+            issues.push(MasterIssue::new(
+                header.line,
+                MasterIssueKind::LegacyLayout,
+                "写真種別の列がない旧形式のため、写真区分の列を写真種別として読み込み".to_string(),
+            ));
+        }
+
+        let missing: Vec<&str> = REQUIRED_COLUMNS
+            .iter()
+            .filter(|&&col| positions[col].is_none())
+            .map(|&col| COLUMNS[col].0)
+            .collect();
+        if !missing.is_empty() {
+            return Err(crate::Error::Parse(format!(
+                "マスタに必須列がありません: {}（ヘッダー: {}）",
+                missing.join(", "),
+                header.fields.join(",")
+            )));
+        }
+        Ok(Self { positions })
+    }
+
+    fn row(&self, fields: &[String]) -> HierarchyRow {
+        let get = |col: usize| {
+            self.positions[col]
+                .and_then(|i| fields.get(i))
+                .map(|f| f.trim().to_string())
+                .unwrap_or_default()
+        };
+        HierarchyRow {
+            photo_division: get(0),
+            photo_type: get(1),
+            work_type: get(2),
+            variety: get(3),
+            subphase: get(4),
+            remarks: get(5),
+            search_patterns: normalize_patterns(&get(6)),
         }
     }
 }
 
-/// CSV行をパース（ダブルクォート対応）
-fn parse_csv_line(line: &str) -> Vec<&str> {
-    let mut fields = Vec::new();
-    let mut in_quotes = false;
-    let mut field_start = 0;
-    let chars: Vec<char> = line.chars().collect();
-    let mut i = 0;
-
-    while i < chars.len() {
-        let c = chars[i];
-        if c == '"' {
-            in_quotes = !in_quotes;
-        } else if c == ',' && !in_quotes {
-            // フィールド終了
-            let field = &line[field_start..byte_index(line, i)];
-            fields.push(trim_quotes(field));
-            field_start = byte_index(line, i + 1);
+/// 検索パターンの区切りを `|` に揃える（旧Excel形式はカンマ区切り）
+pub(crate) fn normalize_patterns(patterns: &str) -> String {
+    pattern::split_patterns(patterns).join("|")
+}
+
+/// 階層の欠け（上位が空で下位が入力されている）を検査
+fn check_levels(row: &HierarchyRow, line: usize, issues: &mut Vec<MasterIssue>) {
+    let levels = [
+        ("工種", &row.work_type),
+        ("種別", &row.variety),
+        ("細別", &row.subphase),
+        ("備考", &row.remarks),
+    ];
+    if levels.iter().all(|(_, value)| value.is_empty()) {
+        issues.push(MasterIssue::new(line, MasterIssueKind::EmptyLevel, "工種が空です".to_string()));
+        return;
+    }
+    if row.work_type.is_empty() && !row.variety.is_empty() {
+        issues.push(MasterIssue::new(
+            line,
+            MasterIssueKind::OrphanVariety,
+            format!("種別「{}」に工種がありません", row.variety),
+        ));
+        return;
+    }
+    for (i, (upper, value)) in levels.iter().enumerate() {
+        if !value.is_empty() {
+            continue;
+        }
+        if let Some((lower, _)) = levels[i + 1..].iter().find(|(_, v)| !v.is_empty()) {
+            issues.push(MasterIssue::new(
+                line,
+                MasterIssueKind::EmptyLevel,
+                format!("{}が空で{}が入力されています", upper, lower),
+            ));
+            return;
         }
-        i += 1;
     }
+}
+
+/// マスタの問題の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MasterIssueKind {
+    /// 構文エラー・列数不一致（読み込まない）
+    Malformed,
+    /// 同じ分類の行が既にある
+    Duplicate,
+    /// 階層の途中が空
+    EmptyLevel,
+    /// 工種のない種別
+    OrphanVariety,
+    /// 不明・重複した列
+    UnknownColumn,
+    /// 解釈できない検索パターン（照合では無視）
+    InvalidPattern,
+    /// 旧形式の列の並びとして読み替えた
+    LegacyLayout,
+}
 
-    // 最後のフィールド
-    if field_start <= line.len() {
-        let field = &line[field_start..];
-        fields.push(trim_quotes(field));
+impl MasterIssueKind {
+    /// 表示名
+    pub fn label(&self) -> &'static str {
+        match self {
+            MasterIssueKind::Malformed => "不正な行",
+            MasterIssueKind::Duplicate => "重複",
+            MasterIssueKind::EmptyLevel => "階層の欠け",
+            MasterIssueKind::OrphanVariety => "工種のない種別",
+            MasterIssueKind::UnknownColumn => "不明な列",
+            MasterIssueKind::InvalidPattern => "不正な検索パターン",
+            MasterIssueKind::LegacyLayout => "旧形式",
+        }
     }
+}
 
-    fields
+/// マスタの問題1件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MasterIssue {
+    /// 行番号（1始まり、ヘッダーが1行目。JSONは先頭からの件数）
+    pub line: usize,
+    pub kind: MasterIssueKind,
+    pub message: String,
 }
 
-fn byte_index(s: &str, char_index: usize) -> usize {
-    s.char_indices()
-        .nth(char_index)
-        .map(|(i, _)| i)
-        .unwrap_or(s.len())
+impl MasterIssue {
+    pub(crate) fn new(line: usize, kind: MasterIssueKind, message: String) -> Self {
+        Self { line, kind, message }
+    }
+
+    /// 読み込めない行か（それ以外は警告）
+    pub fn is_error(&self) -> bool {
+        self.kind == MasterIssueKind::Malformed
+    }
 }
 
-fn trim_quotes(s: &str) -> &str {
-    let s = s.trim();
-    if s.starts_with('"') && s.ends_with('"') && s.len() >= 2 {
-        &s[1..s.len() - 1]
-    } else {
-        s
+impl std::fmt::Display for MasterIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}行目: [{}] {}", self.line, self.kind.label(), self.message)
     }
 }
 
+/// マスタ読み込み結果（CSV・Excel・JSON共通）
+#[derive(Debug, Clone)]
+pub struct MasterLoad {
+    pub master: HierarchyMaster,
+    /// 行番号順の問題一覧
+    pub issues: Vec<MasterIssue>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -396,4 +577,71 @@ mod tests {
         assert!(json.is_object());
         assert!(json.get("舗装工").is_some());
     }
+
+    #[test]
+    fn test_columns_mapped_by_header_name() {
+        let csv = "\u{feff}工種,備考,種別,細別,写真種別\n\"舗装工\",\"舗設状況, 全景\",舗装打換え工,表層工,施工状況写真\n";
+        let master = HierarchyMaster::from_csv_str(csv).unwrap();
+        let row = &master.rows()[0];
+        assert_eq!(row.work_type, "舗装工");
+        assert_eq!(row.remarks, "舗設状況, 全景");
+        assert_eq!(row.photo_type, "施工状況写真");
+        assert_eq!(row.photo_division, "");
+
+        assert!(HierarchyMaster::from_csv_str("工種,種別\n舗装工,表層工\n").is_err());
+
+        // 写真種別の列がないだけでは写真区分を読み替えない
+        let loaded = HierarchyMaster::load_csv_str("写真区分,工種,種別,細別,備考,検索パターン\n直接工事費,舗装工,舗装打換え工,表層工,舗設状況,\n").unwrap();
+        assert!(loaded.issues.is_empty());
+        assert_eq!(loaded.master.rows()[0].photo_division, "直接工事費");
+        assert_eq!(loaded.master.rows()[0].photo_type, "");
+
+        // 旧形式のヘッダーなら読み替えて報告する
+        let loaded = HierarchyMaster::load_csv_str("写真区分,工種,種別,細別,備考,matchPatterns\n品質管理写真,舗装工,舗装打換え工,表層工,温度測定,\n").unwrap();
+        assert_eq!(loaded.issues[0].kind, MasterIssueKind::LegacyLayout);
+        assert_eq!(loaded.master.rows()[0].photo_type, "品質管理写真");
+    }
+
+    #[test]
+    fn test_shift_jis_file() {
+        let dir = std::env::temp_dir().join(format!("hierarchy-sjis-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("master.csv");
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode(TEST_CSV);
+        std::fs::write(&path, &bytes).unwrap();
+        let master = HierarchyMaster::from_csv(&path).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(master.get_subphases("区画線工", "区画線工"), vec!["溶融式区画線"]);
+    }
+
+    #[test]
+    fn test_validation_issues() {
+        let csv = "写真区分,写真種別,工種,種別,細別,備考,検索パターン,メモ
+直接工事費,施工状況写真,舗装工,舗装打換え工,表層工,舗設状況,,
+直接工事費,施工状況写真,舗装工,舗装打換え工,表層工,舗設状況,,
+直接工事費,施工状況写真,舗装工
+直接工事費,施工状況写真,,区画線工,溶融式,,,
+直接工事費,施工状況写真,舗装工,,表層工,,,
+直接工事費,施工状況写真,,,,,,
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,到着温度,到着温度|re:(温度,
+";
+        let loaded = HierarchyMaster::load_csv_str(csv).unwrap();
+        let found: Vec<(usize, MasterIssueKind)> = loaded.issues.iter().map(|i| (i.line, i.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, MasterIssueKind::UnknownColumn),
+                (3, MasterIssueKind::Duplicate),
+                (4, MasterIssueKind::Malformed),
+                (5, MasterIssueKind::OrphanVariety),
+                (6, MasterIssueKind::EmptyLevel),
+                (7, MasterIssueKind::EmptyLevel),
+                (8, MasterIssueKind::InvalidPattern),
+            ]
+        );
+        assert_eq!(loaded.issues[1].message, "2行目と重複");
+        // 不正な行以外は読み込む
+        assert_eq!(loaded.master.rows().len(), 6);
+        assert!(HierarchyMaster::from_csv_str(csv).unwrap_err().to_string().contains("4行目"));
+    }
 }
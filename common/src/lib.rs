@@ -9,8 +9,18 @@
 pub mod types;
 pub mod layout;
 pub mod alias;
+pub mod alias_preset;
 pub mod error;
 pub mod hierarchy;
+pub mod csv;
+pub mod master_format;
+pub mod master_diff;
+pub mod master_edit;
+pub mod master_overlay;
+pub mod validation;
+pub mod pattern;
+pub mod candidates;
+pub mod station;
 pub mod parser;
 pub mod analyzer;
 pub mod prompts;
@@ -18,12 +28,21 @@ pub mod step2;
 #[cfg(feature = "excel")]
 pub mod export;
 
-pub use types::{AnalysisResult, RawImageData};
+pub use types::{AnalysisResult, ExifMetadata, FolderHints, RawImageData};
 pub use layout::{PdfLayout, ExcelLayout};
-pub use alias::{AliasConfig, apply_aliases};
+pub use alias::{AliasConfig, AliasField, AliasHit, AliasMatcher, AliasReport, AliasRule, AliasRules, Conditions, apply_aliases};
+pub use alias_preset::{AliasPreset, AliasRegistry, PresetSource};
 pub use error::{Error, Result};
-pub use hierarchy::{HierarchyMaster, HierarchyRow};
+pub use hierarchy::{HierarchyMaster, HierarchyRow, MasterLoad, MasterIssue, MasterIssueKind};
+pub use master_format::MasterFormat;
+pub use master_diff::MasterDiff;
+pub use master_edit::{NodePath, TreeNode};
+pub use master_overlay::MasterOverlay;
+pub use validation::{ChainSuggestion, ClassificationChain, ValidationIssue, ValidationIssueKind, chain_for_remarks, correct_results, switch_remarks, validate_chain, validate_result};
+pub use pattern::{PatternMatch, PatternSet, PatternText, SearchPattern};
+pub use candidates::{RankedRow, fill_remarks_candidates};
+pub use station::{Station, StationRange, canonical_station};
 pub use parser::{extract_json, parse_step1_response, parse_single_step_response};
 pub use analyzer::detect_work_types;
-pub use prompts::{PHOTO_CATEGORIES, build_step1_prompt, build_single_step_prompt};
+pub use prompts::{PHOTO_CATEGORIES, build_step1_prompt, build_single_step_prompt, build_folder_hints_section};
 pub use step2::{Step2Result, build_step2_prompt, parse_step2_response, merge_results, ImageMeta};
@@ -0,0 +1,216 @@
+//! 工種マスタの差分・分割・統合
+//!
+//! 行は (工種, 種別, 細別, 備考) のチェーンで同一視する。
+//! 写真区分・写真種別・検索パターンが異なる行は「変更」として扱う。
+
+use crate::hierarchy::{HierarchyMaster, HierarchyRow};
+use std::collections::{HashMap, HashSet};
+
+/// 行を同一視するキー（工種, 種別, 細別, 備考）
+pub type ChainKey = (String, String, String, String);
+
+/// 行のチェーンキー
+pub fn chain_key(row: &HierarchyRow) -> ChainKey {
+    (
+        row.work_type.clone(),
+        row.variety.clone(),
+        row.subphase.clone(),
+        row.remarks.clone(),
+    )
+}
+
+/// チェーンの表示（空の階層は省略）
+pub fn chain_label(row: &HierarchyRow) -> String {
+    [&row.work_type, &row.variety, &row.subphase, &row.remarks]
+        .iter()
+        .filter(|s| !s.is_empty())
+        .map(|s| s.as_str())
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+/// 同じチェーンで内容が異なる行
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedRow {
+    pub before: HierarchyRow,
+    pub after: HierarchyRow,
+    /// 変更された列（表示名）
+    pub fields: Vec<&'static str>,
+}
+
+/// 2つのマスタの差分
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MasterDiff {
+    pub added: Vec<HierarchyRow>,
+    pub removed: Vec<HierarchyRow>,
+    pub changed: Vec<ChangedRow>,
+}
+
+impl MasterDiff {
+    /// 差分がないか
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// 差分を取る（`before` → `after`、順序は各マスタの行順）
+pub fn diff(before: &HierarchyMaster, after: &HierarchyMaster) -> MasterDiff {
+    let before_rows: HashMap<ChainKey, &HierarchyRow> =
+        before.rows().iter().map(|row| (chain_key(row), row)).collect();
+    let after_keys: HashSet<ChainKey> = after.rows().iter().map(chain_key).collect();
+
+    let mut result = MasterDiff::default();
+    for row in after.rows() {
+        match before_rows.get(&chain_key(row)) {
+            None => result.added.push(row.clone()),
+            Some(old) => {
+                let fields = changed_fields(old, row);
+                if !fields.is_empty() {
+                    result.changed.push(ChangedRow {
+                        before: (*old).clone(),
+                        after: row.clone(),
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+    result.removed = before
+        .rows()
+        .iter()
+        .filter(|row| !after_keys.contains(&chain_key(row)))
+        .cloned()
+        .collect();
+    result
+}
+
+fn changed_fields(before: &HierarchyRow, after: &HierarchyRow) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if before.photo_division != after.photo_division {
+        fields.push("写真区分");
+    }
+    if before.photo_type != after.photo_type {
+        fields.push("写真種別");
+    }
+    if before.search_patterns != after.search_patterns {
+        fields.push("検索パターン");
+    }
+    fields
+}
+
+/// 工種ごとに分割（工種の初出順、工種が空の行は含まない）
+pub fn split_by_work_type(master: &HierarchyMaster) -> Vec<(String, HierarchyMaster)> {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, Vec<HierarchyRow>> = HashMap::new();
+    for row in master.rows().iter().filter(|row| !row.work_type.is_empty()) {
+        if !groups.contains_key(&row.work_type) {
+            order.push(row.work_type.clone());
+        }
+        groups.entry(row.work_type.clone()).or_default().push(row.clone());
+    }
+    order
+        .into_iter()
+        .map(|work_type| {
+            let rows = groups.remove(&work_type).unwrap_or_default();
+            (work_type, HierarchyMaster::from_rows(rows))
+        })
+        .collect()
+}
+
+/// 工種別マスタを統合マスタに書き戻す
+///
+/// 分割に含まれる工種の行は、統合マスタ上の最初の位置で分割側の行に置き換える。
+/// 分割にない工種（工種が空の行を含む）は統合マスタの行をそのまま残し、
+/// 統合マスタにない工種は末尾に追加する
+pub fn merge_splits(base: &HierarchyMaster, splits: &[HierarchyMaster]) -> HierarchyMaster {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, Vec<HierarchyRow>> = HashMap::new();
+    for row in splits.iter().flat_map(|split| split.rows()) {
+        if !groups.contains_key(&row.work_type) {
+            order.push(row.work_type.clone());
+        }
+        groups.entry(row.work_type.clone()).or_default().push(row.clone());
+    }
+
+    let mut rows = Vec::new();
+    for row in base.rows() {
+        if !groups.contains_key(&row.work_type) {
+            rows.push(row.clone());
+        } else if let Some(split_rows) = groups.get_mut(&row.work_type) {
+            // 最初の出現位置で工種ごと差し替え（2行目以降は空になっている）
+            rows.append(split_rows);
+        }
+    }
+    for work_type in order {
+        if let Some(split_rows) = groups.get_mut(&work_type) {
+            rows.append(split_rows);
+        }
+    }
+    HierarchyMaster::from_rows(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(photo_type: &str, work_type: &str, variety: &str, remarks: &str, patterns: &str) -> HierarchyRow {
+        HierarchyRow {
+            photo_division: "直接工事費".to_string(),
+            photo_type: photo_type.to_string(),
+            work_type: work_type.to_string(),
+            variety: variety.to_string(),
+            subphase: "表層工".to_string(),
+            remarks: remarks.to_string(),
+            search_patterns: patterns.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_diff_added_removed_changed() {
+        let before = HierarchyMaster::from_rows(vec![
+            row("品質管理写真", "舗装工", "舗装打換え工", "到着温度", "到着温度"),
+            row("施工状況写真", "舗装工", "舗装打換え工", "舗設状況", ""),
+        ]);
+        let after = HierarchyMaster::from_rows(vec![
+            row("品質管理写真", "舗装工", "舗装打換え工", "到着温度", "到着温度|出荷温度"),
+            row("施工状況写真", "区画線工", "区画線工", "清掃状況", ""),
+        ]);
+
+        let d = diff(&before, &after);
+        assert_eq!(d.added.len(), 1);
+        assert_eq!(chain_label(&d.added[0]), "区画線工 > 区画線工 > 表層工 > 清掃状況");
+        assert_eq!(d.removed.len(), 1);
+        assert_eq!(d.removed[0].remarks, "舗設状況");
+        assert_eq!(d.changed.len(), 1);
+        assert_eq!(d.changed[0].fields, vec!["検索パターン"]);
+        assert!(diff(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn test_split_and_merge_round_trip() {
+        let combined = HierarchyMaster::from_rows(vec![
+            row("施工状況写真", "舗装工", "舗装打換え工", "舗設状況", ""),
+            row("安全管理写真", "", "", "", ""),
+            row("施工状況写真", "区画線工", "区画線工", "清掃状況", ""),
+            row("品質管理写真", "舗装工", "舗装打換え工", "到着温度", "到着温度"),
+        ]);
+
+        let splits = split_by_work_type(&combined);
+        let names: Vec<&str> = splits.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["舗装工", "区画線工"]);
+        assert_eq!(splits[0].1.rows().len(), 2);
+
+        // 舗装工の分割にパターンを追加し、道路土工を新設して書き戻す
+        let mut paving = splits[0].1.rows().to_vec();
+        paving[1].search_patterns = "到着温度|出荷温度".to_string();
+        let earthwork = HierarchyMaster::from_rows(vec![row("施工状況写真", "道路土工", "掘削工", "掘削状況", "")]);
+        let merged = merge_splits(
+            &combined,
+            &[HierarchyMaster::from_rows(paving), splits[1].1.clone(), earthwork],
+        );
+
+        let work_types: Vec<&str> = merged.rows().iter().map(|r| r.work_type.as_str()).collect();
+        assert_eq!(work_types, vec!["舗装工", "舗装工", "", "区画線工", "道路土工"]);
+        assert_eq!(merged.rows()[1].search_patterns, "到着温度|出荷温度");
+    }
+}
@@ -0,0 +1,305 @@
+//! 工種マスタの階層表示と編集
+//!
+//! 階層は 写真種別 → 工種 → 種別 → 細別 → 備考 の5段。
+//! ノードは `/` 区切りのパス（例: `品質管理写真/舗装工/舗装打換え工`）で指定し、
+//! `*` はその階層の任意の値に一致する（例: `*/舗装工` は全写真種別の舗装工）。
+//! 追加・名前変更・削除はパスに一致するすべての行に反映する。
+
+use crate::hierarchy::{self, HierarchyMaster, HierarchyRow};
+use crate::pattern;
+use crate::{Error, Result};
+
+/// 階層の数
+pub const LEVEL_COUNT: usize = 5;
+
+/// 階層名（表示用）
+pub const LEVEL_NAMES: [&str; LEVEL_COUNT] = ["写真種別", "工種", "種別", "細別", "備考"];
+
+/// パスの任意一致
+const WILDCARD: &str = "*";
+
+fn level(row: &HierarchyRow, index: usize) -> &str {
+    match index {
+        0 => &row.photo_type,
+        1 => &row.work_type,
+        2 => &row.variety,
+        3 => &row.subphase,
+        _ => &row.remarks,
+    }
+}
+
+fn level_mut(row: &mut HierarchyRow, index: usize) -> &mut String {
+    match index {
+        0 => &mut row.photo_type,
+        1 => &mut row.work_type,
+        2 => &mut row.variety,
+        3 => &mut row.subphase,
+        _ => &mut row.remarks,
+    }
+}
+
+/// ノードのパス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodePath {
+    segments: Vec<String>,
+}
+
+impl NodePath {
+    /// `/` 区切りの文字列から作成（1〜5段）
+    pub fn parse(path: &str) -> Result<Self> {
+        let segments: Vec<String> = path.split('/').map(|s| s.trim().to_string()).collect();
+        if path.trim().is_empty() || segments.len() > LEVEL_COUNT {
+            return Err(Error::Master(format!(
+                "ノードのパスは {} を / で区切った1〜{}段で指定してください: {}",
+                LEVEL_NAMES.join("/"),
+                LEVEL_COUNT,
+                path
+            )));
+        }
+        Ok(Self { segments })
+    }
+
+    /// 行の分類のパス（末尾の空の階層は含めない）
+    pub fn from_row(row: &HierarchyRow) -> Result<Self> {
+        let depth = (0..LEVEL_COUNT).rev().find(|&i| !level(row, i).is_empty()).map_or(0, |i| i + 1);
+        if depth == 0 {
+            return Err(Error::Master("写真種別〜備考がすべて空の行です".to_string()));
+        }
+        Ok(Self { segments: (0..depth).map(|i| level(row, i).to_string()).collect() })
+    }
+
+    /// 段数
+    pub fn depth(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// 最下段の名前
+    pub fn name(&self) -> &str {
+        self.segments.last().map(|s| s.as_str()).unwrap_or_default()
+    }
+
+    /// 最下段の階層名
+    pub fn level_name(&self) -> &'static str {
+        LEVEL_NAMES[self.depth() - 1]
+    }
+
+    fn has_wildcard(&self) -> bool {
+        self.segments.iter().any(|s| s == WILDCARD)
+    }
+
+    /// 行がこのノード以下にあるか
+    pub fn matches(&self, row: &HierarchyRow) -> bool {
+        self.segments
+            .iter()
+            .enumerate()
+            .all(|(i, segment)| segment == WILDCARD || level(row, i) == segment)
+    }
+
+    /// 行がこのノードそのもの（下位の階層が空）か
+    fn is_leaf_of(&self, row: &HierarchyRow) -> bool {
+        self.matches(row) && (self.depth()..LEVEL_COUNT).all(|i| level(row, i).is_empty())
+    }
+}
+
+impl std::fmt::Display for NodePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.segments.join("/"))
+    }
+}
+
+/// 階層ツリーのノード
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TreeNode {
+    pub name: String,
+    /// このノード以下の行数
+    pub rows: usize,
+    pub children: Vec<TreeNode>,
+}
+
+/// 階層ツリーを作る（各階層は初出順、末尾の空の階層は省略）
+pub fn tree(master: &HierarchyMaster) -> Vec<TreeNode> {
+    let mut roots: Vec<TreeNode> = Vec::new();
+    for row in master.rows() {
+        let depth = (0..LEVEL_COUNT).rev().find(|&i| !level(row, i).is_empty()).map_or(0, |i| i + 1);
+        let mut nodes = &mut roots;
+        for i in 0..depth {
+            let name = level(row, i);
+            let index = match nodes.iter().position(|node| node.name == name) {
+                Some(index) => index,
+                None => {
+                    nodes.push(TreeNode { name: name.to_string(), ..Default::default() });
+                    nodes.len() - 1
+                }
+            };
+            nodes[index].rows += 1;
+            nodes = &mut nodes[index].children;
+        }
+    }
+    roots
+}
+
+/// ノードを追加する
+///
+/// 親ノードそのものの行（下位の階層が空）があればその行を埋め、
+/// なければ親ノードに最も近い行の後ろに新しい行を挿入する
+pub fn add(master: &HierarchyMaster, path: &NodePath, division: &str, patterns: &str) -> Result<HierarchyMaster> {
+    if path.has_wildcard() || path.segments.iter().any(|s| s.is_empty()) {
+        return Err(Error::Master(format!("追加するノードには * や空の階層を使えません: {}", path)));
+    }
+    if master.rows().iter().any(|row| path.matches(row)) {
+        return Err(Error::Master(format!("{}「{}」は既にあります: {}", path.level_name(), path.name(), path)));
+    }
+
+    let mut rows = master.rows().to_vec();
+    let parent = NodePath { segments: path.segments[..path.depth() - 1].to_vec() };
+    if let Some(row) = rows.iter_mut().find(|row| parent.depth() > 0 && parent.is_leaf_of(row)) {
+        *level_mut(row, path.depth() - 1) = path.name().to_string();
+        if !patterns.is_empty() {
+            row.search_patterns = hierarchy::normalize_patterns(patterns);
+        }
+        return Ok(HierarchyMaster::from_rows(rows));
+    }
+
+    let mut row = HierarchyRow {
+        photo_division: division.to_string(),
+        search_patterns: hierarchy::normalize_patterns(patterns),
+        ..Default::default()
+    };
+    for (i, segment) in path.segments.iter().enumerate() {
+        *level_mut(&mut row, i) = segment.clone();
+    }
+    // 共通する上位の階層が最も深い行の後ろ（同じ深さなら最後の行）に挿入
+    let shared = |other: &HierarchyRow| (0..path.depth()).take_while(|&i| level(other, i) == level(&row, i)).count();
+    let position = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, other)| shared(other) > 0)
+        .max_by_key(|(i, other)| (shared(other), *i))
+        .map_or(rows.len(), |(i, _)| i + 1);
+    rows.insert(position, row);
+    Ok(HierarchyMaster::from_rows(rows))
+}
+
+/// ノードの名前を変更し、変更した行数を返す
+///
+/// 変更の結果まったく同じ分類の行ができた場合は1行にまとめ、検索パターンを統合する
+pub fn rename(master: &HierarchyMaster, path: &NodePath, new_name: &str) -> Result<(HierarchyMaster, usize)> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() || new_name == WILDCARD || new_name.contains('/') {
+        return Err(Error::Master(format!("新しい名前が不正です: \"{}\"", new_name)));
+    }
+    let index = path.depth() - 1;
+    let mut changed = 0;
+    let mut rows = master.rows().to_vec();
+    for row in rows.iter_mut().filter(|row| path.matches(row)) {
+        *level_mut(row, index) = new_name.to_string();
+        changed += 1;
+    }
+    if changed == 0 {
+        return Err(not_found(path));
+    }
+    Ok((HierarchyMaster::from_rows(merge_duplicates(rows)), changed))
+}
+
+/// ノード以下の行を削除し、削除した行数を返す
+pub fn remove(master: &HierarchyMaster, path: &NodePath) -> Result<(HierarchyMaster, usize)> {
+    let (removed, kept): (Vec<HierarchyRow>, Vec<HierarchyRow>) =
+        master.rows().iter().cloned().partition(|row| path.matches(row));
+    if removed.is_empty() {
+        return Err(not_found(path));
+    }
+    Ok((HierarchyMaster::from_rows(kept), removed.len()))
+}
+
+pub(crate) fn not_found(path: &NodePath) -> Error {
+    Error::Master(format!("{}「{}」が見つかりません: {}", path.level_name(), path.name(), path))
+}
+
+/// 写真区分〜備考が同じ行をまとめる（先の行に検索パターンを追記）
+fn merge_duplicates(rows: Vec<HierarchyRow>) -> Vec<HierarchyRow> {
+    let mut merged: Vec<HierarchyRow> = Vec::with_capacity(rows.len());
+    for row in rows {
+        let same = |other: &&mut HierarchyRow| {
+            other.photo_division == row.photo_division && (0..LEVEL_COUNT).all(|i| level(other, i) == level(&row, i))
+        };
+        match merged.iter_mut().find(|other| same(other)) {
+            Some(first) => first.search_patterns = pattern::merge_patterns(&first.search_patterns, &row.search_patterns),
+            None => merged.push(row),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASTER_CSV: &str = "写真区分,写真種別,工種,種別,細別,備考,検索パターン
+直接工事費,施工状況写真,舗装工,舗装打換え工,表層工,舗設状況,舗設
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,到着温度,到着温度
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,敷均し温度,敷均し温度
+直接工事費,施工状況写真,区画線工,区画線工,溶融式区画線,,
+直接工事費,品質管理写真,舗装工,舗装打換え工,上層路盤工,現場密度測定,密度
+";
+
+    fn master() -> HierarchyMaster {
+        HierarchyMaster::from_csv_str(MASTER_CSV).unwrap()
+    }
+
+    fn path(s: &str) -> NodePath {
+        NodePath::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_tree_counts_rows() {
+        let roots = tree(&master());
+        let names: Vec<&str> = roots.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["施工状況写真", "品質管理写真"]);
+        assert_eq!(roots[1].rows, 3);
+        let paving = &roots[1].children[0].children[0];
+        assert_eq!(paving.children.len(), 2);
+        assert_eq!(paving.children[0].rows, 2);
+        // 備考が空の行は細別で止まる
+        let marking = &roots[0].children[1].children[0].children[0];
+        assert_eq!(marking.name, "溶融式区画線");
+        assert!(marking.children.is_empty());
+    }
+
+    #[test]
+    fn test_add_fills_leaf_or_inserts_next_to_siblings() {
+        let filled = add(&master(), &path("施工状況写真/区画線工/区画線工/溶融式区画線/設置状況"), "直接工事費", "区画線").unwrap();
+        assert_eq!(filled.rows().len(), 5);
+        assert_eq!(filled.rows()[3].remarks, "設置状況");
+        assert_eq!(filled.rows()[3].search_patterns, "区画線");
+
+        let inserted = add(&master(), &path("品質管理写真/舗装工/舗装打換え工/表層工/出荷温度"), "直接工事費", "出荷温度、出荷").unwrap();
+        assert_eq!(inserted.rows().len(), 6);
+        assert_eq!(inserted.rows()[3].remarks, "出荷温度");
+        assert_eq!(inserted.rows()[3].search_patterns, "出荷温度|出荷");
+
+        assert!(add(&master(), &path("品質管理写真/舗装工"), "直接工事費", "").is_err());
+        assert!(add(&master(), &path("*/舗装工/新種別"), "直接工事費", "").is_err());
+    }
+
+    #[test]
+    fn test_rename_cascades_and_merges() {
+        let (renamed, count) = rename(&master(), &path("*/舗装工"), "舗装工事").unwrap();
+        assert_eq!(count, 4);
+        assert!(renamed.rows().iter().all(|row| row.work_type != "舗装工"));
+
+        let (merged, count) = rename(&master(), &path("品質管理写真/舗装工/舗装打換え工/表層工/敷均し温度"), "到着温度").unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(merged.rows().len(), 4);
+        assert_eq!(merged.rows()[1].search_patterns, "到着温度|敷均し温度");
+
+        assert!(rename(&master(), &path("*/道路土工"), "土工").is_err());
+    }
+
+    #[test]
+    fn test_remove_subtree() {
+        let (removed, count) = remove(&master(), &path("品質管理写真/舗装工/舗装打換え工/表層工")).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(removed.rows().len(), 3);
+        assert!(NodePath::parse("a/b/c/d/e/f").is_err());
+    }
+}
@@ -0,0 +1,421 @@
+//! 工種マスタのファイル形式
+//!
+//! CSV・Excel（.xlsx）・JSON のいずれも [`HierarchyMaster`] として読み書きする。
+//! 旧形式の Excel（.xls）は読み込みのみ。Excel の読み込みは `xlsx-read` 機能（Web版も有効）で使える。
+//!
+//! ## JSON形式
+//! 行の配列（`photoType`/`workType`/`variety`/`subphase`/`remarks`/`searchPatterns`）のほか、
+//! 旧照合マスタの階層形式も読み込める。
+//! ```text
+//! { "直接工事費": { 写真種別: { 工種: { 種別: { 作業段階: { 備考: { "matchPatterns": [...] } } } } } } }
+//! ```
+
+use crate::csv;
+use crate::hierarchy::{self, HierarchyMaster, HierarchyRow, MasterIssue, MasterIssueKind, MasterLoad};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// 旧階層形式でパターンを持つキー
+const MATCH_PATTERNS_KEY: &str = "matchPatterns";
+
+/// 書き出し時の列（CSV・Excel共通）
+const HEADER: [&str; 7] = ["写真区分", "写真種別", "工種", "種別", "細別", "備考", "検索パターン"];
+
+/// マスタのファイル形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MasterFormat {
+    Csv,
+    Json,
+    Xlsx,
+    /// 旧形式の Excel（読み込みのみ）
+    Xls,
+}
+
+impl MasterFormat {
+    /// 拡張子から判定
+    pub fn from_path(path: &Path) -> crate::Result<Self> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        match ext.as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "xlsx" | "xlsm" => Ok(Self::Xlsx),
+            "xls" => Ok(Self::Xls),
+            _ => Err(crate::Error::Parse(format!(
+                "未対応のマスタ形式: {} (csv, json, xlsx, xlsのみ対応)",
+                path.display()
+            ))),
+        }
+    }
+}
+
+impl HierarchyMaster {
+    /// ファイルから読み込み（形式は拡張子で判定）
+    ///
+    /// 不正な行が1つでもあればエラー（内容は [`Self::load_path`] で確認できる）
+    pub fn from_path(path: &Path) -> crate::Result<Self> {
+        Self::load_path(path)?.into_master()
+    }
+
+    /// ファイルから読み込み、行ごとの問題も返す
+    pub fn load_path(path: &Path) -> crate::Result<MasterLoad> {
+        let format = MasterFormat::from_path(path)?;
+        let bytes = std::fs::read(path)?;
+        Self::load_bytes(&bytes, format)
+    }
+
+    /// バイト列から読み込み（Web版のアップロード用）
+    pub fn load_bytes(bytes: &[u8], format: MasterFormat) -> crate::Result<MasterLoad> {
+        match format {
+            MasterFormat::Csv => Self::load_csv_str(&csv::decode_text(bytes)),
+            MasterFormat::Json => Self::load_json_str(&csv::decode_text(bytes)),
+            MasterFormat::Xlsx | MasterFormat::Xls => load_excel(bytes),
+        }
+    }
+
+    /// JSON文字列を読み込み（行の配列・旧階層形式）
+    pub fn load_json_str(content: &str) -> crate::Result<MasterLoad> {
+        let value: Value = serde_json::from_str(content)?;
+        let mut issues = Vec::new();
+        let rows: Vec<(usize, HierarchyRow)> = match value {
+            Value::Array(items) => items
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, item)| match serde_json::from_value::<HierarchyRow>(item) {
+                    Ok(row) => Some((index + 1, row)),
+                    Err(e) => {
+                        issues.push(MasterIssue::new(index + 1, MasterIssueKind::Malformed, e.to_string()));
+                        None
+                    }
+                })
+                .collect(),
+            Value::Object(root) => rows_from_tree(&root).into_iter().enumerate().map(|(i, row)| (i + 1, row)).collect(),
+            _ => return Err(crate::Error::Parse("マスタJSONは配列またはオブジェクトです".to_string())),
+        };
+
+        let rows = rows
+            .into_iter()
+            .map(|(line, mut row)| {
+                row.search_patterns = hierarchy::normalize_patterns(&row.search_patterns);
+                (line, row)
+            })
+            .collect();
+        Ok(hierarchy::check_rows(rows, issues))
+    }
+
+    /// 指定形式で書き出し（形式は拡張子で判定）
+    pub fn write_path(&self, path: &Path) -> crate::Result<()> {
+        let bytes = match MasterFormat::from_path(path)? {
+            MasterFormat::Csv => self.to_csv_string().into_bytes(),
+            MasterFormat::Json => self.to_json_string()?.into_bytes(),
+            MasterFormat::Xlsx => self.to_xlsx_bytes()?,
+            MasterFormat::Xls => {
+                return Err(crate::Error::Parse(format!(
+                    "xls形式には書き出せません（xlsx を指定してください）: {}",
+                    path.display()
+                )))
+            }
+        };
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// CSV文字列に変換（`master/` のCSVと同じ形式: BOMなし・LF・値はすべて引用符付き）
+    pub fn to_csv_string(&self) -> String {
+        let mut out = HEADER.join(",");
+        out.push('\n');
+        for row in self.rows() {
+            let line: Vec<String> = row_fields(row).iter().map(|f| crate::csv::escape_field(f, true)).collect();
+            out.push_str(&line.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// JSON文字列に変換（行の配列）
+    pub fn to_json_string(&self) -> crate::Result<String> {
+        Ok(serde_json::to_string_pretty(self.rows())?)
+    }
+
+    /// Excel（.xlsx）のバイト列に変換
+    #[cfg(feature = "excel")]
+    pub fn to_xlsx_bytes(&self) -> crate::Result<Vec<u8>> {
+        use rust_xlsxwriter::{Format, Workbook};
+
+        let to_err = |e: rust_xlsxwriter::XlsxError| crate::Error::Parse(format!("Excel書き出しエラー: {}", e));
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        let bold = Format::new().set_bold();
+        for (col, name) in HEADER.iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, *name, &bold).map_err(to_err)?;
+        }
+        for (index, row) in self.rows().iter().enumerate() {
+            for (col, value) in row_fields(row).iter().enumerate() {
+                if !value.is_empty() {
+                    sheet.write_string(index as u32 + 1, col as u16, *value).map_err(to_err)?;
+                }
+            }
+        }
+        workbook.save_to_buffer().map_err(to_err)
+    }
+
+    /// Excel（.xlsx）のバイト列に変換
+    #[cfg(not(feature = "excel"))]
+    pub fn to_xlsx_bytes(&self) -> crate::Result<Vec<u8>> {
+        Err(crate::Error::Config("Excel形式は excel 機能が必要です".to_string()))
+    }
+}
+
+impl MasterLoad {
+    /// 不正な行がなければマスタを返す
+    pub fn into_master(self) -> crate::Result<HierarchyMaster> {
+        let errors: Vec<String> = self
+            .issues
+            .iter()
+            .filter(|issue| issue.is_error())
+            .map(|issue| issue.to_string())
+            .collect();
+        if errors.is_empty() {
+            return Ok(self.master);
+        }
+        let mut message = format!("マスタに不正な行があります: {}", errors[..errors.len().min(3)].join(", "));
+        if errors.len() > 3 {
+            message.push_str(&format!(" ほか{}件", errors.len() - 3));
+        }
+        Err(crate::Error::Parse(message))
+    }
+}
+
+fn row_fields(row: &HierarchyRow) -> [&str; 7] {
+    [
+        &row.photo_division,
+        &row.photo_type,
+        &row.work_type,
+        &row.variety,
+        &row.subphase,
+        &row.remarks,
+        &row.search_patterns,
+    ]
+}
+
+/// 旧階層形式を行に展開
+///
+/// ルートが「〜費」のキーだけなら写真区分として1階層下りる
+fn rows_from_tree(root: &Map<String, Value>) -> Vec<HierarchyRow> {
+    let mut rows = Vec::new();
+    let is_division = !root.is_empty() && root.keys().all(|k| k.ends_with('費'));
+    if is_division {
+        for (division, child) in root {
+            let base = HierarchyRow {
+                photo_division: division.clone(),
+                ..Default::default()
+            };
+            if let Some(child) = child.as_object() {
+                collect_tree_rows(child, &base, 0, &mut rows);
+            }
+        }
+    } else {
+        collect_tree_rows(root, &HierarchyRow::default(), 0, &mut rows);
+    }
+    rows
+}
+
+/// 階層を再帰的に走査し、`matchPatterns` を持つ節と葉を行として収集
+fn collect_tree_rows(node: &Map<String, Value>, base: &HierarchyRow, depth: usize, rows: &mut Vec<HierarchyRow>) {
+    if let Some(patterns) = node.get(MATCH_PATTERNS_KEY) {
+        rows.push(HierarchyRow {
+            search_patterns: pattern_list(patterns),
+            ..base.clone()
+        });
+    }
+
+    for (key, child) in node {
+        if key == MATCH_PATTERNS_KEY {
+            continue;
+        }
+        let mut row = base.clone();
+        match depth {
+            0 => row.photo_type = key.clone(),
+            1 => row.work_type = key.clone(),
+            2 => row.variety = key.clone(),
+            3 => row.subphase = key.clone(),
+            4 => row.remarks = key.clone(),
+            _ => continue,
+        }
+        match child {
+            Value::Object(obj) if !obj.is_empty() => collect_tree_rows(obj, &row, depth + 1, rows),
+            // 葉（空オブジェクト・配列・文字列）はその階層までの行
+            _ => {
+                row.search_patterns = pattern_list(child);
+                rows.push(row);
+            }
+        }
+    }
+}
+
+fn pattern_list(value: &Value) -> String {
+    match value {
+        Value::Array(items) => items.iter().filter_map(Value::as_str).collect::<Vec<_>>().join("|"),
+        Value::String(s) => s.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Excel（.xlsx）の最初のシートを表として読み込む
+#[cfg(feature = "xlsx-read")]
+fn load_excel(bytes: &[u8]) -> crate::Result<MasterLoad> {
+    use calamine::Reader;
+
+    // .xlsx・.xls は中身で判定する
+    let mut workbook = calamine::open_workbook_auto_from_rs(std::io::Cursor::new(bytes))
+        .map_err(|e| crate::Error::Parse(format!("Excel読み込みエラー: {}", e)))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| crate::Error::Parse("シートが見つかりません".to_string()))?
+        .map_err(|e| crate::Error::Parse(format!("シート読み込みエラー: {}", e)))?;
+
+    let mut records = range.rows().enumerate().filter_map(|(index, cells)| {
+        let fields: Vec<String> = cells.iter().map(|cell| cell.to_string().trim().to_string()).collect();
+        if fields.iter().all(|f| f.is_empty()) {
+            return None;
+        }
+        Some(Ok(csv::CsvRecord {
+            line: range.start().map_or(0, |(row, _)| row as usize) + index + 1,
+            fields,
+        }))
+    });
+    let header = records
+        .next()
+        .and_then(Result::ok)
+        .ok_or_else(|| crate::Error::Parse("ヘッダー行がありません".to_string()))?;
+    hierarchy::load_table(&header, records)
+}
+
+#[cfg(not(feature = "xlsx-read"))]
+fn load_excel(_bytes: &[u8]) -> crate::Result<MasterLoad> {
+    Err(crate::Error::Config("Excel形式の読み込みは xlsx-read 機能が必要です".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY_TREE: &str = r#"{
+        "直接工事費": {
+            "品質管理写真": {
+                "舗装工": {
+                    "舗装打換え工": {
+                        "表層工": {
+                            "アスファルト混合物温度測定": { "matchPatterns": ["温度管理", "到着温度"] }
+                        },
+                        "上層路盤工": {
+                            "matchPatterns": ["路盤"]
+                        }
+                    }
+                }
+            }
+        }
+    }"#;
+
+    fn sample() -> HierarchyMaster {
+        HierarchyMaster::load_json_str(LEGACY_TREE).unwrap().into_master().unwrap()
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(MasterFormat::from_path(Path::new("m.CSV")).unwrap(), MasterFormat::Csv);
+        assert_eq!(MasterFormat::from_path(Path::new("m.json")).unwrap(), MasterFormat::Json);
+        assert_eq!(MasterFormat::from_path(Path::new("m.xlsx")).unwrap(), MasterFormat::Xlsx);
+        assert!(MasterFormat::from_path(Path::new("m.txt")).is_err());
+    }
+
+    #[test]
+    fn test_legacy_tree_json() {
+        let master = sample();
+        let rows = master.rows();
+        assert_eq!(rows.len(), 2);
+        let temp = rows.iter().find(|r| r.remarks == "アスファルト混合物温度測定").unwrap();
+        assert_eq!(temp.photo_division, "直接工事費");
+        assert_eq!(temp.photo_type, "品質管理写真");
+        assert_eq!(temp.subphase, "表層工");
+        assert_eq!(temp.search_patterns, "温度管理|到着温度");
+        let base = rows.iter().find(|r| r.subphase == "上層路盤工").unwrap();
+        assert_eq!(base.remarks, "");
+        assert_eq!(base.search_patterns, "路盤");
+    }
+
+    #[test]
+    fn test_csv_and_json_round_trip() {
+        let master = sample();
+
+        let from_csv = HierarchyMaster::load_csv_str(&master.to_csv_string()).unwrap();
+        assert!(from_csv.issues.is_empty());
+        assert_eq!(from_csv.master.rows(), master.rows());
+
+        let from_json = HierarchyMaster::load_json_str(&master.to_json_string().unwrap()).unwrap();
+        assert!(from_json.issues.is_empty());
+        assert_eq!(from_json.master.rows(), master.rows());
+    }
+
+    #[cfg(feature = "excel")]
+    #[test]
+    fn test_xlsx_round_trip() {
+        let master = sample();
+        let bytes = master.to_xlsx_bytes().unwrap();
+        let loaded = HierarchyMaster::load_bytes(&bytes, MasterFormat::Xlsx).unwrap();
+        assert!(loaded.issues.is_empty());
+        assert_eq!(loaded.master.rows(), master.rows());
+
+        // .xls は読み込みのみ（中身で判定して読む）
+        assert_eq!(MasterFormat::from_path(Path::new("master.XLS")).unwrap(), MasterFormat::Xls);
+        let loaded = HierarchyMaster::load_bytes(&bytes, MasterFormat::Xls).unwrap();
+        assert_eq!(loaded.master.rows(), master.rows());
+        let dir = std::env::temp_dir().join(format!("photo-ai-xls-{}", std::process::id()));
+        assert!(master.write_path(&dir.with_extension("xls")).is_err());
+    }
+
+    #[cfg(feature = "excel")]
+    #[test]
+    fn test_legacy_xlsx_columns() {
+        use rust_xlsxwriter::Workbook;
+
+        // 旧形式: 写真区分の列に写真種別、パターンはカンマ区切り
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        let table = [
+            ["写真区分", "工種", "種別", "細別", "備考", "matchPatterns"],
+            ["品質管理写真", "舗装工", "舗装打換え工", "表層工", "温度測定", "温度管理, 到着温度"],
+        ];
+        for (r, cells) in table.iter().enumerate() {
+            for (c, value) in cells.iter().enumerate() {
+                sheet.write_string(r as u32, c as u16, *value).unwrap();
+            }
+        }
+        let bytes = workbook.save_to_buffer().unwrap();
+
+        let loaded = HierarchyMaster::load_bytes(&bytes, MasterFormat::Xlsx).unwrap();
+        assert_eq!(loaded.issues.len(), 1);
+        assert_eq!(loaded.issues[0].kind, MasterIssueKind::LegacyLayout);
+        let master = loaded.into_master().unwrap();
+        let row = &master.rows()[0];
+        assert_eq!(row.photo_division, "");
+        assert_eq!(row.photo_type, "品質管理写真");
+        assert_eq!(row.remarks, "温度測定");
+        assert_eq!(row.search_patterns, "温度管理|到着温度");
+    }
+
+    #[test]
+    fn test_json_rows_report_bad_items() {
+        let loaded = HierarchyMaster::load_json_str(
+            r#"[{"workType":"舗装工","variety":"舗装打換え工","subphase":"表層工"}, 3]"#,
+        )
+        .unwrap();
+        assert_eq!(loaded.master.rows().len(), 1);
+        assert_eq!(loaded.issues.len(), 1);
+        assert_eq!(loaded.issues[0].kind, MasterIssueKind::Malformed);
+        assert!(loaded.into_master().is_err());
+    }
+}
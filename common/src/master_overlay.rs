@@ -0,0 +1,183 @@
+//! 工事ごとのマスタ差分（オーバーレイ）
+//!
+//! 共通の工種マスタ（ベース）を複製せず、工事ごとの追加・非表示・名前変更・検索パターン追加を
+//! JSONファイルに書いて重ねる。ノードの指定は [`crate::master_edit`] と同じ `/` 区切りのパス。
+//!
+//! ```json
+//! {
+//!   "hide": ["品質管理写真/舗装工/舗装打換え工/上層路盤工"],
+//!   "rename": [{ "node": "*/舗装工/舗装打換え工", "to": "舗装打換工" }],
+//!   "patterns": [{ "node": "品質管理写真/舗装工/舗装打換工/表層工/到着温度", "add": "出荷温度" }],
+//!   "add": [{ "photoType": "施工状況写真", "workType": "舗装工", "variety": "舗装打換工",
+//!             "subphase": "表層工", "remarks": "端部処理状況", "searchPatterns": "端部" }]
+//! }
+//! ```
+//!
+//! 1つのオーバーレイの中では 非表示 → 名前変更 → 検索パターン追加 → 行追加 の順に適用し、
+//! 複数のオーバーレイは指定順に重ねる。同じ入力からは常に同じ結果になる。
+
+use crate::hierarchy::{HierarchyMaster, HierarchyRow};
+use crate::master_edit::{self, NodePath};
+use crate::pattern;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 追加行の写真区分の既定値
+const DEFAULT_DIVISION: &str = "直接工事費";
+
+/// ノードの名前変更
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OverlayRename {
+    pub node: String,
+    pub to: String,
+}
+
+/// ノード以下の行への検索パターン追加
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OverlayPatterns {
+    pub node: String,
+    pub add: String,
+}
+
+/// オーバーレイ1件
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MasterOverlay {
+    /// 非表示にするノード
+    pub hide: Vec<String>,
+    pub rename: Vec<OverlayRename>,
+    pub patterns: Vec<OverlayPatterns>,
+    /// 追加する行
+    pub add: Vec<HierarchyRow>,
+}
+
+impl MasterOverlay {
+    /// JSON文字列から読み込み
+    pub fn from_json_str(content: &str) -> crate::Result<Self> {
+        Ok(serde_json::from_str(content.trim_start_matches('\u{feff}'))?)
+    }
+
+    /// JSONファイルから読み込み
+    pub fn from_path(path: &Path) -> crate::Result<Self> {
+        Self::from_json_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// ベースに重ねた結果と、適用できなかった項目の警告を返す
+    pub fn apply(&self, base: &HierarchyMaster) -> (HierarchyMaster, Vec<String>) {
+        let mut master = base.clone();
+        let mut warnings = Vec::new();
+        let mut warn = |error: crate::Error| {
+            warnings.push(match error {
+                crate::Error::Master(message) => message,
+                other => other.to_string(),
+            })
+        };
+
+        for node in &self.hide {
+            match NodePath::parse(node).and_then(|path| master_edit::remove(&master, &path)) {
+                Ok((hidden, _)) => master = hidden,
+                Err(e) => warn(e),
+            }
+        }
+        for rename in &self.rename {
+            match NodePath::parse(&rename.node).and_then(|path| master_edit::rename(&master, &path, &rename.to)) {
+                Ok((renamed, _)) => master = renamed,
+                Err(e) => warn(e),
+            }
+        }
+        for patterns in &self.patterns {
+            match NodePath::parse(&patterns.node).and_then(|path| add_patterns(&master, &path, &patterns.add)) {
+                Ok(updated) => master = updated,
+                Err(e) => warn(e),
+            }
+        }
+        for row in &self.add {
+            let division = if row.photo_division.is_empty() { DEFAULT_DIVISION } else { &row.photo_division };
+            match NodePath::from_row(row).and_then(|path| master_edit::add(&master, &path, division, &row.search_patterns)) {
+                Ok(added) => master = added,
+                Err(e) => warn(e),
+            }
+        }
+        (master, warnings)
+    }
+}
+
+/// ノード以下の全行に検索パターンを追記
+fn add_patterns(master: &HierarchyMaster, path: &NodePath, patterns: &str) -> crate::Result<HierarchyMaster> {
+    let mut found = false;
+    let mut rows = master.rows().to_vec();
+    for row in rows.iter_mut().filter(|row| path.matches(row)) {
+        row.search_patterns = pattern::merge_patterns(&row.search_patterns, patterns);
+        found = true;
+    }
+    if !found {
+        return Err(master_edit::not_found(path));
+    }
+    Ok(HierarchyMaster::from_rows(rows))
+}
+
+/// ベースにオーバーレイを順に重ねる（警告には各オーバーレイの名前を付ける）
+pub fn resolve(base: &HierarchyMaster, overlays: &[(String, MasterOverlay)]) -> (HierarchyMaster, Vec<String>) {
+    let mut master = base.clone();
+    let mut warnings = Vec::new();
+    for (name, overlay) in overlays {
+        let (resolved, found) = overlay.apply(&master);
+        master = resolved;
+        warnings.extend(found.into_iter().map(|w| format!("{}: {}", name, w)));
+    }
+    (master, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_CSV: &str = "写真区分,写真種別,工種,種別,細別,備考,検索パターン
+直接工事費,施工状況写真,舗装工,舗装打換え工,表層工,舗設状況,舗設
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,到着温度,到着温度
+直接工事費,品質管理写真,舗装工,舗装打換え工,上層路盤工,現場密度測定,密度
+";
+
+    const OVERLAY_JSON: &str = r#"{
+        "hide": ["品質管理写真/舗装工/舗装打換え工/上層路盤工"],
+        "rename": [{ "node": "*/舗装工/舗装打換え工", "to": "舗装打換工" }],
+        "patterns": [{ "node": "品質管理写真/舗装工/舗装打換工/表層工/到着温度", "add": "出荷温度|到着温度" }],
+        "add": [{ "photoType": "施工状況写真", "workType": "舗装工", "variety": "舗装打換工",
+                  "subphase": "表層工", "remarks": "端部処理状況", "searchPatterns": "端部" }]
+    }"#;
+
+    #[test]
+    fn test_apply_overlay_in_order() {
+        let base = HierarchyMaster::from_csv_str(BASE_CSV).unwrap();
+        let overlay = MasterOverlay::from_json_str(OVERLAY_JSON).unwrap();
+        let (master, warnings) = overlay.apply(&base);
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let remarks: Vec<&str> = master.rows().iter().map(|r| r.remarks.as_str()).collect();
+        assert_eq!(remarks, vec!["舗設状況", "端部処理状況", "到着温度"]);
+        assert!(master.rows().iter().all(|r| r.variety == "舗装打換工"));
+        assert_eq!(master.rows()[2].search_patterns, "到着温度|出荷温度");
+        assert_eq!(master.rows()[1].photo_division, "直接工事費");
+        // ベースは変更しない
+        assert_eq!(base.rows().len(), 3);
+    }
+
+    #[test]
+    fn test_resolve_layers_and_reports_missing_nodes() {
+        let base = HierarchyMaster::from_csv_str(BASE_CSV).unwrap();
+        let first = MasterOverlay::from_json_str(OVERLAY_JSON).unwrap();
+        let second = MasterOverlay {
+            hide: vec!["*/舗装工/舗装打換え工".to_string()],
+            rename: vec![OverlayRename { node: "*/舗装工/舗装打換工/表層工/舗設状況".to_string(), to: "表層舗設状況".to_string() }],
+            ..Default::default()
+        };
+        let overlays = vec![("a.json".to_string(), first), ("b.json".to_string(), second)];
+
+        let (master, warnings) = resolve(&base, &overlays);
+        assert_eq!(master.rows()[0].remarks, "表層舗設状況");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("b.json: 種別「舗装打換え工」が見つかりません"));
+        // 同じ入力からは同じ結果
+        assert_eq!(resolve(&base, &overlays).0.rows(), master.rows());
+    }
+}
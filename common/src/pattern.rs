@@ -0,0 +1,272 @@
+//! 検索パターンの構文と照合
+//!
+//! マスタの検索パターン（`|` 区切り）は1件ごとに次の構文を持つ。
+//!
+//! | 書き方 | 意味 |
+//! |---|---|
+//! | `到着温度` | 部分一致（大文字・小文字を区別しない） |
+//! | `re:乳剤.*散布` | 正規表現 |
+//! | `温度^3` | 重み（一致すると3点、省略時は1点） |
+//! | `!養生砂` | 否定（一致したらその行は候補から外す） |
+//! | `ocr:到着温度` / `scene:散布` | 照合する項目を黒板・看板の文字（OCR）か情景説明に限定 |
+//!
+//! 接頭辞は `!`、項目、`re:` の順に重ねられる（例: `!scene:re:砂.*散布`）。
+//! 正規表現の中の `|`・`,` は括弧の中に書けば区切りとみなさない（例: `re:(乳剤|タック)散布`）。
+
+use crate::types::AnalysisResult;
+use regex::{Regex, RegexBuilder};
+
+/// 照合する項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternField {
+    /// OCR・情景説明・その他の文字すべて
+    Any,
+    /// 黒板・看板の文字（detectedText）
+    Ocr,
+    /// 情景説明（description）
+    Scene,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    /// 小文字にした部分文字列
+    Text(String),
+    Regex(Regex),
+}
+
+/// 検索パターン1件
+#[derive(Debug, Clone)]
+pub struct SearchPattern {
+    /// 元の表記
+    pub source: String,
+    pub field: PatternField,
+    pub weight: u32,
+    pub negative: bool,
+    matcher: Matcher,
+}
+
+impl SearchPattern {
+    /// 1件を解釈する（正規表現・重みが不正ならエラー）
+    pub fn parse(source: &str) -> crate::Result<Self> {
+        let invalid = |reason: String| crate::Error::Parse(format!("検索パターン「{}」: {}", source, reason));
+        let mut rest = source.trim();
+
+        let negative = rest.starts_with('!');
+        if negative {
+            rest = &rest[1..];
+        }
+        let field = if let Some(r) = rest.strip_prefix("ocr:") {
+            rest = r;
+            PatternField::Ocr
+        } else if let Some(r) = rest.strip_prefix("scene:") {
+            rest = r;
+            PatternField::Scene
+        } else {
+            PatternField::Any
+        };
+
+        let mut weight = 1;
+        if let Some((body, suffix)) = rest.rsplit_once('^') {
+            if !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) {
+                weight = suffix.parse().map_err(|_| invalid(format!("重み {} が大きすぎます", suffix)))?;
+                rest = body;
+            }
+        }
+
+        let matcher = match rest.strip_prefix("re:") {
+            Some(expr) => Matcher::Regex(
+                RegexBuilder::new(expr)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| invalid(format!("正規表現が不正です（{}）", e)))?,
+            ),
+            None => Matcher::Text(rest.to_lowercase()),
+        };
+        let empty = match &matcher {
+            Matcher::Text(text) => text.is_empty(),
+            Matcher::Regex(re) => re.as_str().is_empty(),
+        };
+        if empty {
+            return Err(invalid("照合する文字がありません".to_string()));
+        }
+
+        Ok(Self { source: source.trim().to_string(), field, weight, negative, matcher })
+    }
+
+    /// 対象の文字に一致するか
+    pub fn is_match(&self, text: &PatternText) -> bool {
+        let target = match self.field {
+            PatternField::Any => &text.all,
+            PatternField::Ocr => &text.ocr,
+            PatternField::Scene => &text.scene,
+        };
+        match &self.matcher {
+            Matcher::Text(pattern) => target.contains(pattern.as_str()),
+            Matcher::Regex(re) => re.is_match(target),
+        }
+    }
+}
+
+/// 照合対象の文字（小文字化済み）
+#[derive(Debug, Clone, Default)]
+pub struct PatternText {
+    ocr: String,
+    scene: String,
+    all: String,
+}
+
+impl PatternText {
+    /// 項目を区別しない文字（`ocr:`・`scene:` のパターンもこの文字で照合する）
+    pub fn plain(text: &str) -> Self {
+        let text = text.to_lowercase();
+        Self { ocr: text.clone(), scene: text.clone(), all: text }
+    }
+
+    /// 項目ごとの文字（`extra` は項目を限定しないパターンだけが見る）
+    pub fn new(ocr: &str, scene: &str, extra: &str) -> Self {
+        Self {
+            ocr: ocr.to_lowercase(),
+            scene: scene.to_lowercase(),
+            all: format!("{} {} {}", ocr, scene, extra).to_lowercase(),
+        }
+    }
+
+    /// 解析結果の文字（OCR・情景説明・写真種別）
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        Self::new(&result.detected_text, &result.description, &result.photo_category)
+    }
+}
+
+/// 照合結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatternMatch {
+    /// 一致したパターンの重みの合計
+    pub score: u32,
+    /// 一致したパターン（元の表記）
+    pub hits: Vec<String>,
+}
+
+/// 1行分の検索パターン
+#[derive(Debug, Clone, Default)]
+pub struct PatternSet {
+    patterns: Vec<SearchPattern>,
+}
+
+impl PatternSet {
+    /// `|` 区切りの検索パターンを解釈する（不正なパターンは読み飛ばす）
+    pub fn parse(patterns: &str) -> Self {
+        Self { patterns: split_patterns(patterns).into_iter().filter_map(|p| SearchPattern::parse(p).ok()).collect() }
+    }
+
+    /// 不正なパターンのエラー一覧
+    pub fn errors(patterns: &str) -> Vec<crate::Error> {
+        split_patterns(patterns).into_iter().filter_map(|p| SearchPattern::parse(p).err()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn patterns(&self) -> &[SearchPattern] {
+        &self.patterns
+    }
+
+    /// 照合する。否定パターンが一致した場合・肯定パターンが1件も一致しない場合は `None`
+    pub fn find(&self, text: &PatternText) -> Option<PatternMatch> {
+        let mut result = PatternMatch::default();
+        for pattern in &self.patterns {
+            if !pattern.is_match(text) {
+                continue;
+            }
+            if pattern.negative {
+                return None;
+            }
+            result.score += pattern.weight;
+            result.hits.push(pattern.source.clone());
+        }
+        (result.score > 0).then_some(result)
+    }
+}
+
+/// 検索パターンを区切る（`|`・`,`・`、`。括弧の中と `\` の直後は区切らない）
+pub fn split_patterns(patterns: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in patterns.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '|' | ',' | '、' if depth == 0 => {
+                parts.push(&patterns[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&patterns[start..]);
+    parts.into_iter().map(str::trim).filter(|p| !p.is_empty()).collect()
+}
+
+/// 検索パターンを統合する（`base` の後ろに、ないものだけを追記）
+pub fn merge_patterns(base: &str, extra: &str) -> String {
+    let mut patterns = split_patterns(base);
+    for pattern in split_patterns(extra) {
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+    patterns.join("|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_keeps_regex_groups() {
+        assert_eq!(split_patterns("温度管理| 到着温度,敷均し"), vec!["温度管理", "到着温度", "敷均し"]);
+        assert_eq!(split_patterns("re:(乳剤|タック)散布|re:\\d{2,3}℃^2"), vec!["re:(乳剤|タック)散布", "re:\\d{2,3}℃^2"]);
+        assert_eq!(merge_patterns("re:(乳剤|タック)|散布", "散布|乳剤^2"), "re:(乳剤|タック)|散布|乳剤^2");
+    }
+
+    #[test]
+    fn test_parse_prefixes_and_weight() {
+        let p = SearchPattern::parse("!scene:re:養生砂.*散布^3").unwrap();
+        assert!(p.negative);
+        assert_eq!(p.field, PatternField::Scene);
+        assert_eq!(p.weight, 3);
+        assert!(SearchPattern::parse("re:(").is_err());
+        assert!(SearchPattern::parse("!").is_err());
+        // 末尾が数字でなければ ^ は文字として扱う
+        assert_eq!(SearchPattern::parse("a^b").unwrap().weight, 1);
+    }
+
+    #[test]
+    fn test_weighted_score_and_negative_veto() {
+        let emulsion = PatternSet::parse("散布|乳剤^3|!養生砂");
+        let text = PatternText::new("", "アスファルト乳剤散布状況", "");
+        let found = emulsion.find(&text).unwrap();
+        assert_eq!(found.score, 4);
+        assert_eq!(found.hits, vec!["散布", "乳剤^3"]);
+
+        let sand = PatternText::new("", "養生砂散布状況", "");
+        assert_eq!(emulsion.find(&sand), None);
+        assert_eq!(PatternSet::parse("温度").find(&sand), None);
+    }
+
+    #[test]
+    fn test_field_scope() {
+        let set = PatternSet::parse("ocr:到着温度");
+        assert!(set.find(&PatternText::new("到着温度 160℃", "", "")).is_some());
+        assert!(set.find(&PatternText::new("", "到着温度を測定", "")).is_none());
+        assert!(set.find(&PatternText::plain("到着温度")).is_some());
+        assert!(PatternSet::parse("re:^tack").find(&PatternText::plain("TACK coat")).is_some());
+    }
+}
@@ -4,8 +4,10 @@
 //! - PHOTO_CATEGORIES: 写真区分の定数
 //! - build_step1_prompt: Step1（画像認識）用プロンプト
 //! - build_single_step_prompt: 1ステップ解析用プロンプト
+//! - build_folder_hints_section: フォルダ構成から得た既知情報の追記
 
 use crate::hierarchy::HierarchyMaster;
+use crate::types::FolderHints;
 
 /// 写真区分（工種階層マスタの写真種別）
 pub const PHOTO_CATEGORIES: &[&str] = &[
@@ -81,6 +83,33 @@ pub fn build_step1_prompt(images: &[(&str, Option<&str>)]) -> String {
     )
 }
 
+/// フォルダ構成から得た既知情報のプロンプト追記部分を生成
+///
+/// ヒントのある写真がなければ空文字。各プロンプトの末尾に連結して使う。
+///
+/// # Arguments
+/// * `hints` - (ファイル名, フォルダヒント) のスライス
+pub fn build_folder_hints_section(hints: &[(&str, &FolderHints)]) -> String {
+    let hint_list = hints
+        .iter()
+        .filter(|(_, h)| !h.is_empty())
+        .map(|(name, h)| format!("- {}: {}", name, h.describe()))
+        .collect::<Vec<_>>();
+    if hint_list.is_empty() {
+        return String::new();
+    }
+
+    format!(
+        r#"
+
+## フォルダ構成からの既知情報（確定情報）
+以下は写真の保存フォルダ名から得た情報です。原則としてこの値を正として分類してください。
+黒板の記載など写真の内容と明らかに矛盾する場合のみ、その写真の出力に "hintConflict": "矛盾の内容" を追加してください。
+{}"#,
+        hint_list.join("\n")
+    )
+}
+
 /// 1ステップ解析プロンプト生成（工種指定版）
 ///
 /// 工種が既知の場合、画像認識と分類を1回のAI呼び出しで実行
@@ -289,4 +318,20 @@ mod tests {
         assert!(prompt.contains("施工状況写真"));
     }
 
+    #[test]
+    fn test_build_folder_hints_section() {
+        let hints = FolderHints {
+            work_type: "舗装工".to_string(),
+            station: "No.5".to_string(),
+            ..Default::default()
+        };
+        let empty = FolderHints::default();
+
+        let section = build_folder_hints_section(&[("a.jpg", &hints), ("b.jpg", &empty)]);
+        assert!(section.contains("- a.jpg: 工種=舗装工, 測点=No.5"));
+        assert!(!section.contains("b.jpg"));
+        assert!(section.contains("hintConflict"));
+
+        assert_eq!(build_folder_hints_section(&[("b.jpg", &empty)]), "");
+    }
 }
@@ -0,0 +1,277 @@
+//! 測点（No.N+X）の解釈・表記・並び
+//!
+//! 測点は自由入力の文字列（`No.10`, `NO.10+5`, `No10+5.0m`, `測点10`, `ＮＯ．１０`）で入ってくる。
+//! 追加距離（No.1 = 20m）に換算して数値で比べ、`No.10+5` の形に揃える。
+//! `No.5〜No.8` のような範囲も扱う。
+
+use crate::error::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// 測点間隔（m）。No.1 = 20m
+pub const STATION_INTERVAL: f64 = 20.0;
+
+/// 1測点あたりの 0.1m 単位の数
+const INTERVAL_TENTHS: i64 = (STATION_INTERVAL * 10.0) as i64;
+
+/// 範囲の区切り
+const RANGE_SEPARATORS: [&str; 4] = ["〜", "～", "~", "から"];
+
+/// 測点（追加距離を 0.1m 単位で持つ）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Station {
+    tenths: i64,
+}
+
+impl Station {
+    /// 追加距離（m）から作成（0.1m単位に丸め、負の値は0）
+    pub fn from_meters(meters: f64) -> Self {
+        Self { tenths: (meters.max(0.0) * 10.0).round() as i64 }
+    }
+
+    /// 追加距離（m）
+    pub fn meters(&self) -> f64 {
+        self.tenths as f64 / 10.0
+    }
+
+    /// 測点番号（No.N の N）
+    pub fn number(&self) -> i64 {
+        self.tenths / INTERVAL_TENTHS
+    }
+
+    /// 測点番号からの端数（m）
+    pub fn plus(&self) -> f64 {
+        (self.tenths % INTERVAL_TENTHS) as f64 / 10.0
+    }
+
+    /// 測点文字列を解釈
+    ///
+    /// `No.12+5.3` / `NO12+5` / `No10+5.0m` / `測点10` / `No.10-5`（手前へ5m）/ 全角表記を受け付ける。
+    /// 接頭辞のない数値（`245.3`）は追加距離（m）とみなす。
+    pub fn parse(s: &str) -> Option<Self> {
+        station_meters(&to_ascii(s)).map(Self::from_meters)
+    }
+}
+
+impl fmt::Display for Station {
+    /// `No.12` / `No.12+5` / `No.12+5.3`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plus = self.tenths % INTERVAL_TENTHS;
+        match (plus / 10, plus % 10) {
+            (0, 0) => write!(f, "No.{}", self.number()),
+            (m, 0) => write!(f, "No.{}+{}", self.number(), m),
+            (m, d) => write!(f, "No.{}+{}.{}", self.number(), m, d),
+        }
+    }
+}
+
+impl FromStr for Station {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s).ok_or_else(|| Error::Parse(format!("測点を解釈できません: {}", s)))
+    }
+}
+
+/// 測点の範囲（`No.5〜No.8`）。1点だけの測点は始点と終点が同じ範囲
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StationRange {
+    pub start: Station,
+    pub end: Station,
+}
+
+impl StationRange {
+    /// 範囲または1点の測点を解釈（始点と終点が逆なら入れ替える）
+    ///
+    /// 終点の接頭辞は省略できる（`No.5〜8` は `No.5〜No.8`）。`-` は後ろが測点なら範囲（`No.5-No.8`）。
+    /// `No.5-8` は範囲とも手前への距離とも読めるため解釈しない（`No.5-8m` は距離）
+    pub fn parse(s: &str) -> Option<Self> {
+        let (start, end) = range_meters(&to_ascii(s))?;
+        let (start, end) = (Station::from_meters(start), Station::from_meters(end));
+        Some(Self { start: start.min(end), end: start.max(end) })
+    }
+
+    /// 1点だけか
+    pub fn is_single(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// 範囲内か（両端を含む）
+    pub fn contains(&self, station: &Station) -> bool {
+        self.start <= *station && *station <= self.end
+    }
+}
+
+impl fmt::Display for StationRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_single() {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}〜{}", self.start, self.end)
+        }
+    }
+}
+
+impl FromStr for StationRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s).ok_or_else(|| Error::Parse(format!("測点の範囲を解釈できません: {}", s)))
+    }
+}
+
+/// 測点の値を正規の表記にする（解釈できない値・接頭辞のない数値は None）
+///
+/// 0.1m 単位で表せない値（`No.10+5.25`）も、書き直すと距離が変わるので None
+pub fn canonical_station(s: &str) -> Option<String> {
+    if s.trim().parse::<f64>().is_ok() {
+        return None;
+    }
+    let (start, end) = range_meters(&to_ascii(s))?;
+    let exact = |meters: f64| (Station::from_meters(meters).meters() - meters).abs() < 1e-6;
+    if !exact(start) || !exact(end) {
+        return None;
+    }
+    StationRange::parse(s).map(|range| range.to_string())
+}
+
+/// 全角英数字・記号を半角に、空白を除く
+fn to_ascii(s: &str) -> String {
+    s.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '−' | '‐' => '-',
+            '№' => '#',
+            _ => c,
+        })
+        .collect()
+}
+
+/// 接頭辞（`測点` / `No.` / `NO` / `№`）を除いた残り（接頭辞がなければ None）
+fn strip_prefix(s: &str) -> Option<&str> {
+    let (s, named) = match s.strip_prefix("測点") {
+        Some(rest) => (rest, true),
+        None => (s, false),
+    };
+    match ["NO", "No", "no", "nO", "#"].iter().find_map(|p| s.strip_prefix(p)) {
+        Some(rest) => Some(rest.trim_start_matches('.')),
+        // `測点10` のように番号だけのもの
+        None => named.then_some(s),
+    }
+}
+
+/// 半角にした測点の追加距離（m、丸める前）
+fn station_meters(s: &str) -> Option<f64> {
+    match strip_prefix(s) {
+        Some(body) => body_meters(body),
+        None if s.contains(['+', '-']) => body_meters(s),
+        None => s.parse::<f64>().ok().filter(|v| v.is_finite() && *v >= 0.0),
+    }
+}
+
+/// 半角にした範囲または1点の測点の始点・終点の追加距離（m、丸める前・書いた順）
+fn range_meters(text: &str) -> Option<(f64, f64)> {
+    let (start, end) = match RANGE_SEPARATORS.iter().find_map(|sep| text.split_once(sep)) {
+        Some(parts) => parts,
+        None => match text.split_once('-') {
+            Some((start, end)) if strip_prefix(end).is_some() => (start, end),
+            Some((_, end)) if !end.is_empty() && end.chars().all(|c| c.is_ascii_digit()) => return None,
+            _ => {
+                let meters = station_meters(text)?;
+                return Some((meters, meters));
+            }
+        },
+    };
+    let start = station_meters(start)?;
+    let end = match strip_prefix(end) {
+        Some(_) => station_meters(end)?,
+        None => body_meters(end)?,
+    };
+    Some((start, end))
+}
+
+/// `N` / `N+X` / `N-X`（末尾の `m` は無視）の追加距離（m）
+fn body_meters(body: &str) -> Option<f64> {
+    let body = body.trim_end_matches(['m', 'M']);
+    let (number, sign, plus) = match body.find(['+', '-']) {
+        Some(i) => (&body[..i], if body[i..].starts_with('-') { -1.0 } else { 1.0 }, body[i + 1..].parse::<f64>().ok()?),
+        None => (body, 1.0, 0.0),
+    };
+    let number: u32 = number.parse().ok()?;
+    let meters = number as f64 * STATION_INTERVAL + sign * plus;
+    (plus.is_finite() && meters >= 0.0).then_some(meters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_station() {
+        let meters = |s: &str| Station::parse(s).map(|st| st.meters());
+        assert_eq!(meters("No.12+5.3"), Some(245.3));
+        assert_eq!(meters("No.3"), Some(60.0));
+        assert_eq!(meters("no 2+10"), Some(50.0));
+        assert_eq!(meters("123.4"), Some(123.4));
+        assert_eq!(meters("abc"), None);
+
+        // よくある表記はすべて No.10+5
+        for s in ["NO.10+5", "No10+5.0m", "No.10 + 5", "ＮＯ．１０＋５", "測点10+5", "№10+5", "No.11-15"] {
+            assert_eq!(Station::parse(s).unwrap().to_string(), "No.10+5", "{}", s);
+        }
+        assert_eq!("測点10".parse::<Station>().unwrap().to_string(), "No.10");
+        assert!("No.".parse::<Station>().is_err());
+
+        assert_eq!(Station::from_meters(245.3).to_string(), "No.12+5.3");
+        assert_eq!(Station::from_meters(60.0).to_string(), "No.3");
+        assert_eq!(Station::from_meters(59.98).to_string(), "No.3");
+        assert_eq!(Station::from_meters(0.04).to_string(), "No.0");
+    }
+
+    #[test]
+    fn test_order_and_ranges() {
+        let mut stations: Vec<Station> = ["No.10", "No.9+15", "No.2", "No.10+0.5"].iter().map(|s| s.parse().unwrap()).collect();
+        stations.sort();
+        let sorted: Vec<String> = stations.iter().map(Station::to_string).collect();
+        assert_eq!(sorted, vec!["No.2", "No.9+15", "No.10", "No.10+0.5"]);
+
+        let range: StationRange = "No.8〜No.5".parse().unwrap();
+        assert_eq!(range.to_string(), "No.5〜No.8");
+        assert_eq!(StationRange::parse("NO5~8"), Some(range));
+        assert!(range.contains(&"No.6+10".parse().unwrap()));
+        assert!(!range.contains(&"No.8+0.1".parse().unwrap()));
+        assert!(StationRange::parse("No.5").unwrap().is_single());
+
+        assert_eq!(canonical_station("NO.5 ～ No.8").as_deref(), Some("No.5〜No.8"));
+        assert_eq!(canonical_station("No10+5.0m").as_deref(), Some("No.10+5"));
+        assert_eq!(canonical_station("10"), None);
+        assert_eq!(canonical_station("12月26日"), None);
+    }
+
+    #[test]
+    fn test_hyphen_ranges() {
+        // 後ろが測点なら範囲
+        assert_eq!(canonical_station("No.5-No.8").as_deref(), Some("No.5〜No.8"));
+        assert_eq!(canonical_station("測点8－測点5").as_deref(), Some("No.5〜No.8"));
+
+        // 範囲とも手前への距離とも読める値はそのまま
+        assert_eq!(StationRange::parse("No.5-8"), None);
+        assert_eq!(canonical_station("No.5-8"), None);
+        assert_eq!(canonical_station("5-8"), None);
+
+        // 単位・小数のある値は手前への距離
+        assert_eq!(canonical_station("No.5-8m").as_deref(), Some("No.4+12"));
+        assert_eq!(canonical_station("No.5-2.5").as_deref(), Some("No.4+17.5"));
+    }
+
+    #[test]
+    fn test_canonical_station_keeps_precision() {
+        // 0.1m 単位で表せない測量値は丸めずにそのまま
+        assert_eq!(canonical_station("No.10+5.25"), None);
+        assert_eq!(canonical_station("NO10+5.25m"), None);
+        assert_eq!(canonical_station("No.5〜No.8+0.05"), None);
+        assert_eq!(canonical_station("NO.10+5.30").as_deref(), Some("No.10+5.3"));
+        assert_eq!(canonical_station("No.12+5.3").as_deref(), Some("No.12+5.3"));
+    }
+}
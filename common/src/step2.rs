@@ -9,7 +9,7 @@ use crate::error::{Error, Result};
 use crate::hierarchy::HierarchyMaster;
 use crate::parser::extract_json;
 use crate::prompts::PHOTO_CATEGORIES;
-use crate::types::{AnalysisResult, RawImageData};
+use crate::types::{AnalysisResult, ExifMetadata, RawImageData};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -111,6 +111,7 @@ pub struct ImageMeta {
     pub file_name: String,
     pub file_path: String,  // WASMでは空文字可
     pub date: String,       // WASMでは空文字可
+    pub exif: ExifMetadata, // WASMでは未取得
 }
 
 /// Step1+Step2結果をマージしてAnalysisResult生成
@@ -143,6 +144,9 @@ pub fn merge_results(
             let date = img_info
                 .map(|i| i.date.clone())
                 .unwrap_or_default();
+            let exif = img_info
+                .map(|i| i.exif.clone())
+                .unwrap_or_default();
 
             AnalysisResult {
                 file_name: raw.file_name.clone(),
@@ -163,6 +167,10 @@ pub fn merge_results(
                 remarks_candidates: Vec::new(),
                 reasoning: step2.map(|s| s.reasoning.clone()).unwrap_or_default(),
                 focus_target: String::new(), // TODO: 1ステップ解析では出力される
+                exif,
+                hint_conflict: raw.hint_conflict.clone(),
+                warnings: Vec::new(),
+                qc_failures: Vec::new(),
             }
         })
         .collect()
@@ -300,6 +308,7 @@ mod tests {
             measurements: "160.4℃".to_string(),
             scene_description: "舗装工事".to_string(),
             photo_category: "到着温度".to_string(),
+            ..Default::default()
         }];
 
         let master = HierarchyMaster::default();
@@ -319,6 +328,7 @@ mod tests {
             measurements: "".to_string(),
             scene_description: "舗装工事".to_string(),
             photo_category: "".to_string(),
+            ..Default::default()
         }];
 
         let master = HierarchyMaster::default();
@@ -337,6 +347,7 @@ mod tests {
             measurements: "".to_string(),
             scene_description: "".to_string(),
             photo_category: "".to_string(),
+            ..Default::default()
         }];
 
         let master = HierarchyMaster::default();
@@ -410,6 +421,7 @@ mod tests {
                 measurements: "160.4℃".to_string(),
                 scene_description: "舗装作業".to_string(),
                 photo_category: "到着温度".to_string(),
+                ..Default::default()
             },
             RawImageData {
                 file_name: "test2.jpg".to_string(),
@@ -436,11 +448,13 @@ mod tests {
                 file_name: "test1.jpg".to_string(),
                 file_path: "/path/to/test1.jpg".to_string(),
                 date: "2025-01-18".to_string(),
+                ..Default::default()
             },
             ImageMeta {
                 file_name: "test2.jpg".to_string(),
                 file_path: "/path/to/test2.jpg".to_string(),
                 date: "2025-01-18".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -3,6 +3,8 @@
 //! CLIとWeb(WASM)で共有される型:
 //! - RawImageData: Step1（画像認識）の出力
 //! - AnalysisResult: 最終出力
+//! - ExifMetadata: 撮影メタデータ（向き・GPS・カメラ・画素数）
+//! - FolderHints: フォルダ構成から得た既知情報（日付・工種・測点等）
 
 use serde::{Deserialize, Serialize};
 
@@ -16,6 +18,116 @@ pub struct RawImageData {
     pub measurements: String,
     pub scene_description: String,
     pub photo_category: String,
+    /// フォルダ情報と写真内容の矛盾（AIが指摘した場合のみ）
+    pub hint_conflict: String,
+}
+
+/// 撮影メタデータ（EXIF）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExifMetadata {
+    /// EXIF Orientation（1〜8、0は未取得）
+    pub orientation: u16,
+    /// 緯度（度、南緯は負）
+    pub gps_latitude: Option<f64>,
+    /// 経度（度、西経は負）
+    pub gps_longitude: Option<f64>,
+    /// 高度（m、海面下は負）
+    pub gps_altitude: Option<f64>,
+    /// カメラメーカー
+    pub camera_make: String,
+    /// カメラ機種
+    pub camera_model: String,
+    /// 画素数（幅、回転前）
+    pub width: u32,
+    /// 画素数（高さ、回転前）
+    pub height: u32,
+    /// 撮影・編集ソフトウェア
+    pub software: String,
+}
+
+impl ExifMetadata {
+    /// 何も取得できていないか
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 表示時に縦横が入れ替わる向きか（90°/270°回転）
+    pub fn swaps_dimensions(&self) -> bool {
+        matches!(self.orientation, 5..=8)
+    }
+
+    /// 向きを適用した表示サイズ（幅, 高さ）
+    pub fn display_size(&self) -> (u32, u32) {
+        if self.swaps_dimensions() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    /// GPS座標（緯度, 経度）
+    pub fn gps(&self) -> Option<(f64, f64)> {
+        Some((self.gps_latitude?, self.gps_longitude?))
+    }
+}
+
+/// フォルダ構成から得た既知情報（例: `2025-01-18/舗装工/No.5/`）
+///
+/// 解析プロンプトにヒントとして渡し、AIが矛盾を指摘しない限り解析結果より優先する
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FolderHints {
+    pub date: String,
+    pub work_type: String,
+    pub variety: String,
+    pub subphase: String,
+    pub station: String,
+}
+
+impl FolderHints {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// プロンプト・ログ用の表記（例: `工種=舗装工, 測点=No.5`）
+    pub fn describe(&self) -> String {
+        [
+            ("日付", &self.date),
+            ("工種", &self.work_type),
+            ("種別", &self.variety),
+            ("作業段階", &self.subphase),
+            ("測点", &self.station),
+        ]
+        .iter()
+        .filter(|(_, v)| !v.is_empty())
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    /// 解析結果に反映
+    ///
+    /// 日付は EXIF がない場合のみ補完する。
+    /// AIが矛盾を指摘した写真（`hint_conflict` あり）は工種等を上書きしない
+    pub fn apply(&self, result: &mut AnalysisResult) {
+        if result.date.is_empty() && !self.date.is_empty() {
+            result.date = self.date.clone();
+        }
+        if !result.hint_conflict.is_empty() {
+            return;
+        }
+        for (hint, field) in [
+            (&self.work_type, &mut result.work_type),
+            (&self.variety, &mut result.variety),
+            (&self.subphase, &mut result.subphase),
+            (&self.station, &mut result.station),
+        ] {
+            if !hint.is_empty() {
+                *field = hint.clone();
+            }
+        }
+    }
 }
 
 /// AI解析結果
@@ -49,7 +161,7 @@ pub struct AnalysisResult {
     pub remarks: String,          // 備考
 
     #[serde(default)]
-    pub remarks_candidates: Vec<String>, // 備考候補（AIが提案）
+    pub remarks_candidates: Vec<String>, // 備考候補（AI・マスタ照合が提案）
 
     #[serde(default)]
     pub description: String,      // 写真説明
@@ -71,6 +183,22 @@ pub struct AnalysisResult {
 
     #[serde(default)]
     pub focus_target: String,     // 撮影対象（全景/黒板アップ/温度計アップ等）
+
+    /// 撮影メタデータ（EXIF）
+    #[serde(default, skip_serializing_if = "ExifMetadata::is_empty")]
+    pub exif: ExifMetadata,
+
+    /// フォルダ情報と写真内容の矛盾（AIが指摘した場合のみ）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hint_conflict: String,
+
+    /// 画質チェック（lint）の警告
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+
+    /// 品質規格（qc）を満たさない計測値
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub qc_failures: Vec<String>,
 }
 
 #[cfg(test)]
@@ -81,7 +209,7 @@ mod tests {
     fn test_analysis_result_default() {
         let result = AnalysisResult::default();
         assert_eq!(result.file_name, "");
-        assert_eq!(result.has_board, false);
+        assert!(!result.has_board);
     }
 
     #[test]
@@ -113,7 +241,7 @@ mod tests {
         assert_eq!(result.file_name, "photo.jpg");
         assert_eq!(result.work_type, "区画線工");
         assert_eq!(result.photo_category, "施工状況写真");
-        assert_eq!(result.has_board, false);
+        assert!(!result.has_board);
     }
 
     #[test]
@@ -124,7 +252,7 @@ mod tests {
         let result: AnalysisResult = serde_json::from_str(json).expect("デシリアライズ失敗");
         assert_eq!(result.file_name, "minimal.jpg");
         assert_eq!(result.work_type, ""); // デフォルト値
-        assert_eq!(result.has_board, false); // デフォルト値
+        assert!(!result.has_board); // デフォルト値
     }
 
     #[test]
@@ -155,6 +283,70 @@ mod tests {
         assert_eq!(original.photo_category, restored.photo_category);
     }
 
+    #[test]
+    fn test_exif_metadata_serialization() {
+        // 未取得なら出力しない（既存のresult.jsonと互換）
+        let plain = AnalysisResult { file_name: "a.jpg".to_string(), ..Default::default() };
+        let json = serde_json::to_string(&plain).expect("シリアライズ失敗");
+        assert!(!json.contains("exif"));
+
+        let rotated = AnalysisResult {
+            file_name: "b.jpg".to_string(),
+            exif: ExifMetadata {
+                orientation: 6,
+                gps_latitude: Some(35.68),
+                gps_longitude: Some(139.76),
+                camera_model: "TG-6".to_string(),
+                width: 4000,
+                height: 3000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&rotated).expect("シリアライズ失敗");
+        assert!(json.contains("\"orientation\":6"));
+        assert!(json.contains("\"gpsLatitude\":35.68"));
+
+        let restored: AnalysisResult = serde_json::from_str(&json).expect("デシリアライズ失敗");
+        assert_eq!(restored.exif, rotated.exif);
+        assert!(restored.exif.swaps_dimensions());
+        assert_eq!(restored.exif.display_size(), (3000, 4000));
+        assert_eq!(restored.exif.gps(), Some((35.68, 139.76)));
+    }
+
+    #[test]
+    fn test_folder_hints_apply() {
+        let hints = FolderHints {
+            date: "2025-01-18".to_string(),
+            work_type: "舗装工".to_string(),
+            station: "No.5".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(hints.describe(), "日付=2025-01-18, 工種=舗装工, 測点=No.5");
+
+        let mut result = AnalysisResult {
+            date: "2025-01-19 10:00:00".to_string(),
+            work_type: "区画線工".to_string(),
+            variety: "表層工".to_string(),
+            ..Default::default()
+        };
+        hints.apply(&mut result);
+        assert_eq!(result.date, "2025-01-19 10:00:00"); // EXIF優先
+        assert_eq!(result.work_type, "舗装工");
+        assert_eq!(result.variety, "表層工"); // ヒントなしはAIの値
+        assert_eq!(result.station, "No.5");
+
+        // AIが矛盾を指摘した写真は上書きしない
+        let mut conflicted = AnalysisResult {
+            work_type: "区画線工".to_string(),
+            hint_conflict: "黒板に区画線工と記載".to_string(),
+            ..Default::default()
+        };
+        hints.apply(&mut conflicted);
+        assert_eq!(conflicted.work_type, "区画線工");
+        assert_eq!(conflicted.date, "2025-01-18");
+    }
+
     // =============================================
     // RawImageData テスト
     // =============================================
@@ -176,6 +368,7 @@ mod tests {
             measurements: "160.4℃".to_string(),
             scene_description: "アスファルト舗装工事".to_string(),
             photo_category: "到着温度".to_string(),
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&raw).expect("シリアライズ失敗");
@@ -0,0 +1,540 @@
+//! 分類とマスタの整合チェック
+//!
+//! 解析結果の (写真種別, 工種, 種別, 細別, 備考) がマスタに存在するかを検査し、
+//! 存在しない場合は最も近い有効なチェーンを提案する。
+//! 近さは入力済みの階層ごとの編集距離の合計から、写真の文字に含まれる検索パターン1件につき
+//! [`PATTERN_HIT_WEIGHT`] を差し引いた値で並べる。
+//! CLI・Web・デスクトップで共通に使う。
+
+use crate::hierarchy::{HierarchyMaster, HierarchyRow};
+use crate::pattern::PatternText;
+use crate::types::AnalysisResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 1件あたりの提案数
+const MAX_SUGGESTIONS: usize = 3;
+
+/// 検索パターン1件の一致を編集距離何文字分とみなすか
+pub const PATTERN_HIT_WEIGHT: usize = 2;
+
+/// 分類のチェーン
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassificationChain {
+    pub photo_category: String,
+    pub work_type: String,
+    pub variety: String,
+    pub subphase: String,
+    pub remarks: String,
+}
+
+impl ClassificationChain {
+    /// 解析結果の分類
+    pub fn from_result(result: &AnalysisResult) -> Self {
+        Self {
+            photo_category: result.photo_category.clone(),
+            work_type: result.work_type.clone(),
+            variety: result.variety.clone(),
+            subphase: result.subphase.clone(),
+            remarks: result.remarks.clone(),
+        }
+    }
+
+    /// 解析結果に書き込む
+    pub fn apply_to(&self, result: &mut AnalysisResult) {
+        result.photo_category = self.photo_category.clone();
+        result.work_type = self.work_type.clone();
+        result.variety = self.variety.clone();
+        result.subphase = self.subphase.clone();
+        result.remarks = self.remarks.clone();
+    }
+
+    /// 表示用（空の階層は省略）
+    pub fn label(&self) -> String {
+        [&self.work_type, &self.variety, &self.subphase, &self.remarks]
+            .iter()
+            .filter(|s| !s.is_empty())
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+            .join(" > ")
+    }
+
+    fn levels(&self) -> [&str; 4] {
+        [&self.work_type, &self.variety, &self.subphase, &self.remarks]
+    }
+}
+
+/// 整合チェックで見つかった問題の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidationIssueKind {
+    /// マスタにない工種
+    UnknownWorkType,
+    /// 写真種別にない工種
+    PhotoCategoryMismatch,
+    /// 工種にない種別
+    InvalidVariety,
+    /// 種別にない細別
+    UnknownSubphase,
+    /// 細別にない備考
+    UnknownRemarks,
+}
+
+impl ValidationIssueKind {
+    /// 問題のある項目名
+    pub fn field(&self) -> &'static str {
+        match self {
+            ValidationIssueKind::UnknownWorkType | ValidationIssueKind::PhotoCategoryMismatch => "工種",
+            ValidationIssueKind::InvalidVariety => "種別",
+            ValidationIssueKind::UnknownSubphase => "細別",
+            ValidationIssueKind::UnknownRemarks => "備考",
+        }
+    }
+}
+
+/// 有効なチェーンの提案
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainSuggestion {
+    pub chain: ClassificationChain,
+    /// 入力済みの階層の編集距離の合計
+    pub distance: usize,
+    /// 写真の文字（OCR・情景説明・写真種別）に一致した検索パターンの重みの合計（否定パターンが一致した行は0）
+    pub pattern_hits: usize,
+    /// 自動適用してよい近さか（編集距離が異なる階層の入力の半分以下。検索パターンが一致すれば入力の長さ以下）
+    pub confident: bool,
+}
+
+/// 整合チェックの問題1件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub file_name: String,
+    pub kind: ValidationIssueKind,
+    /// マスタにない値
+    pub value: String,
+    /// 近い順の提案
+    pub suggestions: Vec<ChainSuggestion>,
+    /// 先頭の提案を自動適用したか
+    pub applied: bool,
+}
+
+impl ValidationIssue {
+    /// 表示用のメッセージ
+    pub fn message(&self) -> String {
+        let problem = match self.kind {
+            ValidationIssueKind::UnknownWorkType => format!("工種「{}」はマスタにありません", self.value),
+            ValidationIssueKind::PhotoCategoryMismatch => format!("工種「{}」は写真種別にありません", self.value),
+            ValidationIssueKind::InvalidVariety => format!("種別「{}」は工種にありません", self.value),
+            ValidationIssueKind::UnknownSubphase => format!("細別「{}」は種別にありません", self.value),
+            ValidationIssueKind::UnknownRemarks => format!("備考「{}」は細別にありません", self.value),
+        };
+        match (self.suggestions.first(), self.applied) {
+            (Some(best), true) => format!("{} → {} に修正", problem, best.chain.label()),
+            (Some(best), false) => format!("{}（候補: {}）", problem, best.chain.label()),
+            (None, _) => problem,
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.file_name, self.message())
+    }
+}
+
+/// 分類を検査し、最初に見つかった問題（上位の階層を優先）と提案を返す
+///
+/// `text` は検索パターンの照合に使う写真の文字（OCR・説明など）
+pub fn validate_chain(
+    chain: &ClassificationChain,
+    text: &PatternText,
+    master: &HierarchyMaster,
+) -> Option<(ValidationIssueKind, String, Vec<ChainSuggestion>)> {
+    let (kind, value) = find_issue(chain, master)?;
+    Some((kind, value, nearest_chains(chain, text, master)))
+}
+
+/// 解析結果を検査
+pub fn validate_result(result: &AnalysisResult, master: &HierarchyMaster) -> Option<ValidationIssue> {
+    let chain = ClassificationChain::from_result(result);
+    let (kind, value, suggestions) = validate_chain(&chain, &PatternText::from_result(result), master)?;
+    Some(ValidationIssue {
+        file_name: result.file_name.clone(),
+        kind,
+        value,
+        suggestions,
+        applied: false,
+    })
+}
+
+/// 解析結果をマスタに合わせて修正し、見つかった問題を返す
+///
+/// 1. 備考がマスタにある場合は、その備考を持つ行から階層を確定
+/// 2. 問題があれば、確度の高い提案を自動適用（なければ問題の階層以下を空にする）
+/// 3. 問題は `warnings` にも追記する
+pub fn correct_results(results: &mut [AnalysisResult], master: &HierarchyMaster) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for result in results.iter_mut() {
+        resolve_by_remarks(result, master);
+
+        let Some(mut issue) = validate_result(result, master) else {
+            continue;
+        };
+        match issue.suggestions.first() {
+            Some(best) if best.confident => {
+                best.chain.apply_to(result);
+                issue.applied = true;
+            }
+            _ => clear_from(result, issue.kind),
+        }
+        result.warnings.push(issue.message());
+        issues.push(issue);
+    }
+    issues
+}
+
+/// 問題の種類と値を特定
+fn find_issue(chain: &ClassificationChain, master: &HierarchyMaster) -> Option<(ValidationIssueKind, String)> {
+    let rows = master.rows();
+    let category_ok = |row: &HierarchyRow| chain.photo_category.is_empty() || row.photo_type == chain.photo_category;
+
+    if chain.work_type.is_empty() {
+        return None;
+    }
+    if !rows.iter().any(|row| row.work_type == chain.work_type) {
+        return Some((ValidationIssueKind::UnknownWorkType, chain.work_type.clone()));
+    }
+    if !rows.iter().any(|row| row.work_type == chain.work_type && category_ok(row)) {
+        return Some((ValidationIssueKind::PhotoCategoryMismatch, chain.work_type.clone()));
+    }
+    if chain.variety.is_empty() {
+        return None;
+    }
+    let in_variety = |row: &&HierarchyRow| {
+        row.work_type == chain.work_type && row.variety == chain.variety && category_ok(row)
+    };
+    if !rows.iter().any(|row| in_variety(&row)) {
+        return Some((ValidationIssueKind::InvalidVariety, chain.variety.clone()));
+    }
+    if chain.subphase.is_empty() {
+        return None;
+    }
+    if !rows.iter().filter(in_variety).any(|row| row.subphase == chain.subphase) {
+        return Some((ValidationIssueKind::UnknownSubphase, chain.subphase.clone()));
+    }
+    if chain.remarks.is_empty() {
+        return None;
+    }
+    let has_remarks = rows
+        .iter()
+        .filter(in_variety)
+        .any(|row| row.subphase == chain.subphase && row.remarks == chain.remarks);
+    if !has_remarks {
+        return Some((ValidationIssueKind::UnknownRemarks, chain.remarks.clone()));
+    }
+    None
+}
+
+/// 近い順に有効なチェーンを返す
+///
+/// 入力のある最も深い階層までを比較・提案する（備考が空なら備考は提案しない）
+pub fn nearest_chains(chain: &ClassificationChain, text: &PatternText, master: &HierarchyMaster) -> Vec<ChainSuggestion> {
+    let levels = chain.levels();
+    let depth = levels.iter().rposition(|v| !v.is_empty()).map_or(0, |i| i + 1);
+
+    let mut seen = HashSet::new();
+    let mut suggestions: Vec<ChainSuggestion> = master
+        .rows_with_patterns()
+        .filter(|(row, _)| !row.work_type.is_empty())
+        .filter_map(|(row, patterns)| {
+            let row_levels = [&row.work_type, &row.variety, &row.subphase, &row.remarks];
+            let mut candidate = ClassificationChain {
+                photo_category: if chain.photo_category.is_empty() { String::new() } else { row.photo_type.clone() },
+                ..Default::default()
+            };
+            for (i, value) in row_levels.iter().enumerate().take(depth) {
+                let slot = match i {
+                    0 => &mut candidate.work_type,
+                    1 => &mut candidate.variety,
+                    2 => &mut candidate.subphase,
+                    _ => &mut candidate.remarks,
+                };
+                *slot = (*value).clone();
+            }
+            if !seen.insert(candidate.clone()) {
+                return None;
+            }
+
+            // 異なる階層の入力文字数（確度の判定に使う）
+            let mut changed_len = 0;
+            let mut distance: usize = levels[..depth]
+                .iter()
+                .zip(candidate.levels())
+                .map(|(a, b)| {
+                    let d = edit_distance(a, b);
+                    if d > 0 {
+                        changed_len += a.chars().count();
+                    }
+                    d
+                })
+                .sum();
+            if !chain.photo_category.is_empty() && candidate.photo_category != chain.photo_category {
+                distance += 1;
+            }
+            let pattern_hits = patterns.find(text).map_or(0, |found| found.score as usize);
+            // 検索パターンの一致は許容する距離を広げるだけで、遠いチェーンは一致しても自動適用しない
+            let max_distance = if pattern_hits > 0 { changed_len } else { changed_len / 2 };
+            Some(ChainSuggestion {
+                confident: distance <= max_distance,
+                chain: candidate,
+                distance,
+                pattern_hits,
+            })
+        })
+        .collect();
+
+    let rank = |s: &ChainSuggestion| (s.distance as isize - (s.pattern_hits * PATTERN_HIT_WEIGHT) as isize, s.distance);
+    suggestions.sort_by_key(rank);
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+/// 文字単位の編集距離（レーベンシュタイン距離）
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != cb);
+            current[j + 1] = substitute.min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        prev = current;
+    }
+    prev[b.len()]
+}
+
+/// 行の列を取り出す関数
+type RowField = fn(&HierarchyRow) -> &str;
+
+/// 備考をマスタの行に当てはめたチェーン
+///
+/// その備考を持つ行のうち、入力済みの写真種別・工種・種別・細別が最も合う行の階層を返す（備考がマスタになければ `None`）
+pub fn chain_for_remarks(chain: &ClassificationChain, remarks: &str, master: &HierarchyMaster) -> Option<ClassificationChain> {
+    if remarks.is_empty() {
+        return None;
+    }
+    let mut candidates: Vec<&HierarchyRow> = master.rows().iter().filter(|row| row.remarks == remarks).collect();
+    let filters: [(&str, RowField); 4] = [
+        (&chain.photo_category, |row| &row.photo_type),
+        (&chain.work_type, |row| &row.work_type),
+        (&chain.variety, |row| &row.variety),
+        (&chain.subphase, |row| &row.subphase),
+    ];
+    for (value, field) in filters {
+        if value.is_empty() {
+            continue;
+        }
+        let filtered: Vec<&HierarchyRow> = candidates.iter().copied().filter(|row| field(row) == value).collect();
+        if !filtered.is_empty() {
+            candidates = filtered;
+        }
+    }
+    candidates.first().map(|row| ClassificationChain {
+        photo_category: row.photo_type.clone(),
+        work_type: row.work_type.clone(),
+        variety: row.variety.clone(),
+        subphase: row.subphase.clone(),
+        remarks: remarks.to_string(),
+    })
+}
+
+/// 備考を候補の1つに切り替える
+///
+/// 元の備考は候補の先頭に戻す。マスタがあれば切り替えた備考の行に合わせて上位の階層も直す
+pub fn switch_remarks(result: &mut AnalysisResult, remarks: &str, master: Option<&HierarchyMaster>) {
+    if result.remarks == remarks {
+        return;
+    }
+    let previous = std::mem::replace(&mut result.remarks, remarks.to_string());
+    result.remarks_candidates.retain(|candidate| candidate != remarks && *candidate != previous);
+    if !previous.is_empty() {
+        result.remarks_candidates.insert(0, previous);
+    }
+    if let Some(chain) = master.and_then(|master| chain_for_remarks(&ClassificationChain::from_result(result), remarks, master)) {
+        chain.apply_to(result);
+    }
+}
+
+/// 備考がマスタにある場合、その備考を持つ行のうち入力済みの階層が最も合う行で階層を確定
+fn resolve_by_remarks(result: &mut AnalysisResult, master: &HierarchyMaster) {
+    if let Some(chain) = chain_for_remarks(&ClassificationChain::from_result(result), &result.remarks, master) {
+        chain.apply_to(result);
+    }
+}
+
+/// 問題の階層以下を空にする
+fn clear_from(result: &mut AnalysisResult, kind: ValidationIssueKind) {
+    let level = match kind {
+        ValidationIssueKind::UnknownWorkType | ValidationIssueKind::PhotoCategoryMismatch => 0,
+        ValidationIssueKind::InvalidVariety => 1,
+        ValidationIssueKind::UnknownSubphase => 2,
+        ValidationIssueKind::UnknownRemarks => 3,
+    };
+    let fields = [&mut result.work_type, &mut result.variety, &mut result.subphase, &mut result.remarks];
+    for field in fields.into_iter().skip(level) {
+        field.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASTER_CSV: &str = "写真区分,写真種別,工種,種別,細別,備考,検索パターン
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,到着温度,到着温度|出荷温度
+直接工事費,品質管理写真,舗装工,舗装打換え工,表層工,敷均し温度,敷均し温度
+直接工事費,品質管理写真,舗装工,舗装打換え工,上層路盤工,現場密度測定,密度測定|RI計器
+直接工事費,施工状況写真,区画線工,区画線工,溶融式区画線,設置状況,区画線
+";
+
+    fn master() -> HierarchyMaster {
+        HierarchyMaster::from_csv_str(MASTER_CSV).unwrap()
+    }
+
+    fn result(work_type: &str, variety: &str, subphase: &str, remarks: &str) -> AnalysisResult {
+        AnalysisResult {
+            file_name: "a.jpg".to_string(),
+            photo_category: "品質管理写真".to_string(),
+            work_type: work_type.to_string(),
+            variety: variety.to_string(),
+            subphase: subphase.to_string(),
+            remarks: remarks.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("表層工", "表層工"), 0);
+        assert_eq!(edit_distance("舗装打替え工", "舗装打換え工"), 1);
+        assert_eq!(edit_distance("", "工種"), 2);
+    }
+
+    #[test]
+    fn test_valid_chain_has_no_issue() {
+        assert!(validate_result(&result("舗装工", "舗装打換え工", "表層工", "到着温度"), &master()).is_none());
+        assert!(validate_result(&result("舗装工", "", "", ""), &master()).is_none());
+    }
+
+    #[test]
+    fn test_issue_kinds() {
+        let master = master();
+        let kind = |r: AnalysisResult| validate_result(&r, &master).unwrap().kind;
+        assert_eq!(kind(result("土工", "", "", "")), ValidationIssueKind::UnknownWorkType);
+        assert_eq!(kind(result("区画線工", "", "", "")), ValidationIssueKind::PhotoCategoryMismatch);
+        assert_eq!(kind(result("舗装工", "舗装打替え工", "表層工", "")), ValidationIssueKind::InvalidVariety);
+        assert_eq!(kind(result("舗装工", "舗装打換え工", "基層工", "")), ValidationIssueKind::UnknownSubphase);
+        assert_eq!(kind(result("舗装工", "舗装打換え工", "表層工", "温度測定")), ValidationIssueKind::UnknownRemarks);
+    }
+
+    #[test]
+    fn test_nearest_chain_uses_distance_then_patterns() {
+        let master = master();
+        let issue = validate_result(&result("舗装工", "舗装打替え工", "表層工", ""), &master).unwrap();
+        let best = &issue.suggestions[0];
+        assert_eq!(best.chain.label(), "舗装工 > 舗装打換え工 > 表層工");
+        assert_eq!(best.distance, 1);
+        assert!(best.confident);
+
+        // 編集距離が少し遠くても、検索パターンが一致する備考を優先
+        let mut r = result("舗装工", "舗装打換え工", "表層工", "温度");
+        r.detected_text = "敷均し温度 145℃".to_string();
+        let issue = validate_result(&r, &master).unwrap();
+        assert_eq!(issue.suggestions[0].chain.remarks, "敷均し温度");
+        assert_eq!(issue.suggestions[0].pattern_hits, 1);
+    }
+
+    #[test]
+    fn test_far_chain_with_incidental_pattern_hit_is_not_applied() {
+        let master = master();
+        // 表層工の写真に RI計器 が写り込んでいても、上層路盤工の備考には直さない
+        let mut results = vec![result("舗装工", "舗装打換え工", "表層工", "温度")];
+        results[0].detected_text = "RI計器".to_string();
+        let issues = correct_results(&mut results, &master);
+
+        let far = issues[0]
+            .suggestions
+            .iter()
+            .find(|s| s.chain.remarks == "現場密度測定")
+            .unwrap();
+        assert_eq!(far.pattern_hits, 1);
+        assert!(!far.confident);
+        assert!(!issues[0].applied);
+        assert_eq!(results[0].subphase, "表層工");
+        assert_eq!(results[0].remarks, "");
+    }
+
+    #[test]
+    fn test_nearest_chain_respects_pattern_scope() {
+        let master = HierarchyMaster::from_csv_str(
+            "写真区分,写真種別,工種,種別,細別,備考,検索パターン
+直接工事費,品質管理写真,舗装工,舗装打換え工,上層路盤工,現場密度測定,ocr:密度測定
+",
+        )
+        .unwrap();
+        let chain = ClassificationChain::from_result(&result("舗装工", "舗装打換え工", "上層路盤工", "密度"));
+
+        // `ocr:` のパターンは情景説明の文字には一致しない
+        let mut r = result("", "", "", "");
+        r.description = "密度測定の状況".to_string();
+        let suggestions = nearest_chains(&chain, &PatternText::from_result(&r), &master);
+        assert_eq!(suggestions[0].pattern_hits, 0);
+
+        r.detected_text = "密度測定 No.3".to_string();
+        let suggestions = nearest_chains(&chain, &PatternText::from_result(&r), &master);
+        assert_eq!(suggestions[0].pattern_hits, 1);
+    }
+
+    #[test]
+    fn test_correct_results_applies_or_clears() {
+        let master = master();
+        let mut results = vec![
+            result("舗装工", "舗装打替え工", "表層工", ""),
+            result("舗装工", "舗装打換え工", "全く違う段階名", ""),
+            result("", "", "", "現場密度測定"),
+        ];
+        let issues = correct_results(&mut results, &master);
+
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].applied);
+        assert_eq!(results[0].variety, "舗装打換え工");
+        assert_eq!(results[0].warnings.len(), 1);
+        assert!(!issues[1].applied);
+        assert_eq!(results[1].variety, "舗装打換え工");
+        assert_eq!(results[1].subphase, "");
+        // 備考から階層を確定
+        assert_eq!(results[2].subphase, "上層路盤工");
+        assert!(results[2].warnings.is_empty());
+    }
+
+    #[test]
+    fn test_switch_remarks_swaps_candidate_and_chain() {
+        let master = master();
+        let mut r = result("舗装工", "舗装打換え工", "表層工", "到着温度");
+        r.remarks_candidates = vec!["現場密度測定".to_string(), "敷均し温度".to_string()];
+
+        switch_remarks(&mut r, "現場密度測定", Some(&master));
+        assert_eq!(r.remarks, "現場密度測定");
+        assert_eq!(r.subphase, "上層路盤工");
+        assert_eq!(r.remarks_candidates, vec!["到着温度", "敷均し温度"]);
+
+        // マスタなしでは備考だけを入れ替える
+        switch_remarks(&mut r, "到着温度", None);
+        assert_eq!(r.remarks, "到着温度");
+        assert_eq!(r.subphase, "上層路盤工");
+        assert_eq!(r.remarks_candidates, vec!["現場密度測定", "敷均し温度"]);
+    }
+}
@@ -10,6 +10,7 @@ use image::ImageReader;
 use crate::io::{default_sorted_path, load_result_items, save_sorted_items};
 use crate::model::{AppState, ResultItem};
 use photo_ai_common::layout::LAYOUT_FIELDS;
+use photo_ai_common::{ClassificationChain, HierarchyMaster, correct_results, validate_result};
 
 const DETAIL_FIELDS: &[(&str, &str)] = &[
     ("file_name", "File Name"),
@@ -28,6 +29,8 @@ const DETAIL_FIELDS: &[(&str, &str)] = &[
     ("reasoning", "Reasoning"),
 ];
 
+const WARNING_COLOR: Color32 = Color32::from_rgb(245, 158, 11);
+
 pub struct DesktopApp {
     state: AppState,
     drag_index: Option<usize>,
@@ -118,9 +121,97 @@ impl DesktopApp {
         self.thumbs.clear();
         self.thumb_inflight.clear();
         self.pending_thumbs.clear();
+        self.check_master();
         Ok(())
     }
 
+    fn open_master(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Master", &["csv", "xlsx", "xlsm", "json"])
+            .pick_file()
+        {
+            match HierarchyMaster::from_path(&path) {
+                Ok(master) => {
+                    self.state.master = Some(master);
+                    self.state.master_path = Some(path.clone());
+                    self.check_master();
+                    self.status = format!(
+                        "Loaded master {} ({} issues)",
+                        path.display(),
+                        self.state.issues.len()
+                    );
+                }
+                Err(err) => self.status = format!("Master load failed: {err}"),
+            }
+        }
+    }
+
+    /// Checks every item against the master the same way the CLI does (`correct_results`).
+    ///
+    /// Only confident corrections are taken back; other issues are shown without clearing
+    /// the user's fields. Issues are kept in `state.issues` instead of `warnings` so that
+    /// re-checking does not pile up messages.
+    fn check_master(&mut self) {
+        self.state.issues.clear();
+        for index in 0..self.state.items.len() {
+            self.check_item(index);
+        }
+    }
+
+    /// Checks one item against the master and takes back its confident correction.
+    fn check_item(&mut self, index: usize) {
+        let (Some(master), Some(item)) = (&self.state.master, self.state.items.get_mut(index)) else {
+            return;
+        };
+        let mut results = vec![item.to_result()];
+        let issue = correct_results(&mut results, master).pop();
+        if issue.as_ref().is_none_or(|issue| issue.applied) {
+            let chain = ClassificationChain::from_result(&results[0]);
+            if item.chain() != chain {
+                item.apply_chain(&chain);
+                self.state.dirty = true;
+            }
+        }
+        match issue {
+            Some(issue) => self.state.issues.insert(item.file_name.clone(), issue),
+            None => self.state.issues.remove(&item.file_name),
+        };
+    }
+
+    /// Lists the issues of the current items without changing them.
+    fn refresh_issues(&mut self) {
+        self.state.issues.clear();
+        let Some(master) = &self.state.master else {
+            return;
+        };
+        self.state.issues = self
+            .state
+            .items
+            .iter()
+            .filter_map(|item| validate_result(&item.to_result(), master))
+            .map(|issue| (issue.file_name.clone(), issue))
+            .collect();
+    }
+
+    fn apply_suggestion(&mut self, index: usize, chain: &ClassificationChain) {
+        let Some(item) = self.state.items.get_mut(index) else {
+            return;
+        };
+        item.apply_chain(chain);
+        self.state.issues.remove(&item.file_name);
+        self.state.dirty = true;
+    }
+
+    fn switch_remarks(&mut self, index: usize, remarks: &str) {
+        let Some(item) = self.state.items.get_mut(index) else {
+            return;
+        };
+        item.switch_remarks(remarks, self.state.master.as_ref());
+        self.state.dirty = true;
+        self.check_item(index);
+        self.status = format!("Remarks: {remarks}");
+    }
+
     fn save_sorted(&mut self) {
         let Some(source) = &self.state.source_path else {
             self.status = "No source file loaded".to_string();
@@ -149,6 +240,7 @@ impl DesktopApp {
     fn reset_order(&mut self) {
         self.state.items = self.state.original_items.clone();
         self.state.dirty = false;
+        self.refresh_issues();
     }
 
     fn move_item(&mut self, from: usize, to: usize) {
@@ -250,6 +342,9 @@ impl DesktopApp {
                             ui.end_row();
                         }
                     });
+                    if let Some(issue) = self.state.issues.get(&item.file_name) {
+                        ui.label(RichText::new(format!("⚠ {}", issue.message())).color(WARNING_COLOR).size(12.0));
+                    }
                 });
             });
         });
@@ -287,7 +382,7 @@ impl DesktopApp {
         }
     }
 
-    fn render_details(&self, ui: &mut egui::Ui) {
+    fn render_details(&mut self, ui: &mut egui::Ui) {
         let Some(index) = self.state.selected_index else {
             ui.label("Select a card to see details.");
             return;
@@ -309,6 +404,37 @@ impl DesktopApp {
             });
         });
 
+        let mut chosen = None;
+        if let Some(issue) = self.state.issues.get(&item.file_name) {
+            ui.group(|ui| {
+                ui.label(RichText::new("Master Check").strong());
+                ui.label(RichText::new(format!("⚠ {}", issue.message())).color(WARNING_COLOR));
+                for suggestion in &issue.suggestions {
+                    if item.chain() == suggestion.chain {
+                        continue;
+                    }
+                    let text = format!("Apply: {} (distance {}, hits {})", suggestion.chain.label(), suggestion.distance, suggestion.pattern_hits);
+                    if ui.button(text).clicked() {
+                        chosen = Some(suggestion.chain.clone());
+                    }
+                }
+            });
+        }
+
+        let mut picked = None;
+        if !item.remarks_candidates.is_empty() {
+            ui.group(|ui| {
+                ui.label(RichText::new("Remarks Candidates").strong());
+                ui.horizontal_wrapped(|ui| {
+                    for candidate in &item.remarks_candidates {
+                        if ui.button(candidate).on_hover_text("Switch remarks").clicked() {
+                            picked = Some(candidate.clone());
+                        }
+                    }
+                });
+            });
+        }
+
         for (field, label) in DETAIL_FIELDS {
             if *field == "file_name" || *field == "file_path" {
                 continue;
@@ -339,6 +465,13 @@ impl DesktopApp {
                 }
             });
         }
+
+        if let Some(chain) = chosen {
+            self.apply_suggestion(index, &chain);
+        }
+        if let Some(remarks) = picked {
+            self.switch_remarks(index, &remarks);
+        }
     }
 
     fn run_export(&mut self, format: ExportFormat) {
@@ -564,6 +697,10 @@ impl eframe::App for DesktopApp {
                         self.reload_json();
                         ui.close_menu();
                     }
+                    if ui.button("Open Master").clicked() {
+                        self.open_master();
+                        ui.close_menu();
+                    }
                     let save_enabled = !self.state.items.is_empty();
                     if ui.add_enabled(save_enabled, egui::Button::new("Save Sorted")).clicked() {
                         self.save_sorted();
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use photo_ai_common::{AnalysisResult, ClassificationChain, HierarchyMaster, ValidationIssue};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -18,6 +21,62 @@ pub struct ResultItem {
     pub detected_text: String,
     pub has_board: bool,
     pub reasoning: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remarks_candidates: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl ResultItem {
+    pub fn chain(&self) -> ClassificationChain {
+        ClassificationChain {
+            photo_category: self.photo_category.clone(),
+            work_type: self.work_type.clone(),
+            variety: self.variety.clone(),
+            subphase: self.subphase.clone(),
+            remarks: self.remarks.clone(),
+        }
+    }
+
+    pub fn apply_chain(&mut self, chain: &ClassificationChain) {
+        self.photo_category = chain.photo_category.clone();
+        self.work_type = chain.work_type.clone();
+        self.variety = chain.variety.clone();
+        self.subphase = chain.subphase.clone();
+        self.remarks = chain.remarks.clone();
+    }
+
+    /// Switch remarks to one of the candidates; the previous remarks go back to the front of the list.
+    /// With a master, the upper levels follow the row that has the new remarks.
+    pub fn switch_remarks(&mut self, remarks: &str, master: Option<&HierarchyMaster>) {
+        let mut result = self.to_result();
+        photo_ai_common::switch_remarks(&mut result, remarks, master);
+        self.apply_chain(&ClassificationChain::from_result(&result));
+        self.remarks_candidates = result.remarks_candidates;
+    }
+
+    /// The item as an analysis result, for the master checks shared with the CLI.
+    pub fn to_result(&self) -> AnalysisResult {
+        AnalysisResult {
+            file_name: self.file_name.clone(),
+            file_path: self.file_path.clone(),
+            date: self.date.clone(),
+            photo_category: self.photo_category.clone(),
+            work_type: self.work_type.clone(),
+            variety: self.variety.clone(),
+            subphase: self.subphase.clone(),
+            remarks: self.remarks.clone(),
+            remarks_candidates: self.remarks_candidates.clone(),
+            station: self.station.clone(),
+            description: self.description.clone(),
+            measurements: self.measurements.clone(),
+            detected_text: self.detected_text.clone(),
+            has_board: self.has_board,
+            reasoning: self.reasoning.clone(),
+            warnings: self.warnings.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -27,4 +86,8 @@ pub struct AppState {
     pub selected_index: Option<usize>,
     pub source_path: Option<std::path::PathBuf>,
     pub dirty: bool,
+    pub master: Option<HierarchyMaster>,
+    pub master_path: Option<std::path::PathBuf>,
+    /// Master check issues keyed by file name.
+    pub issues: HashMap<String, ValidationIssue>,
 }
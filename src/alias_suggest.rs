@@ -0,0 +1,285 @@
+//! 人の修正からエイリアスを提案する（`alias suggest`）
+//!
+//! AIの出力（解析直後の result.json）と人が直した結果（デスクトップ版の result.sorted.json など）を
+//! ファイル名で対応付け、項目ごとに「AIの値 → 直した値」を数える。同じ直し方が繰り返されていれば
+//! 完全一致の規則（`re:^…$`）として提案し、`--alias` でそのまま読めるエイリアスJSONにする。
+//!
+//! 修正ログ（`{"fileName", "field", "before", "after"}` のJSON配列またはJSON Lines）も読める。
+
+use crate::analyzer::AnalysisResult;
+use crate::error::{PhotoAiError, Result};
+use photo_ai_common::{AliasConfig, AliasField, AliasRule};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// 修正の読み込み元
+#[derive(Debug, Clone, PartialEq)]
+pub enum CorrectionSource {
+    /// AIの結果ファイルと直した結果ファイル（`AI.json=EDITED.json`）
+    Pair { original: PathBuf, edited: PathBuf },
+    /// 修正ログ、またはデスクトップ版の `*.sorted.json`（同じフォルダの `*.json` と比べる）
+    File(PathBuf),
+}
+
+impl std::str::FromStr for CorrectionSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((original, edited)) if !original.trim().is_empty() && !edited.trim().is_empty() => Ok(Self::Pair {
+                original: PathBuf::from(original.trim()),
+                edited: PathBuf::from(edited.trim()),
+            }),
+            Some(_) => Err(format!("比較するファイルは AI.json=EDITED.json の形式で指定: {}", s)),
+            None => Ok(Self::File(PathBuf::from(s))),
+        }
+    }
+}
+
+impl std::fmt::Display for CorrectionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pair { original, edited } => write!(f, "{} → {}", original.display(), edited.display()),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// 修正ログの1件
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Correction {
+    #[serde(default)]
+    pub file_name: String,
+    pub field: AliasField,
+    pub before: String,
+    pub after: String,
+}
+
+/// AIの値と最終的な値の組（直していないものも含む）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    pub field: AliasField,
+    pub before: String,
+    pub after: String,
+}
+
+impl From<Correction> for Observation {
+    fn from(c: Correction) -> Self {
+        Self { field: c.field, before: c.before, after: c.after }
+    }
+}
+
+/// 提案するエイリアス1件
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasSuggestion {
+    pub field: AliasField,
+    pub from: String,
+    pub to: String,
+    /// この直し方をした件数
+    pub support: usize,
+    /// AIがこの値を出した件数（直さなかったものを含む）
+    pub seen: usize,
+}
+
+impl AliasSuggestion {
+    /// 完全一致で置き換える規則
+    pub fn rule(&self) -> AliasRule {
+        AliasRule {
+            name: format!("{}: {} → {}", self.field, self.from, self.to),
+            field: self.field,
+            from: format!("re:^{}$", regex::escape(&self.from)),
+            to: self.to.replace('$', "$$"),
+            when: BTreeMap::new(),
+            priority: 0,
+        }
+    }
+}
+
+/// 結果ファイルを比べる（ファイル名で対応付け、AIの値が空の項目は除く）
+pub fn compare(original: &[AnalysisResult], edited: &[AnalysisResult]) -> Vec<Observation> {
+    let by_name: BTreeMap<&str, &AnalysisResult> = original.iter().map(|r| (r.file_name.as_str(), r)).collect();
+    let mut observations = Vec::new();
+    for after in edited {
+        let Some(before) = by_name.get(after.file_name.as_str()) else {
+            continue;
+        };
+        for field in AliasField::ALL {
+            let value = field.get(before);
+            if value.is_empty() {
+                continue;
+            }
+            observations.push(Observation { field, before: value.to_string(), after: field.get(after).to_string() });
+        }
+    }
+    observations
+}
+
+/// 修正ログを読む（JSON配列またはJSON Lines）
+pub fn parse_log(content: &str) -> Result<Vec<Correction>> {
+    let content = content.trim_start_matches('\u{feff}').trim();
+    if content.starts_with('[') {
+        return Ok(serde_json::from_str(content)?);
+    }
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+fn read_results(path: &Path) -> Result<Vec<AnalysisResult>> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// デスクトップ版の `*.sorted.json` に対応するAIの結果ファイル
+fn sorted_original(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let stem = name.strip_suffix(".sorted.json")?;
+    let original = path.with_file_name(format!("{}.json", stem));
+    original.is_file().then_some(original)
+}
+
+/// 読み込み元から値の組を集める
+pub fn read_source(source: &CorrectionSource) -> Result<Vec<Observation>> {
+    match source {
+        CorrectionSource::Pair { original, edited } => Ok(compare(&read_results(original)?, &read_results(edited)?)),
+        CorrectionSource::File(path) => {
+            if let Some(original) = sorted_original(path) {
+                return Ok(compare(&read_results(&original)?, &read_results(path)?));
+            }
+            let content = std::fs::read_to_string(path)?;
+            let log = parse_log(&content).map_err(|_| {
+                PhotoAiError::Config(format!(
+                    "{} は修正ログとして読めません（結果ファイルは AI.json=EDITED.json で指定）",
+                    path.display()
+                ))
+            })?;
+            Ok(log.into_iter().map(Observation::from).collect())
+        }
+    }
+}
+
+/// 同じ直し方が `min_support` 件以上あり、AIがその値を出したうちの過半数で直されていれば提案する
+///
+/// 変換できない項目（日付・計測値）の修正は、エイリアスにできないので数えない
+pub fn suggest(observations: &[Observation], min_support: usize) -> Vec<AliasSuggestion> {
+    // (項目, AIの値) → 最終的な値ごとの件数
+    let mut groups: BTreeMap<(AliasField, &str), BTreeMap<&str, usize>> = BTreeMap::new();
+    for o in observations.iter().filter(|o| !o.before.is_empty() && AliasField::ALL.contains(&o.field)) {
+        *groups.entry((o.field, &o.before)).or_default().entry(&o.after).or_default() += 1;
+    }
+
+    let mut suggestions: Vec<AliasSuggestion> = groups
+        .into_iter()
+        .filter_map(|((field, from), afters)| {
+            let seen: usize = afters.values().sum();
+            // 件数が同じなら値の順（BTreeMap の順）で先のもの
+            let (to, support) = afters
+                .iter()
+                .filter(|(after, _)| !after.is_empty() && **after != from)
+                .fold(None, |best: Option<(&str, usize)>, (after, count)| match best {
+                    Some((_, n)) if n >= *count => best,
+                    _ => Some((after, *count)),
+                })?;
+            (support >= min_support.max(1) && support * 2 > seen).then(|| AliasSuggestion {
+                field,
+                from: from.to_string(),
+                to: to.to_string(),
+                support,
+                seen,
+            })
+        })
+        .collect();
+    suggestions.sort_by(|a, b| a.field.cmp(&b.field).then(b.support.cmp(&a.support)).then_with(|| a.from.cmp(&b.from)));
+    suggestions
+}
+
+/// 提案をエイリアスJSONの設定にする
+pub fn to_config(suggestions: &[AliasSuggestion]) -> AliasConfig {
+    AliasConfig { rules: suggestions.iter().map(AliasSuggestion::rule).collect(), ..Default::default() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(file_name: &str, subphase: &str, remarks: &str) -> AnalysisResult {
+        AnalysisResult {
+            file_name: file_name.to_string(),
+            subphase: subphase.to_string(),
+            remarks: remarks.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_suggest_from_result_pairs() {
+        let original = vec![
+            result("1.jpg", "表層", "温度測定"),
+            result("2.jpg", "表層", "温度測定"),
+            result("3.jpg", "表層", "温度測定"),
+            result("4.jpg", "基層", "温度測定"),
+        ];
+        let edited = vec![
+            result("1.jpg", "表層工", "到着温度"),
+            result("2.jpg", "表層工", "敷均し温度"),
+            result("3.jpg", "表層", "温度測定"),
+            result("4.jpg", "基層工", "温度測定"),
+        ];
+
+        let observations = compare(&original, &edited);
+        let suggestions = suggest(&observations, 2);
+        // 備考は直し方がばらばら、基層は1件だけなので提案しない
+        assert_eq!(
+            suggestions,
+            vec![AliasSuggestion {
+                field: AliasField::Subphase,
+                from: "表層".to_string(),
+                to: "表層工".to_string(),
+                support: 2,
+                seen: 3,
+            }]
+        );
+        assert_eq!(suggest(&observations, 1).len(), 2);
+    }
+
+    #[test]
+    fn test_log_and_written_config_round_trip() {
+        let log = parse_log(
+            "{\"fileName\":\"a.jpg\",\"field\":\"station\",\"before\":\"No.1(+5)\",\"after\":\"No.1+5\"}\n\
+             {\"fileName\":\"b.jpg\",\"field\":\"station\",\"before\":\"No.1(+5)\",\"after\":\"No.1+5\"}\n\
+             {\"fileName\":\"a.jpg\",\"field\":\"measurements\",\"before\":\"150度\",\"after\":\"150℃\"}\n\
+             {\"fileName\":\"b.jpg\",\"field\":\"measurements\",\"before\":\"150度\",\"after\":\"150℃\"}\n",
+        )
+        .unwrap();
+        assert_eq!(parse_log(r#"[{"field":"work_type","before":"舗装","after":"舗装工"}]"#).unwrap().len(), 1);
+
+        let observations: Vec<Observation> = log.into_iter().map(Observation::from).collect();
+        let config = to_config(&suggest(&observations, 2));
+        // 計測値の修正はエイリアスにできないので提案しない
+        assert_eq!(config.rules.len(), 1);
+        let json = serde_json::to_string_pretty(&config).unwrap();
+        let loaded = AliasConfig::from_json(&json).unwrap();
+
+        // 正規表現の記号は文字として扱い、完全一致だけを置き換える
+        let apply = |station: &str| loaded.apply(&AnalysisResult { station: station.to_string(), ..Default::default() }).station;
+        assert_eq!(apply("No.1(+5)"), "No.1+5");
+        assert_eq!(apply("No.1(+5) 付近"), "No.1(+5) 付近");
+    }
+
+    #[test]
+    fn test_sorted_file_is_paired_with_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("result.json");
+        let sorted = dir.path().join("result.sorted.json");
+        std::fs::write(&original, serde_json::to_string(&vec![result("1.jpg", "表層", "")]).unwrap()).unwrap();
+        std::fs::write(&sorted, serde_json::to_string(&vec![result("1.jpg", "表層工", "")]).unwrap()).unwrap();
+
+        let source: CorrectionSource = sorted.to_str().unwrap().parse().unwrap();
+        let observations = read_source(&source).unwrap();
+        assert_eq!(observations[0], Observation { field: AliasField::Subphase, before: "表層".to_string(), after: "表層工".to_string() });
+        assert!("=b.json".parse::<CorrectionSource>().is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! 道路中心線（線形）による測点推定
+//!
+//! 測点と緯度経度の対応表（CSV）から中心線のポリラインを作り、
+//! 写真の EXIF GPS 位置を射影して `No.12+5.3` 形式の測点を求める。
+//! 中心線から離れすぎた写真は推定しない（GPS誤差・別現場の混入対策）。
+//!
+//! ## CSV形式
+//! ```text
+//! station,lat,lon
+//! No.0,35.681200,139.767100
+//! No.1,35.681380,139.767150
+//! 40,35.681560,139.767200
+//! ```
+//! 測点列は `No.N` / `No.N+X` 形式またはメートル単位の追加距離。
+
+use crate::analyzer::AnalysisResult;
+use crate::error::{PhotoAiError, Result};
+use photo_ai_common::Station;
+use std::path::Path;
+
+/// 中心線からの許容距離のデフォルト（m）
+pub const DEFAULT_MAX_OFFSET: f64 = 30.0;
+
+/// 地球半径（m）
+const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/// 中心線上の測量点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlignmentPoint {
+    /// 追加距離（m）
+    pub chainage: f64,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// 道路中心線
+#[derive(Debug, Clone)]
+pub struct Alignment {
+    points: Vec<AlignmentPoint>,
+}
+
+/// 射影結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projection {
+    /// 追加距離（m）
+    pub chainage: f64,
+    /// 中心線からの距離（m）
+    pub offset: f64,
+}
+
+impl Projection {
+    /// 測点文字列（例: `No.12+5.3`）
+    pub fn station(&self) -> String {
+        Station::from_meters(self.chainage).to_string()
+    }
+}
+
+impl Alignment {
+    /// 測量点から作成（追加距離の昇順であること）
+    pub fn new(points: Vec<AlignmentPoint>) -> Result<Self> {
+        if points.len() < 2 {
+            return Err(PhotoAiError::InvalidAlignment(
+                "測量点が2点以上必要です".to_string(),
+            ));
+        }
+        if let Some(w) = points.windows(2).find(|w| w[1].chainage <= w[0].chainage) {
+            return Err(PhotoAiError::InvalidAlignment(format!(
+                "測点が昇順ではありません: {} → {}",
+                Station::from_meters(w[0].chainage),
+                Station::from_meters(w[1].chainage)
+            )));
+        }
+        Ok(Self { points })
+    }
+
+    /// CSVファイルから読み込み
+    pub fn from_csv(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_csv_str(&content)
+    }
+
+    /// CSV文字列から読み込み（ヘッダー行・`#` コメント行は無視）
+    pub fn from_csv_str(content: &str) -> Result<Self> {
+        let mut points = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim().trim_start_matches('\u{feff}');
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let cols: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+            if cols.len() < 3 {
+                return Err(PhotoAiError::InvalidAlignment(format!(
+                    "{}行目: 列が不足しています（station,lat,lon）",
+                    i + 1
+                )));
+            }
+            let (Ok(lat), Ok(lon)) = (cols[1].parse::<f64>(), cols[2].parse::<f64>()) else {
+                if points.is_empty() {
+                    // ヘッダー行
+                    continue;
+                }
+                return Err(PhotoAiError::InvalidAlignment(format!(
+                    "{}行目: 緯度経度が数値ではありません",
+                    i + 1
+                )));
+            };
+            let chainage = Station::parse(cols[0]).map(|s| s.meters()).ok_or_else(|| {
+                PhotoAiError::InvalidAlignment(format!("{}行目: 測点を解釈できません: {}", i + 1, cols[0]))
+            })?;
+            points.push(AlignmentPoint { chainage, lat, lon });
+        }
+        Self::new(points)
+    }
+
+    pub fn points(&self) -> &[AlignmentPoint] {
+        &self.points
+    }
+
+    /// 緯度経度を中心線に射影
+    ///
+    /// 最も近い区間に垂線を下ろし、区間両端の測点から追加距離を補間する。
+    /// 始点より手前・終点より先は端点までの距離になる。
+    pub fn project(&self, lat: f64, lon: f64) -> Projection {
+        // 始点を原点とする平面座標（m）に変換（現場規模なら正距円筒で十分）
+        let origin = self.points[0];
+        let cos_lat = origin.lat.to_radians().cos();
+        let to_xy = |lat: f64, lon: f64| {
+            (
+                (lon - origin.lon).to_radians() * cos_lat * EARTH_RADIUS,
+                (lat - origin.lat).to_radians() * EARTH_RADIUS,
+            )
+        };
+        let (px, py) = to_xy(lat, lon);
+
+        let mut best = Projection { chainage: origin.chainage, offset: f64::INFINITY };
+        for seg in self.points.windows(2) {
+            let (ax, ay) = to_xy(seg[0].lat, seg[0].lon);
+            let (bx, by) = to_xy(seg[1].lat, seg[1].lon);
+            let (dx, dy) = (bx - ax, by - ay);
+            let len_sq = dx * dx + dy * dy;
+            let t = if len_sq > 0.0 {
+                (((px - ax) * dx + (py - ay) * dy) / len_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let (qx, qy) = (ax + t * dx, ay + t * dy);
+            let offset = ((px - qx).powi(2) + (py - qy).powi(2)).sqrt();
+            if offset < best.offset {
+                best = Projection {
+                    chainage: seg[0].chainage + t * (seg[1].chainage - seg[0].chainage),
+                    offset,
+                };
+            }
+        }
+        best
+    }
+}
+
+/// 測点推定の集計
+#[derive(Debug, Default)]
+pub struct AlignmentReport {
+    /// 測点を設定した写真
+    pub assigned: Vec<(String, Projection)>,
+    /// 中心線から離れすぎて推定しなかった写真
+    pub too_far: Vec<(String, Projection)>,
+    /// GPSなしの写真
+    pub no_gps: Vec<String>,
+    /// 測点が既に入っていたため変更しなかった写真
+    pub kept: Vec<String>,
+}
+
+/// 測点が空の写真について GPS から測点を推定して設定する
+pub fn assign_stations(
+    results: &mut [AnalysisResult],
+    alignment: &Alignment,
+    max_offset: f64,
+) -> AlignmentReport {
+    let mut report = AlignmentReport::default();
+    for result in results.iter_mut() {
+        if !result.station.trim().is_empty() {
+            report.kept.push(result.file_name.clone());
+            continue;
+        }
+        let Some((lat, lon)) = result.exif.gps() else {
+            report.no_gps.push(result.file_name.clone());
+            continue;
+        };
+        let projection = alignment.project(lat, lon);
+        if projection.offset > max_offset {
+            report.too_far.push((result.file_name.clone(), projection));
+            continue;
+        }
+        result.station = projection.station();
+        report.assigned.push((result.file_name.clone(), projection));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use photo_ai_common::ExifMetadata;
+
+    /// 北向きの直線（1m ≒ 緯度 1/111320 度）
+    const CSV: &str = "station,lat,lon\n\
+        No.0,35.000000,139.000000\n\
+        No.1,35.000180,139.000000\n\
+        # コメント\n\
+        No.2,35.000360,139.000000\n";
+
+    fn result_at(name: &str, lat: f64, lon: f64) -> AnalysisResult {
+        AnalysisResult {
+            file_name: name.to_string(),
+            exif: ExifMetadata { gps_latitude: Some(lat), gps_longitude: Some(lon), ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_csv_str() {
+        let alignment = Alignment::from_csv_str(CSV).unwrap();
+        assert_eq!(alignment.points().len(), 3);
+        assert_eq!(alignment.points()[2].chainage, 40.0);
+
+        assert!(Alignment::from_csv_str("No.0,35,139\n").is_err());
+        assert!(Alignment::from_csv_str("No.1,35,139\nNo.0,35.1,139\n").is_err());
+    }
+
+    #[test]
+    fn test_project_onto_centerline() {
+        let alignment = Alignment::from_csv_str(CSV).unwrap();
+
+        // No.1 から北へ約5.3m、東へ約3m
+        let lat = 35.000180 + 5.3 / 111_320.0;
+        let lon = 139.0 + 3.0 / (111_320.0 * 35f64.to_radians().cos());
+        let p = alignment.project(lat, lon);
+        assert_eq!(p.station(), "No.1+5.3");
+        assert!((p.offset - 3.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_assign_stations() {
+        let alignment = Alignment::from_csv_str(CSV).unwrap();
+        let far_lon = 139.0 + 100.0 / (111_320.0 * 35f64.to_radians().cos());
+        let mut results = vec![
+            result_at("a.jpg", 35.000180, 139.0),
+            result_at("far.jpg", 35.000180, far_lon),
+            AnalysisResult { file_name: "nogps.jpg".to_string(), ..Default::default() },
+            AnalysisResult { file_name: "board.jpg".to_string(), station: "No.5".to_string(), ..Default::default() },
+        ];
+
+        let report = assign_stations(&mut results, &alignment, DEFAULT_MAX_OFFSET);
+        assert_eq!(results[0].station, "No.1");
+        assert_eq!(results[1].station, "");
+        assert_eq!(results[3].station, "No.5");
+        assert_eq!(report.assigned.len(), 1);
+        assert_eq!(report.too_far.len(), 1);
+        assert_eq!(report.no_gps, vec!["nogps.jpg"]);
+        assert_eq!(report.kept, vec!["board.jpg"]);
+    }
+}
@@ -167,7 +167,13 @@ pub fn filter_cached_images(
         };
 
         if let Some(result) = cache.get(&hash) {
-            cached_results.push(result.clone());
+            // パス・EXIF・フォルダ情報はスキャン時の値を使う（同じ内容の別ファイル・キャッシュ作成後の変更対策）
+            let mut result = result.clone();
+            result.file_name = img.file_name.clone();
+            result.file_path = img.path.display().to_string();
+            result.exif = img.exif.clone();
+            img.hints.apply(&mut result);
+            cached_results.push(result);
         } else {
             uncached_images.push((img.clone(), hash));
         }
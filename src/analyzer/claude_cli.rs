@@ -14,12 +14,22 @@ use std::process::Command;
 
 // 共通モジュールから型と関数をインポート
 use photo_ai_common::{
-    AnalysisResult, RawImageData, HierarchyMaster,
-    build_step1_prompt, build_single_step_prompt,
+    AnalysisResult, FolderHints, RawImageData, HierarchyMaster,
+    build_step1_prompt, build_single_step_prompt, build_folder_hints_section,
     parse_step1_response as common_parse_step1,
     parse_single_step_response as common_parse_single_step,
 };
 
+/// フォルダ構成ヒントのプロンプト追記部分（ヒントがなければ空文字）
+fn folder_hints_section(names: &[String], images: &[ImageInfo]) -> String {
+    let hints: Vec<(&str, &FolderHints)> = names
+        .iter()
+        .zip(images)
+        .map(|(name, img)| (name.as_str(), &img.hints))
+        .collect();
+    build_folder_hints_section(&hints)
+}
+
 /// Step1: 画像認識を実行
 pub async fn analyze_batch_step1(
     images: &[ImageInfo],
@@ -38,11 +48,13 @@ pub async fn analyze_batch_step1(
         .join(", ");
 
     // 共通プロンプト生成を使用
-    let image_meta: Vec<(&str, Option<&str>)> = images
+    let names: Vec<String> = images.iter().map(analysis_file_name).collect();
+    let image_meta: Vec<(&str, Option<&str>)> = names
         .iter()
-        .map(|img| (img.file_name.as_str(), img.date.as_deref()))
+        .zip(images)
+        .map(|(name, img)| (name.as_str(), img.date.as_deref()))
         .collect();
-    let step1_prompt = build_step1_prompt(&image_meta);
+    let step1_prompt = build_step1_prompt(&image_meta) + &folder_hints_section(&names, images);
 
     // プロンプト構築（改行をスペースに置換してcmd経由で渡す）
     let raw_prompt = format!(
@@ -76,9 +88,11 @@ pub async fn analyze_batch(
     let raw_data = analyze_batch_step1(images, verbose, provider).await?;
 
     // マスタなしの場合はStep1結果をそのまま変換
-    let info_map: std::collections::HashMap<&str, &ImageInfo> = images
+    let names: Vec<String> = images.iter().map(analysis_file_name).collect();
+    let info_map: std::collections::HashMap<&str, &ImageInfo> = names
         .iter()
-        .map(|img| (img.file_name.as_str(), img))
+        .zip(images)
+        .map(|(name, img)| (name.as_str(), img))
         .collect();
 
     let results = raw_data
@@ -91,18 +105,30 @@ pub async fn analyze_batch(
             let date = img_info
                 .and_then(|i| i.date.clone())
                 .unwrap_or_default();
+            let exif = img_info
+                .map(|i| i.exif.clone())
+                .unwrap_or_default();
+            let file_name = img_info
+                .map(|i| i.file_name.clone())
+                .unwrap_or_else(|| raw.file_name.clone());
 
-            AnalysisResult {
-                file_name: raw.file_name.clone(),
+            let mut result = AnalysisResult {
+                file_name,
                 file_path,
                 date,
+                exif,
                 has_board: raw.has_board,
                 detected_text: raw.detected_text.clone(),
                 measurements: raw.measurements.clone(),
                 description: raw.scene_description.clone(),
                 photo_category: raw.photo_category.clone(),
+                hint_conflict: raw.hint_conflict.clone(),
                 ..Default::default()
+            };
+            if let Some(img) = img_info {
+                img.hints.apply(&mut result);
             }
+            result
         })
         .collect();
 
@@ -132,13 +158,16 @@ pub async fn analyze_batch_single_step(
         .join(", ");
 
     // 画像メタデータ
-    let image_meta: Vec<(&str, Option<&str>)> = images
+    let names: Vec<String> = images.iter().map(analysis_file_name).collect();
+    let image_meta: Vec<(&str, Option<&str>)> = names
         .iter()
-        .map(|img| (img.file_name.as_str(), img.date.as_deref()))
+        .zip(images)
+        .map(|(name, img)| (name.as_str(), img.date.as_deref()))
         .collect();
 
     // 1ステップ解析プロンプト生成
-    let single_step_prompt = build_single_step_prompt(&image_meta, master, work_type, variety);
+    let single_step_prompt = build_single_step_prompt(&image_meta, master, work_type, variety)
+        + &folder_hints_section(&names, images);
 
     // プロンプト構築
     let raw_prompt = format!(
@@ -161,22 +190,32 @@ pub async fn analyze_batch_single_step(
     // レスポンスをパース
     let mut results = parse_single_step_response(&response)?;
 
-    // file_path と date を補完
-    let info_map: std::collections::HashMap<&str, &ImageInfo> = images
+    // file_path・date・EXIF を補完
+    let info_map: std::collections::HashMap<&str, &ImageInfo> = names
         .iter()
-        .map(|img| (img.file_name.as_str(), img))
+        .zip(images)
+        .map(|(name, img)| (name.as_str(), img))
         .collect();
 
     for result in &mut results {
         if let Some(img_info) = info_map.get(result.file_name.as_str()) {
+            result.file_name = img_info.file_name.clone();
             result.file_path = img_info.path.display().to_string();
             result.date = img_info.date.clone().unwrap_or_default();
+            result.exif = img_info.exif.clone();
         }
     }
 
     // マスタとの整合性チェック
     sanitize_classification(&mut results, master);
 
+    // フォルダ構成の既知情報を優先（AIが矛盾を指摘した写真を除く）
+    for result in &mut results {
+        if let Some(img) = images.iter().find(|i| i.file_name == result.file_name) {
+            img.hints.apply(result);
+        }
+    }
+
     Ok(results)
 }
 
@@ -196,12 +235,27 @@ fn get_temp_dir() -> Result<PathBuf> {
     Ok(temp_dir)
 }
 
+/// AI に渡すファイル名（JPEG変換する・変換済みの HEIC は `.jpg` を付与）
+fn analysis_file_name(img: &ImageInfo) -> String {
+    if img.needs_jpeg_conversion() || img.original_path.is_some() {
+        format!("{}.jpg", img.file_name)
+    } else {
+        img.file_name.clone()
+    }
+}
+
 fn copy_to_temp(images: &[ImageInfo], temp_dir: &std::path::Path) -> Result<Vec<PathBuf>> {
     let mut local_paths = Vec::new();
 
     for img in images {
-        let dest = temp_dir.join(&img.file_name);
-        std::fs::copy(&img.path, &dest)?;
+        let dest = temp_dir.join(analysis_file_name(img));
+        if img.needs_jpeg_conversion() {
+            // AI CLI が読めない形式（TIFF等）はJPEGに変換して渡す
+            crate::scanner::format::transcode_to_jpeg(&img.path, &dest, 90, img.exif.orientation)
+                .map_err(|e| PhotoAiError::ImageLoad(format!("{}: {}", img.file_name, e)))?;
+        } else {
+            std::fs::copy(&img.path, &dest)?;
+        }
         // 絶対パスに変換
         let abs_path = std::fs::canonicalize(&dest)?;
         local_paths.push(abs_path);
@@ -474,148 +528,33 @@ fn parse_step1_response(response: &str) -> Result<Vec<RawImageData>> {
         .map_err(|e| PhotoAiError::ApiParse(format!("Step1 JSONパースエラー: {}", e)))
 }
 
+/// マスタとの整合性チェック（問題は警告として表示し、結果の warnings にも残す）
 fn sanitize_classification(results: &mut [AnalysisResult], master: &HierarchyMaster) {
+    // 未舗装部舗装工は自動選択しない（デフォルトは舗装打換え工）
     for result in results.iter_mut() {
-        // remarks から階層を確定（撮影内容ベース）
-        if !result.remarks.is_empty() {
-            let mut candidates: Vec<_> = master.rows().iter()
-                .filter(|row| row.remarks == result.remarks)
-                .collect();
-
-            if !candidates.is_empty() {
-                if !result.photo_category.is_empty() {
-                    let filtered: Vec<_> = candidates
-                        .iter()
-                        .copied()
-                        .filter(|row| row.photo_type == result.photo_category)
-                        .collect();
-                    if !filtered.is_empty() {
-                        candidates = filtered;
-                    }
-                }
-                if !result.work_type.is_empty() {
-                    let filtered: Vec<_> = candidates
-                        .iter()
-                        .copied()
-                        .filter(|row| row.work_type == result.work_type)
-                        .collect();
-                    if !filtered.is_empty() {
-                        candidates = filtered;
-                    }
-                }
-                if !result.variety.is_empty() {
-                    let filtered: Vec<_> = candidates
-                        .iter()
-                        .copied()
-                        .filter(|row| row.variety == result.variety)
-                        .collect();
-                    if !filtered.is_empty() {
-                        candidates = filtered;
-                    }
-                }
-                if !result.subphase.is_empty() {
-                    let filtered: Vec<_> = candidates
-                        .iter()
-                        .copied()
-                        .filter(|row| row.subphase == result.subphase)
-                        .collect();
-                    if !filtered.is_empty() {
-                        candidates = filtered;
-                    }
-                }
-
-                if let Some(row) = candidates.first() {
-                    result.photo_category = row.photo_type.clone();
-                    result.work_type = row.work_type.clone();
-                    result.variety = row.variety.clone();
-                    result.subphase = row.subphase.clone();
-                }
-            }
-        }
-
-        // 未舗装部舗装工は自動選択しない（デフォルトは舗装打換え工）
         if result.work_type == "舗装工" && result.variety == "未舗装部舗装工" {
             result.variety = "舗装打換え工".to_string();
         }
+    }
 
-        // 1) photoCategory (写真種別) と workType の整合
-        if !result.photo_category.is_empty() && !result.work_type.is_empty() {
-            let has_work = master.rows().iter().any(|row| {
-                row.photo_type == result.photo_category && row.work_type == result.work_type
-            });
-            if !has_work {
-                result.work_type.clear();
-                result.variety.clear();
-                result.subphase.clear();
-                result.remarks.clear();
-                continue;
-            }
-        }
-
-        // 2) workType の存在チェック
-        if !result.work_type.is_empty() {
-            let work_types = master.get_work_types();
-            if !work_types.contains(&result.work_type.as_str()) {
-                result.work_type.clear();
-                result.variety.clear();
-                result.subphase.clear();
-                result.remarks.clear();
-                continue;
-            }
-        }
-
-        // 3) variety の整合
-        if !result.work_type.is_empty() && !result.variety.is_empty() {
-            let has_variety = master.rows().iter().any(|row| {
-                row.work_type == result.work_type
-                    && row.variety == result.variety
-                    && (result.photo_category.is_empty()
-                        || row.photo_type == result.photo_category)
-            });
-            if !has_variety {
-                result.variety.clear();
-                result.subphase.clear();
-                result.remarks.clear();
-            }
-        } else {
-            result.variety.clear();
-            result.subphase.clear();
-            result.remarks.clear();
-        }
-
-        // 4) subphase の整合
-        if !result.work_type.is_empty() && !result.variety.is_empty() && !result.subphase.is_empty() {
-            let has_detail = master.rows().iter().any(|row| {
-                row.work_type == result.work_type
-                    && row.variety == result.variety
-                    && row.subphase == result.subphase
-                    && (result.photo_category.is_empty()
-                        || row.photo_type == result.photo_category)
-            });
-            if !has_detail {
-                result.subphase.clear();
-                result.remarks.clear();
-            }
-        } else {
-            result.subphase.clear();
-            result.remarks.clear();
-        }
+    // マスタとの整合性チェック（確度の高い候補は自動適用）
+    for issue in photo_ai_common::correct_results(results, master) {
+        println!("  ⚠ {}", issue);
+    }
 
-        // 5) remarks の整合（同一の photoCategory/work/var/subphase の行に存在する備考のみ許可）
-        if !result.remarks.is_empty() {
-            let has_remarks = master.rows().iter().any(|row| {
-                row.remarks == result.remarks
-                    && row.work_type == result.work_type
-                    && row.variety == result.variety
-                    && row.subphase == result.subphase
-                    && (result.photo_category.is_empty()
-                        || row.photo_type == result.photo_category)
-            });
-            if !has_remarks {
-                result.remarks.clear();
+    // 上位の階層が空なら下位も空にする
+    for result in results.iter_mut() {
+        let mut empty = result.work_type.is_empty();
+        for field in [&mut result.variety, &mut result.subphase, &mut result.remarks] {
+            if empty {
+                field.clear();
             }
+            empty = field.is_empty();
         }
     }
+
+    // 検索パターンで照合した上位の備考を候補に加える
+    photo_ai_common::fill_remarks_candidates(results, master);
 }
 
 #[cfg(test)]
@@ -655,12 +594,29 @@ mod tests {
         assert!(!result[0].has_board);
     }
 
+    #[test]
+    fn test_analysis_file_name() {
+        let jpeg = ImageInfo {
+            file_name: "a.JPG".to_string(),
+            format: Some(::image::ImageFormat::Jpeg),
+            ..Default::default()
+        };
+        let tiff = ImageInfo {
+            file_name: "b.tif".to_string(),
+            format: Some(::image::ImageFormat::Tiff),
+            ..Default::default()
+        };
+        assert_eq!(analysis_file_name(&jpeg), "a.JPG");
+        assert_eq!(analysis_file_name(&tiff), "b.tif.jpg");
+    }
+
     #[test]
     fn test_build_step1_prompt() {
-        let images = vec![ImageInfo {
+        let images = [ImageInfo {
             path: PathBuf::from("test.jpg"),
             file_name: "test.jpg".to_string(),
             date: Some("2025-01-18".to_string()),
+            ..Default::default()
         }];
         let image_meta: Vec<(&str, Option<&str>)> = images
             .iter()
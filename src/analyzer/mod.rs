@@ -49,6 +49,7 @@ pub async fn analyze_images(
     }
 
     pb.finish_with_message("完了");
+    restore_input_order(&mut results, images);
 
     Ok(results)
 }
@@ -103,16 +104,34 @@ pub async fn analyze_images_with_cache(
         }
     }
 
-    // ファイル名でソート（元の順序を維持）
-    let file_order: std::collections::HashMap<&str, usize> = images
+    restore_input_order(&mut cached_results, images);
+
+    Ok(cached_results)
+}
+
+/// 解析結果を入力画像の順（`--sort` で決めた順）に並べ直す
+///
+/// AIはバッチ内の順序を入れ替えて返すことがあるため、解析後に必ず揃える。
+/// `--recursive` では別フォルダに同名の写真があるため、パスで照合する
+/// （パスのない結果だけファイル名で照合）
+pub fn restore_input_order(results: &mut [AnalysisResult], images: &[ImageInfo]) {
+    let path_order: std::collections::HashMap<String, usize> = images
         .iter()
         .enumerate()
-        .map(|(i, img)| (img.file_name.as_str(), i))
+        .map(|(i, img)| (img.path.display().to_string(), i))
         .collect();
+    let mut name_order: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+    for (i, img) in images.iter().enumerate() {
+        name_order.entry(img.file_name.as_str()).or_insert(i);
+    }
 
-    cached_results.sort_by_key(|r| file_order.get(r.file_name.as_str()).copied().unwrap_or(usize::MAX));
-
-    Ok(cached_results)
+    results.sort_by_key(|r| {
+        path_order
+            .get(&r.file_path)
+            .or_else(|| name_order.get(r.file_name.as_str()))
+            .copied()
+            .unwrap_or(usize::MAX)
+    });
 }
 
 /// 工種指定の1ステップ解析
@@ -158,6 +177,33 @@ pub async fn analyze_images_single_step(
     }
 
     pb.finish_with_message("1ステップ解析完了");
+    restore_input_order(&mut results, images);
 
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_restore_input_order_with_same_names_in_subfolders() {
+        let image = |path: &str| ImageInfo {
+            path: PathBuf::from(path),
+            file_name: "IMG_0001.JPG".to_string(),
+            ..Default::default()
+        };
+        let images = vec![image("day1/IMG_0001.JPG"), image("day2/IMG_0001.JPG"), image("day3/IMG_0001.JPG")];
+        let result = |path: &str| AnalysisResult {
+            file_name: "IMG_0001.JPG".to_string(),
+            file_path: path.to_string(),
+            ..Default::default()
+        };
+        let mut results = vec![result("day3/IMG_0001.JPG"), result("day1/IMG_0001.JPG"), result("day2/IMG_0001.JPG")];
+
+        restore_input_order(&mut results, &images);
+        let paths: Vec<&str> = results.iter().map(|r| r.file_path.as_str()).collect();
+        assert_eq!(paths, vec!["day1/IMG_0001.JPG", "day2/IMG_0001.JPG", "day3/IMG_0001.JPG"]);
+    }
+}
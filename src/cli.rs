@@ -1,5 +1,9 @@
 use clap::{Parser, Subcommand};
 use crate::ai_provider::AiProvider;
+use crate::alias_suggest::CorrectionSource;
+use crate::master_tool::MasterStack;
+use crate::normalizer::NormalizationPass;
+use crate::ordering::{CameraOffset, SortOrder};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -34,9 +38,9 @@ pub enum Commands {
         #[arg(short, long, default_value = "5")]
         batch_size: usize,
 
-        /// 工種マスタJSONファイル
+        /// 工種マスタ（CSV・Excel・JSON）。カンマ区切りで工事ごとのオーバーレイ（JSON）を重ねる
         #[arg(short, long)]
-        master: Option<PathBuf>,
+        master: Option<MasterStack>,
 
         /// 工種を指定（1ステップ解析モード）
         #[arg(short = 'w', long)]
@@ -50,6 +54,14 @@ pub enum Commands {
         #[arg(short = 's', long)]
         station: Option<String>,
 
+        /// 道路中心線CSV（測点,緯度,経度）。GPS位置から測点を推定
+        #[arg(long)]
+        alignment: Option<PathBuf>,
+
+        /// 中心線からの許容距離（m）。これより離れた写真は測点を推定しない
+        #[arg(long, default_value = "30")]
+        max_offset: f64,
+
         /// キャッシュを使用（再解析をスキップ）
         #[arg(long)]
         use_cache: bool,
@@ -58,9 +70,33 @@ pub enum Commands {
         #[arg(short = 'r', long)]
         recursive: bool,
 
-        /// 除外フォルダも含める（デフォルトは「非使用」等を除外）
+        /// 除外ルールを無視してすべて含める（既定の「非使用」等と .photoaiignore）
         #[arg(long)]
         include_all: bool,
+
+        /// 類似写真（連写）はベストショットのみ解析
+        #[arg(long)]
+        dedupe: bool,
+
+        /// HEIC/HEIF写真をJPEGに変換して取り込む（heif-convert/magick/sipsが必要）
+        #[arg(long)]
+        heic: bool,
+
+        /// フォルダ構成テンプレート（例: "{date}/{work_type}/{station}/"、"regex:..."も可）
+        #[arg(long)]
+        path_template: Option<String>,
+
+        /// 並び順 (natural/name/exif/folder/master)。master は工種マスタの行順→測点→撮影日時
+        #[arg(long, default_value = "natural")]
+        sort: SortOrder,
+
+        /// カメラ別の時計補正（例: "TG-6=+90", "iPhone 15=-0:01:30"）。複数指定可
+        #[arg(long = "camera-offset", value_name = "MODEL=OFFSET")]
+        camera_offset: Vec<CameraOffset>,
+
+        /// 正規化で行わない段（dates/station-format/remarks/station/category/work-type/measurements/rules）。カンマ区切り
+        #[arg(long, value_delimiter = ',')]
+        skip: Vec<NormalizationPass>,
     },
 
     /// 解析結果からPDF/Excelを生成
@@ -89,13 +125,25 @@ pub enum Commands {
         #[arg(long, default_value = "medium")]
         pdf_quality: PdfQuality,
 
-        /// エイリアスプリセット (pavement/marking/general)
-        #[arg(long)]
-        preset: Option<String>,
+        /// エイリアスプリセット（カンマ区切り・複数指定可、後に指定したものが優先）。一覧は alias list
+        #[arg(long, value_delimiter = ',')]
+        preset: Vec<String>,
 
         /// カスタムエイリアスファイル（JSON）
         #[arg(long)]
         alias: Option<PathBuf>,
+
+        /// 並べ替えてから出力 (natural/name/exif/folder/master)。省略時は result.json の順
+        #[arg(long)]
+        sort: Option<SortOrder>,
+
+        /// カメラ別の時計補正（--sort exif 用）。複数指定可
+        #[arg(long = "camera-offset", value_name = "MODEL=OFFSET")]
+        camera_offset: Vec<CameraOffset>,
+
+        /// 工種マスタ（CSV・Excel・JSON、--sort master 用）。カンマ区切りでオーバーレイを重ねる
+        #[arg(short, long)]
+        master: Option<MasterStack>,
     },
 
     /// 解析からPDF/Excel出力まで一括実行
@@ -116,9 +164,9 @@ pub enum Commands {
         #[arg(short, long, default_value = "5")]
         batch_size: usize,
 
-        /// 工種マスタJSONファイル
+        /// 工種マスタ（CSV・Excel・JSON）。カンマ区切りで工事ごとのオーバーレイ（JSON）を重ねる
         #[arg(short, long)]
-        master: Option<PathBuf>,
+        master: Option<MasterStack>,
 
         /// 工種を指定（1ステップ解析モード）
         #[arg(short = 'w', long)]
@@ -132,6 +180,14 @@ pub enum Commands {
         #[arg(short = 's', long)]
         station: Option<String>,
 
+        /// 道路中心線CSV（測点,緯度,経度）。GPS位置から測点を推定
+        #[arg(long)]
+        alignment: Option<PathBuf>,
+
+        /// 中心線からの許容距離（m）。これより離れた写真は測点を推定しない
+        #[arg(long, default_value = "30")]
+        max_offset: f64,
+
         /// PDF画像品質 (high/medium/low)
         #[arg(long, default_value = "medium")]
         pdf_quality: PdfQuality,
@@ -144,9 +200,181 @@ pub enum Commands {
         #[arg(short = 'r', long)]
         recursive: bool,
 
-        /// 除外フォルダも含める（デフォルトは「非使用」等を除外）
+        /// 除外ルールを無視してすべて含める（既定の「非使用」等と .photoaiignore）
         #[arg(long)]
         include_all: bool,
+
+        /// 類似写真（連写）はベストショットのみ解析
+        #[arg(long)]
+        dedupe: bool,
+
+        /// HEIC/HEIF写真をJPEGに変換して取り込む（heif-convert/magick/sipsが必要）
+        #[arg(long)]
+        heic: bool,
+
+        /// フォルダ構成テンプレート（例: "{date}/{work_type}/{station}/"、"regex:..."も可）
+        #[arg(long)]
+        path_template: Option<String>,
+
+        /// 並び順 (natural/name/exif/folder/master)。master は工種マスタの行順→測点→撮影日時
+        #[arg(long, default_value = "natural")]
+        sort: SortOrder,
+
+        /// カメラ別の時計補正（例: "TG-6=+90", "iPhone 15=-0:01:30"）。複数指定可
+        #[arg(long = "camera-offset", value_name = "MODEL=OFFSET")]
+        camera_offset: Vec<CameraOffset>,
+
+        /// 正規化で行わない段（dates/station-format/remarks/station/category/work-type/measurements/rules）。カンマ区切り
+        #[arg(long, value_delimiter = ',')]
+        skip: Vec<NormalizationPass>,
+    },
+
+    /// フォルダを監視し、届いた写真を順次解析して result.json に追記
+    Watch {
+        /// 監視する写真フォルダ
+        #[arg(required = true)]
+        folder: PathBuf,
+
+        /// 追記先JSONファイル（デフォルト: 入力フォルダ/result.json）
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// バッチサイズ（一度に解析する枚数）
+        #[arg(short, long, default_value = "5")]
+        batch_size: usize,
+
+        /// 最後の変更からこの秒数、新しい変更がなければ解析を開始
+        #[arg(long, default_value = "10")]
+        debounce: u64,
+
+        /// 追加があればこの分数ごとにPDFを再生成
+        #[arg(long, value_name = "MINUTES")]
+        pdf_every: Option<u64>,
+
+        /// PDF出力先（デフォルト: 入力フォルダ）
+        #[arg(long)]
+        pdf_output: Option<PathBuf>,
+
+        /// ドキュメントタイトル
+        #[arg(short, long, default_value = "工事写真帳")]
+        title: String,
+
+        /// PDF画像品質 (high/medium/low)
+        #[arg(long, default_value = "medium")]
+        pdf_quality: PdfQuality,
+
+        /// サブフォルダも再帰的に監視
+        #[arg(short = 'r', long)]
+        recursive: bool,
+
+        /// 除外ルールを無視してすべて含める（既定の「非使用」等と .photoaiignore）
+        #[arg(long)]
+        include_all: bool,
+
+        /// HEIC/HEIF写真をJPEGに変換して取り込む（heif-convert/magick/sipsが必要）
+        #[arg(long)]
+        heic: bool,
+
+        /// フォルダ構成テンプレート（例: "{date}/{work_type}/{station}/"、"regex:..."も可）
+        #[arg(long)]
+        path_template: Option<String>,
+    },
+
+    /// 写真の画質をチェック（ピンぼけ・露出・画素数・縦横比・ファイルサイズ）
+    Lint {
+        /// 写真フォルダのパス
+        #[arg(required = true)]
+        folder: PathBuf,
+
+        /// 解析結果JSON。黒板の判読性も検査し、各写真の warnings に書き込む
+        #[arg(long)]
+        result: Option<PathBuf>,
+
+        /// レポート出力先（.csv / .json）
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// ピンぼけ判定のしきい値（鮮明度がこれ未満で警告）
+        #[arg(long, default_value = "60")]
+        blur_threshold: f64,
+
+        /// 最小画素数（電子納品の目安: 100万画素）
+        #[arg(long, default_value = "1000000")]
+        min_pixels: u64,
+
+        /// サブフォルダも再帰的にスキャン
+        #[arg(short = 'r', long)]
+        recursive: bool,
+
+        /// 除外ルールを無視してすべて含める（既定の「非使用」等と .photoaiignore）
+        #[arg(long)]
+        include_all: bool,
+
+        /// HEIC/HEIF写真をJPEGに変換して取り込む（heif-convert/magick/sipsが必要）
+        #[arg(long)]
+        heic: bool,
+    },
+
+    /// 規格値による品質管理チェック（計測値の合否判定）
+    Qc {
+        /// 解析結果JSONファイル
+        #[arg(required = true)]
+        input: PathBuf,
+
+        /// 規格表JSON（省略時は .photo-ai/qc.json）
+        #[arg(short, long)]
+        spec: Option<PathBuf>,
+
+        /// レポート出力先（.csv / .json）
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// 不合格を解析結果JSONの qc_failures に書き込む（PDF/Excelで強調表示される）
+        #[arg(long)]
+        write: bool,
+    },
+
+    /// 工種マスタの管理
+    Master {
+        #[command(subcommand)]
+        command: MasterCommand,
+    },
+
+    /// エイリアスプリセットの管理
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommand,
+    },
+
+    /// 類似写真（連写・撮り直し）を検出してベストショットを提案
+    Dedupe {
+        /// 写真フォルダのパス
+        #[arg(required = true)]
+        folder: PathBuf,
+
+        /// 類似判定の閾値（ハッシュの異なるビット数、小さいほど厳密）
+        #[arg(short, long, default_value = "6")]
+        threshold: u32,
+
+        /// ベストショット以外を除外フォルダへ移動
+        #[arg(long = "move")]
+        move_rejected: bool,
+
+        /// 移動先フォルダ名
+        #[arg(long, default_value = "非使用")]
+        exclude_dir: String,
+
+        /// サブフォルダも再帰的にスキャン
+        #[arg(short = 'r', long)]
+        recursive: bool,
+
+        /// 除外ルールを無視してすべて含める（既定の「非使用」等と .photoaiignore）
+        #[arg(long)]
+        include_all: bool,
+
+        /// HEIC/HEIF写真をJPEGに変換して取り込む（heif-convert/magick/sipsが必要）
+        #[arg(long)]
+        heic: bool,
     },
 
     /// 設定を表示/編集
@@ -169,6 +397,14 @@ pub enum Commands {
         /// 出力先（省略時は上書き）
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// 道路中心線CSV（測点,緯度,経度）。GPSのある写真は自動で測点を設定
+        #[arg(long)]
+        alignment: Option<PathBuf>,
+
+        /// 中心線からの許容距離（m）
+        #[arg(long, default_value = "30")]
+        max_offset: f64,
     },
 
     /// キャッシュ管理
@@ -186,7 +422,7 @@ pub enum Commands {
         info: bool,
     },
 
-    /// 解析結果を正規化（グループ単位で計測値を統一）
+    /// 解析結果を正規化（日付・備考・測点・写真区分・工種/種別・計測値をセット単位で統一）
     Normalize {
         /// 入力JSONファイル
         #[arg(required = true)]
@@ -203,6 +439,194 @@ pub enum Commands {
         /// ドライラン（変更を適用せずプレビュー）
         #[arg(long)]
         dry_run: bool,
+
+        /// 行わない段（dates/station-format/remarks/station/category/work-type/measurements/rules）。カンマ区切り
+        #[arg(long, value_delimiter = ',')]
+        skip: Vec<NormalizationPass>,
+
+        /// 規則ファイル（省略時は .photo-ai/normalize.json があれば使う）
+        #[arg(long)]
+        rules: Option<PathBuf>,
+    },
+}
+
+/// `alias` のサブコマンド
+#[derive(Subcommand)]
+pub enum AliasCommand {
+    /// 登録済みのプリセット（組み込み・~/.config/photo-ai/aliases・.photo-ai/aliases）を一覧
+    List,
+
+    /// プリセットの内容をJSONで表示
+    Show {
+        /// プリセット名（別名も可）
+        #[arg(required = true)]
+        name: String,
+    },
+
+    /// 人の修正（AIの結果と直した結果の差分・修正ログ）からエイリアスを提案
+    Suggest {
+        /// AI.json=EDITED.json、*.sorted.json（同じフォルダの *.json と比較）、または修正ログ
+        #[arg(required = true)]
+        sources: Vec<CorrectionSource>,
+
+        /// 提案をエイリアスJSONに書き出す（--alias で読み込める）
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// 同じ直し方の最小件数
+        #[arg(long, default_value = "2")]
+        min_support: usize,
+    },
+}
+
+/// `master` のサブコマンド
+#[derive(Subcommand)]
+pub enum MasterCommand {
+    /// マスタを検査（不正な行・重複・階層の欠け・工種のない種別）
+    Validate {
+        /// マスタファイル（CSV・Excel・JSON）
+        #[arg(required = true)]
+        file: PathBuf,
+
+        /// 警告もエラーとして扱う
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// 2つのマスタの差分を表示（工種・種別・細別・備考で行を対応付け）
+    Diff {
+        /// 比較元
+        #[arg(required = true)]
+        before: PathBuf,
+
+        /// 比較先
+        #[arg(required = true)]
+        after: PathBuf,
+    },
+
+    /// 統合マスタから工種別マスタを作り直す（--merge で工種別マスタを統合マスタに書き戻す）
+    Sync {
+        /// 統合マスタ
+        #[arg(short, long, default_value = "master/construction_hierarchy.csv")]
+        master: PathBuf,
+
+        /// 工種別マスタのフォルダ
+        #[arg(short, long, default_value = "master/by_work_type")]
+        dir: PathBuf,
+
+        /// 工種別マスタを統合マスタに書き戻す
+        #[arg(long)]
+        merge: bool,
+
+        /// 差分の表示のみ（書き込まない）
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// 過去の納品物（PHOTO.XML・写真台帳Excel）から工種マスタを作成・拡張
+    Learn {
+        /// PHOTO.XML・写真台帳Excel、またはそれらを含むフォルダ
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// 出力先マスタ（CSV・Excel・JSON）
+        #[arg(short, long, required = true)]
+        output: PathBuf,
+
+        /// 拡張する既存マスタ（同じ分類の行には検索パターンを追記）
+        #[arg(short, long)]
+        base: Option<PathBuf>,
+
+        /// 検索パターンにする語の最小出現写真数
+        #[arg(long, default_value = "2")]
+        min_count: usize,
+
+        /// 1分類あたりの検索パターン数の上限
+        #[arg(long, default_value = "5")]
+        max_patterns: usize,
+    },
+
+    /// 写真種別 → 工種 → 種別 → 細別 → 備考 の階層を行数つきで表示
+    Tree {
+        /// マスタファイル（カンマ区切りでオーバーレイを重ねる）
+        #[arg(short, long, default_value = "master/construction_hierarchy.csv")]
+        master: MasterStack,
+
+        /// オーバーレイを重ねた結果を表示（省略時はベースのみ）
+        #[arg(long)]
+        resolved: bool,
+
+        /// 表示する階層の深さ（1〜5）
+        #[arg(long)]
+        depth: Option<usize>,
+    },
+
+    /// ノードを追加（例: 品質管理写真/舗装工/舗装打換え工/表層工/出荷温度）
+    Add {
+        /// 追加するノードのパス（写真種別/工種/種別/細別/備考）
+        #[arg(required = true)]
+        node: String,
+
+        /// 検索パターン（| 区切り）
+        #[arg(short, long, default_value = "")]
+        patterns: String,
+
+        /// 写真区分
+        #[arg(long, default_value = "直接工事費")]
+        division: String,
+
+        /// マスタファイル
+        #[arg(short, long, default_value = "master/construction_hierarchy.csv")]
+        master: PathBuf,
+
+        /// 差分の表示のみ（書き込まない）
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// ノードの名前を変更（配下の全行に反映。* はその階層の任意の値）
+    Rename {
+        /// 変更するノードのパス（例: */舗装工）
+        #[arg(required = true)]
+        node: String,
+
+        /// 新しい名前
+        #[arg(required = true)]
+        new_name: String,
+
+        /// マスタファイル
+        #[arg(short, long, default_value = "master/construction_hierarchy.csv")]
+        master: PathBuf,
+
+        /// 差分の表示のみ（書き込まない）
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// ノードと配下の行を削除（* はその階層の任意の値）
+    Remove {
+        /// 削除するノードのパス
+        #[arg(required = true)]
+        node: String,
+
+        /// マスタファイル
+        #[arg(short, long, default_value = "master/construction_hierarchy.csv")]
+        master: PathBuf,
+
+        /// 差分の表示のみ（書き込まない）
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// 文字列に検索パターンが一致する行を表示
+    Search {
+        /// 検索する文字列（OCR結果など）
+        #[arg(required = true)]
+        text: String,
+
+        /// マスタファイル（カンマ区切りでオーバーレイを重ねる）
+        #[arg(short, long, default_value = "master/construction_hierarchy.csv")]
+        master: MasterStack,
     },
 }
 
@@ -1,6 +1,6 @@
 use crate::error::{PhotoAiError, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
@@ -9,6 +9,9 @@ pub struct Config {
     pub max_image_size: u32,
     pub default_batch_size: usize,
     pub timeout_seconds: u64,
+    /// フォルダ構成テンプレート（例: `{date}/{work_type}/{station}/`）
+    #[serde(default)]
+    pub path_template: Option<String>,
 }
 
 impl Config {
@@ -49,6 +52,7 @@ impl Config {
             max_image_size: 1568,  // Claude Vision推奨サイズ
             default_batch_size: 5,
             timeout_seconds: 120,
+            path_template: None,
         }
     }
 
@@ -67,3 +71,17 @@ impl Config {
         self.save()
     }
 }
+
+/// 工事ごとの設定ファイル（規格表・規則ファイルなど）を読む。誤りにはファイル名を付ける
+pub fn load_project_file<T>(path: &Path, parse: impl FnOnce(&str) -> Result<T>) -> Result<T> {
+    if !path.is_file() {
+        return Err(PhotoAiError::FileNotFound(path.display().to_string()));
+    }
+    parse(&std::fs::read_to_string(path)?).map_err(|e| {
+        let message = match e {
+            PhotoAiError::Config(message) | PhotoAiError::Common(photo_ai_common::Error::Parse(message)) => message,
+            e => e.to_string(),
+        };
+        PhotoAiError::Config(format!("{}: {}", path.display(), message))
+    })
+}
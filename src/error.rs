@@ -42,9 +42,15 @@ pub enum PhotoAiError {
     #[error("マスタ読み込みエラー: {0}")]
     MasterLoad(String),
 
+    #[error("線形ファイルが不正: {0}")]
+    InvalidAlignment(String),
+
     #[error("画像が見つかりません: {0}")]
     NoImagesFound(String),
 
+    #[error("フォルダ監視エラー: {0}")]
+    Watch(String),
+
     #[error("CLI実行エラー: {0}")]
     CliExecution(String),
 
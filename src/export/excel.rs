@@ -22,6 +22,8 @@ struct JsPhoto {
     station: String,
     remarks: String,
     measurements: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    qc_failures: Vec<String>,
 }
 
 pub fn generate_excel(
@@ -62,11 +64,26 @@ fn generate_excel_via_exceljs(
     title: &str,
     photos_per_page: u8,
 ) -> Result<()> {
+    // ExcelJSが埋め込めない形式（WebP/TIFF等）や回転が必要な写真は一時JPEGに変換
+    let mut converted: Vec<PathBuf> = Vec::new();
+    let file_paths: Vec<String> = results
+        .iter()
+        .map(|r| match excel_compatible_image(&r.file_path, super::orientation_for(r)) {
+            Some(path) => {
+                let s = path.display().to_string();
+                converted.push(path);
+                s
+            }
+            None => r.file_path.clone(),
+        })
+        .collect();
+
     let photos = results
         .iter()
-        .map(|r| JsPhoto {
+        .zip(file_paths)
+        .map(|(r, file_path)| JsPhoto {
             file_name: r.file_name.clone(),
-            file_path: r.file_path.clone(),
+            file_path,
             date: r.date.clone(),
             photo_category: r.photo_category.clone(),
             work_type: r.work_type.clone(),
@@ -75,6 +92,7 @@ fn generate_excel_via_exceljs(
             station: r.station.clone(),
             remarks: r.remarks.clone(),
             measurements: r.measurements.clone(),
+            qc_failures: r.qc_failures.clone(),
         })
         .collect();
 
@@ -108,6 +126,10 @@ fn generate_excel_via_exceljs(
         .output()
         .map_err(|e| PhotoAiError::ExcelGeneration(format!("Node起動エラー: {}", e)))?;
 
+    for path in &converted {
+        let _ = std::fs::remove_file(path);
+    }
+
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(PhotoAiError::ExcelGeneration(format!(
@@ -119,7 +141,73 @@ fn generate_excel_via_exceljs(
     Ok(())
 }
 
+/// ExcelJSで埋め込めない画像を一時JPEGに変換し、そのパスを返す
+///
+/// ExcelJSブリッジは拡張子で jpeg/png/gif を判定するため、
+/// 内容と拡張子が一致しない場合も変換対象にする。
+/// Excel は EXIF の向きを無視するので、回転が必要な写真も正立させて変換する
+fn excel_compatible_image(file_path: &str, orientation: u16) -> Option<PathBuf> {
+    use crate::scanner::format::{self, DetectedFormat};
+    use ::image::ImageFormat;
+
+    if file_path.is_empty() {
+        return None;
+    }
+    let path = Path::new(file_path);
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    // (そのまま埋め込めるか, 変換できるか)
+    let (embeddable, decodable) = match format::sniff_format(path).ok()? {
+        DetectedFormat::Image(ImageFormat::Jpeg) => (ext == "jpg" || ext == "jpeg", true),
+        DetectedFormat::Image(ImageFormat::Png) => (ext == "png", true),
+        DetectedFormat::Image(ImageFormat::Gif) => (ext == "gif", true),
+        DetectedFormat::Image(fmt) => (false, format::is_decodable(fmt)),
+        DetectedFormat::Heic | DetectedFormat::Unknown => (false, false),
+    };
+    if !decodable || (embeddable && orientation <= 1) {
+        return None;
+    }
+
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let dest = std::env::temp_dir().join(format!("photo-ai-exceljs-{}.jpg", stamp));
+    match format::transcode_to_jpeg(path, &dest, 90, orientation) {
+        Ok(()) => Some(dest),
+        Err(e) => {
+            eprintln!("警告: 画像変換失敗 ({}): {}", file_path, e);
+            None
+        }
+    }
+}
+
 fn exceljs_script_path() -> PathBuf {
     let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     root.join("web-wasm").join("js").join("excel-bridge.js")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_excel_compatible_image_rotates_by_orientation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("portrait.jpg");
+        ::image::RgbImage::new(40, 30).save(&path).unwrap();
+        let file_path = path.display().to_string();
+
+        // 正立済みの JPEG はそのまま
+        assert_eq!(excel_compatible_image(&file_path, 1), None);
+        assert_eq!(excel_compatible_image(&file_path, 0), None);
+
+        // 縦撮り（90°回転）は正立させた一時JPEGになる
+        let rotated = excel_compatible_image(&file_path, 6).expect("回転後の画像");
+        assert_eq!(::image::image_dimensions(&rotated).unwrap(), (30, 40));
+        std::fs::remove_file(rotated).unwrap();
+    }
+}
@@ -7,6 +7,16 @@ use crate::cli::{ExportFormat, PdfQuality};
 use crate::error::Result;
 use std::path::Path;
 
+/// 出力時に適用する EXIF Orientation
+///
+/// 解析結果に記録があればそれを使い、なければ（旧形式の result.json 等）画像ファイルから読む
+pub(crate) fn orientation_for(result: &AnalysisResult) -> u16 {
+    if result.exif.orientation != 0 || result.file_path.is_empty() {
+        return result.exif.orientation;
+    }
+    crate::scanner::exif::read_orientation(Path::new(&result.file_path))
+}
+
 fn output_path_for_format(output: &Path, title: &str, extension: &str) -> std::path::PathBuf {
     if output.is_dir() || output.extension().is_none() {
         output.join(format!("{}.{}", title, extension))
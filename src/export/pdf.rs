@@ -162,7 +162,7 @@ pub fn generate_pdf(
 
     for result in results.iter() {
         if !result.file_path.is_empty() {
-            match load_and_add_image(&mut doc, &result.file_path, quality) {
+            match load_and_add_image(&mut doc, &result.file_path, quality, super::orientation_for(result)) {
                 Ok((id, w, h)) => {
                     image_ids.push(Some(id));
                     image_sizes.push((w, h));
@@ -284,15 +284,23 @@ fn load_and_add_image(
     doc: &mut PdfDocument,
     image_path: &str,
     quality: PdfQuality,
+    orientation: u16,
 ) -> Result<(XObjectId, u32, u32)> {
     let path = Path::new(image_path);
     if !path.exists() {
         return Err(PhotoAiError::FileNotFound(image_path.to_string()));
     }
 
-    let dynamic_image = image_crate::open(path)
+    // 拡張子ではなく内容で形式を判定（.Jpg / 拡張子違い / WebP・TIFF 対応）
+    let dynamic_image = image_crate::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| PhotoAiError::PdfGeneration(format!("画像読み込みエラー: {}", e)))?
+        .decode()
         .map_err(|e| PhotoAiError::PdfGeneration(format!("画像読み込みエラー: {}", e)))?;
 
+    // EXIF の向きを適用（縦撮り写真が横倒しにならないように）
+    let dynamic_image = crate::scanner::format::apply_orientation(dynamic_image, orientation);
+
     // 品質設定に基づいてリサイズ
     let resized = resize_image(dynamic_image, quality);
     let (width, height) = (resized.width(), resized.height());
@@ -600,8 +608,14 @@ fn add_info_field_ops(
             // ラベル（左寄せ）
             add_text_ops(ops, &label_text, info_x_pt + 5.0, text_y, UNIFIED_FONT_SIZE, fonts);
 
-            // 値（ラベル右側）
+            // 値（ラベル右側）。規格外は赤字
+            if field.alert {
+                ops.push(Op::SetFillColor { col: Color::Rgb(Rgb { r: 0.8, g: 0.0, b: 0.0, icc_profile: None }) });
+            }
             add_fitted_text_ops(ops, &value_text, info_x_pt + label_width + 10.0, text_y, fonts, &text_config);
+            if field.alert {
+                ops.push(Op::SetFillColor { col: Color::Rgb(Rgb { r: 0.0, g: 0.0, b: 0.0, icc_profile: None }) });
+            }
 
             // 行の下に水平線（最後の行以外）
             if i < field_count - 1 {
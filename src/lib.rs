@@ -7,5 +7,13 @@ pub mod analyzer;
 pub mod matcher;
 pub mod export;
 pub mod station;
+pub mod alignment;
+pub mod ordering;
 pub mod master_selector;
+pub mod master_tool;
+pub mod master_learn;
+pub mod alias_suggest;
 pub mod normalizer;
+pub mod watch;
+pub mod lint;
+pub mod qc;
@@ -0,0 +1,403 @@
+//! 写真の画質チェック（lint）
+//!
+//! AI解析や台帳提出の前に、撮り直しが必要な写真を機械的に洗い出す。
+//! 全画像を rayon で並列に検査する。
+//!
+//! - ピンぼけ（ラプラシアン分散）
+//! - 露出不足・露出過多（平均輝度と白飛び・黒つぶれの割合）
+//! - 画素数不足（デジタル写真管理情報基準の目安: 有効画素数100万画素程度以上）
+//! - 極端な縦横比（パノラマ・切り抜き）
+//! - ファイルサイズが極端に小さい（サムネイル・転送途中）
+//! - 黒板の文字が読み取れない（解析結果がある場合のみ）
+
+use crate::analyzer::AnalysisResult;
+use crate::error::{PhotoAiError, Result};
+use crate::scanner::phash::{laplacian_variance, mean_brightness};
+use crate::scanner::ImageInfo;
+use ::image as image_crate;
+use image_crate::imageops::FilterType;
+use image_crate::GrayImage;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 画質評価に使う縮小サイズ（長辺px、類似写真検出と同じ）
+const SAMPLE_SIZE: u32 = 512;
+
+/// 黒つぶれ・白飛びとみなす輝度
+const DARK_CLIP: u8 = 5;
+const BRIGHT_CLIP: u8 = 250;
+
+/// 判定しきい値
+#[derive(Debug, Clone, Copy)]
+pub struct LintThresholds {
+    /// これ未満はピンぼけ（縮小画像のラプラシアン分散）
+    pub blur: f64,
+    /// 平均輝度がこれ未満は露出不足（0.0〜1.0）
+    pub dark: f64,
+    /// 平均輝度がこれを超えると露出過多（0.0〜1.0）
+    pub bright: f64,
+    /// 黒つぶれ・白飛びした画素の割合の上限
+    pub clipped_ratio: f64,
+    /// 最小画素数
+    pub min_pixels: u64,
+    /// 長辺/短辺の上限
+    pub max_aspect: f64,
+    /// 最小ファイルサイズ（バイト）
+    pub min_file_size: u64,
+}
+
+impl Default for LintThresholds {
+    fn default() -> Self {
+        Self {
+            blur: 60.0,
+            dark: 0.15,
+            bright: 0.85,
+            clipped_ratio: 0.4,
+            min_pixels: 1_000_000,
+            max_aspect: 2.0,
+            min_file_size: 50 * 1024,
+        }
+    }
+}
+
+/// 検出した問題
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintIssue {
+    /// ピンぼけ・手ぶれ
+    Blurry { sharpness: f64 },
+    /// 露出不足
+    Underexposed { brightness: f64 },
+    /// 露出過多
+    Overexposed { brightness: f64 },
+    /// 画素数不足
+    LowResolution { width: u32, height: u32 },
+    /// 極端な縦横比
+    ExtremeAspect { ratio: f64 },
+    /// ファイルサイズが小さすぎる
+    TinyFile { bytes: u64 },
+    /// 黒板の文字が読み取れない
+    BoardIllegible,
+    /// 画像として読み込めない
+    Unreadable(String),
+}
+
+impl LintIssue {
+    /// レポート用の識別子
+    pub fn code(&self) -> &'static str {
+        match self {
+            LintIssue::Blurry { .. } => "blur",
+            LintIssue::Underexposed { .. } => "underexposed",
+            LintIssue::Overexposed { .. } => "overexposed",
+            LintIssue::LowResolution { .. } => "resolution",
+            LintIssue::ExtremeAspect { .. } => "aspect",
+            LintIssue::TinyFile { .. } => "tiny-file",
+            LintIssue::BoardIllegible => "board",
+            LintIssue::Unreadable(_) => "unreadable",
+        }
+    }
+}
+
+impl std::fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LintIssue::Blurry { sharpness } => write!(f, "ピンぼけの可能性 (鮮明度 {:.0})", sharpness),
+            LintIssue::Underexposed { brightness } => write!(f, "露出不足 (平均輝度 {:.0}%)", brightness * 100.0),
+            LintIssue::Overexposed { brightness } => write!(f, "露出過多 (平均輝度 {:.0}%)", brightness * 100.0),
+            LintIssue::LowResolution { width, height } => {
+                let mega = *width as f64 * *height as f64 / 1_000_000.0;
+                write!(f, "画素数不足 ({}x{}, {:.1}万画素)", width, height, mega * 100.0)
+            }
+            LintIssue::ExtremeAspect { ratio } => write!(f, "縦横比が極端 ({:.2}:1)", ratio),
+            LintIssue::TinyFile { bytes } => write!(f, "ファイルサイズが小さい ({}KB)", bytes / 1024),
+            LintIssue::BoardIllegible => write!(f, "黒板の文字を読み取れない"),
+            LintIssue::Unreadable(e) => write!(f, "画像を読み込めない: {}", e),
+        }
+    }
+}
+
+/// 1枚分の検査結果
+#[derive(Debug, Clone)]
+pub struct LintFinding {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub issues: Vec<LintIssue>,
+}
+
+/// 画素から求めた指標
+#[derive(Debug, Clone, Copy)]
+struct ImageMetrics {
+    width: u32,
+    height: u32,
+    sharpness: f64,
+    brightness: f64,
+    dark_ratio: f64,
+    bright_ratio: f64,
+}
+
+fn measure(path: &Path) -> Result<ImageMetrics> {
+    let img = image_crate::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| PhotoAiError::ImageLoad(format!("{}: {}", path.display(), e)))?
+        .decode()
+        .map_err(|e| PhotoAiError::ImageLoad(format!("{}: {}", path.display(), e)))?;
+    let gray = img.resize(SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Triangle).to_luma8();
+    let (dark_ratio, bright_ratio) = clipped_ratios(&gray);
+    Ok(ImageMetrics {
+        width: img.width(),
+        height: img.height(),
+        sharpness: laplacian_variance(&gray),
+        brightness: mean_brightness(&gray),
+        dark_ratio,
+        bright_ratio,
+    })
+}
+
+/// 黒つぶれ・白飛びした画素の割合
+fn clipped_ratios(gray: &GrayImage) -> (f64, f64) {
+    let pixels = gray.as_raw();
+    if pixels.is_empty() {
+        return (0.0, 0.0);
+    }
+    let dark = pixels.iter().filter(|&&p| p <= DARK_CLIP).count();
+    let bright = pixels.iter().filter(|&&p| p >= BRIGHT_CLIP).count();
+    let total = pixels.len() as f64;
+    (dark as f64 / total, bright as f64 / total)
+}
+
+/// 指標をしきい値で判定
+fn judge(metrics: &ImageMetrics, t: &LintThresholds) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    if metrics.sharpness < t.blur {
+        issues.push(LintIssue::Blurry { sharpness: metrics.sharpness });
+    }
+    if metrics.brightness < t.dark || metrics.dark_ratio > t.clipped_ratio {
+        issues.push(LintIssue::Underexposed { brightness: metrics.brightness });
+    } else if metrics.brightness > t.bright || metrics.bright_ratio > t.clipped_ratio {
+        issues.push(LintIssue::Overexposed { brightness: metrics.brightness });
+    }
+    if (metrics.width as u64) * (metrics.height as u64) < t.min_pixels {
+        issues.push(LintIssue::LowResolution { width: metrics.width, height: metrics.height });
+    }
+    let (long, short) = (metrics.width.max(metrics.height), metrics.width.min(metrics.height));
+    if short > 0 {
+        let ratio = long as f64 / short as f64;
+        if ratio > t.max_aspect {
+            issues.push(LintIssue::ExtremeAspect { ratio });
+        }
+    }
+    issues
+}
+
+/// 1枚を検査
+pub fn lint_image(path: &Path, thresholds: &LintThresholds) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    if let Ok(meta) = std::fs::metadata(path) {
+        if meta.len() < thresholds.min_file_size {
+            issues.push(LintIssue::TinyFile { bytes: meta.len() });
+        }
+    }
+    match measure(path) {
+        Ok(metrics) => issues.extend(judge(&metrics, thresholds)),
+        Err(e) => issues.push(LintIssue::Unreadable(e.to_string())),
+    }
+    issues
+}
+
+/// 全画像を並列に検査（問題のあった写真のみ、入力順で返す）
+pub fn lint_images(images: &[ImageInfo], thresholds: &LintThresholds) -> Vec<LintFinding> {
+    images
+        .par_iter()
+        .map(|img| LintFinding {
+            file_name: img.file_name.clone(),
+            path: img.path.clone(),
+            issues: lint_image(&img.path, thresholds),
+        })
+        .filter(|finding| !finding.issues.is_empty())
+        .collect()
+}
+
+/// 解析結果から黒板の判読性を検査し、所見に加える
+///
+/// 黒板ありと判定されたのに文字を1つも読み取れていない写真を対象にする
+pub fn add_board_findings(findings: &mut Vec<LintFinding>, results: &[AnalysisResult]) {
+    for result in results {
+        if !result.has_board || !result.detected_text.trim().is_empty() {
+            continue;
+        }
+        match findings.iter_mut().find(|f| f.file_name == result.file_name) {
+            Some(finding) => finding.issues.push(LintIssue::BoardIllegible),
+            None => findings.push(LintFinding {
+                file_name: result.file_name.clone(),
+                path: PathBuf::from(&result.file_path),
+                issues: vec![LintIssue::BoardIllegible],
+            }),
+        }
+    }
+}
+
+/// lint が `warnings` に書き込む警告の接頭辞（マスタ整合性など他の警告と区別する）
+pub const WARNING_PREFIX: &str = "画質: ";
+
+/// 解析結果の `warnings` のうち lint の警告を検査結果で置き換え、警告のついた件数を返す
+///
+/// マスタ整合性チェックなど他の処理が書いた警告は残す
+pub fn apply_warnings(results: &mut [AnalysisResult], findings: &[LintFinding]) -> usize {
+    let by_name: HashMap<&str, &LintFinding> = findings.iter().map(|f| (f.file_name.as_str(), f)).collect();
+    let mut count = 0;
+    for result in results {
+        result.warnings.retain(|w| !w.starts_with(WARNING_PREFIX));
+        if let Some(finding) = by_name.get(result.file_name.as_str()) {
+            result.warnings.extend(finding.issues.iter().map(|i| format!("{}{}", WARNING_PREFIX, i)));
+            count += 1;
+        }
+    }
+    count
+}
+
+/// レポート1行
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportRow<'a> {
+    file_name: &'a str,
+    path: String,
+    code: &'a str,
+    message: String,
+}
+
+/// レポートを書き出す（拡張子 .json は JSON、それ以外は Excel で開ける BOM 付き CSV）
+pub fn write_report(findings: &[LintFinding], path: &Path) -> Result<()> {
+    let rows: Vec<ReportRow> = findings
+        .iter()
+        .flat_map(|f| {
+            f.issues.iter().map(move |issue| ReportRow {
+                file_name: &f.file_name,
+                path: f.path.display().to_string(),
+                code: issue.code(),
+                message: issue.to_string(),
+            })
+        })
+        .collect();
+
+    let is_json = path
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    let content = if is_json {
+        serde_json::to_string_pretty(&rows)?
+    } else {
+        let mut csv = String::from("\u{feff}ファイル名,パス,種類,内容\n");
+        for row in &rows {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                csv_field(row.file_name),
+                csv_field(&row.path),
+                row.code,
+                csv_field(&row.message)
+            ));
+        }
+        csv
+    };
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image_crate::{Luma, RgbImage};
+
+    fn metrics(width: u32, height: u32, sharpness: f64, brightness: f64) -> ImageMetrics {
+        ImageMetrics { width, height, sharpness, brightness, dark_ratio: 0.0, bright_ratio: 0.0 }
+    }
+
+    #[test]
+    fn test_judge_thresholds() {
+        let t = LintThresholds::default();
+        assert!(judge(&metrics(4000, 3000, 500.0, 0.5), &t).is_empty());
+
+        let issues = judge(&metrics(640, 480, 10.0, 0.05), &t);
+        let codes: Vec<_> = issues.iter().map(|i| i.code()).collect();
+        assert_eq!(codes, vec!["blur", "underexposed", "resolution"]);
+
+        assert_eq!(judge(&metrics(4000, 3000, 500.0, 0.95), &t)[0].code(), "overexposed");
+        assert_eq!(judge(&metrics(6000, 2000, 500.0, 0.5), &t)[0], LintIssue::ExtremeAspect { ratio: 3.0 });
+
+        // 平均輝度は正常でも白飛びが多ければ露出過多
+        let clipped = ImageMetrics { bright_ratio: 0.6, ..metrics(4000, 3000, 500.0, 0.6) };
+        assert_eq!(judge(&clipped, &t)[0].code(), "overexposed");
+    }
+
+    #[test]
+    fn test_lint_image_files() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // 細かい市松模様（鮮明・中間輝度）
+        let sharp = dir.path().join("sharp.png");
+        GrayImage::from_fn(1200, 900, |x, y| Luma([if (x / 2 + y / 2) % 2 == 0 { 40 } else { 210 }]))
+            .save(&sharp)
+            .unwrap();
+        let issues = lint_image(&sharp, &LintThresholds { min_file_size: 0, ..Default::default() });
+        assert!(issues.is_empty(), "{:?}", issues);
+
+        // 真っ黒で小さい画像
+        let dark = dir.path().join("dark.png");
+        RgbImage::new(320, 240).save(&dark).unwrap();
+        let codes: Vec<_> = lint_image(&dark, &LintThresholds::default()).iter().map(|i| i.code()).collect();
+        assert_eq!(codes, vec!["tiny-file", "blur", "underexposed", "resolution"]);
+
+        let broken = dir.path().join("broken.jpg");
+        std::fs::write(&broken, b"not an image").unwrap();
+        assert!(matches!(lint_image(&broken, &LintThresholds::default()).last(), Some(LintIssue::Unreadable(_))));
+    }
+
+    #[test]
+    fn test_board_findings_and_warnings() {
+        let mut results = vec![
+            AnalysisResult { file_name: "a.jpg".into(), has_board: true, ..Default::default() },
+            AnalysisResult { file_name: "b.jpg".into(), has_board: true, detected_text: "No.5".into(), ..Default::default() },
+            AnalysisResult {
+                file_name: "c.jpg".into(),
+                warnings: vec!["画質: 古い警告".into(), "備考がマスタにない".into()],
+                ..Default::default()
+            },
+        ];
+        let mut findings = vec![LintFinding {
+            file_name: "a.jpg".into(),
+            path: PathBuf::from("a.jpg"),
+            issues: vec![LintIssue::Blurry { sharpness: 12.0 }],
+        }];
+        add_board_findings(&mut findings, &results);
+        assert_eq!(findings[0].issues.len(), 2);
+
+        assert_eq!(apply_warnings(&mut results, &findings), 1);
+        assert_eq!(results[0].warnings, vec!["画質: ピンぼけの可能性 (鮮明度 12)", "画質: 黒板の文字を読み取れない"]);
+        assert!(results[1].warnings.is_empty());
+        // lint 以外の警告は残す
+        assert_eq!(results[2].warnings, vec!["備考がマスタにない"]);
+    }
+
+    #[test]
+    fn test_write_csv_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lint.csv");
+        let findings = vec![LintFinding {
+            file_name: "a,b.jpg".into(),
+            path: PathBuf::from("a,b.jpg"),
+            issues: vec![LintIssue::TinyFile { bytes: 2048 }],
+        }];
+        write_report(&findings, &path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with('\u{feff}'));
+        assert!(content.contains("\"a,b.jpg\",\"a,b.jpg\",tiny-file,ファイルサイズが小さい (2KB)"));
+    }
+}
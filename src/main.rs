@@ -1,6 +1,6 @@
 use clap::Parser;
-use photo_ai_rust::{ai_provider::AiProvider, cli, config, error, scanner, analyzer, matcher, export, station, master_selector};
-use cli::{Cli, Commands};
+use photo_ai_rust::{ai_provider::AiProvider, alignment, cli, ordering, config, error, watch, lint, scanner, analyzer, matcher, export, station, master_selector, master_tool, master_learn, alias_suggest, qc};
+use cli::{AliasCommand, Cli, Commands, MasterCommand};
 use config::Config;
 use error::Result;
 use photo_ai_common::HierarchyMaster;
@@ -13,7 +13,7 @@ async fn run_analysis(
     folder: &Path,
     batch_size: usize,
     verbose: bool,
-    master: Option<&Path>,
+    master: Option<&master_tool::MasterStack>,
     use_cache: bool,
     provider: AiProvider,
     work_type: Option<&str>,
@@ -24,18 +24,18 @@ async fn run_analysis(
     // 工種指定時は1ステップ解析（推奨）
     if let Some(wt) = work_type {
         // マスタパスを決定
-        let master_path_buf: PathBuf = if let Some(mp) = master {
-            mp.to_path_buf()
+        let master_stack: master_tool::MasterStack = if let Some(mp) = master {
+            mp.clone()
         } else {
             // 工種別マスタを自動選択
             let by_work_type = PathBuf::from("master/by_work_type").join(format!("{}.csv", wt));
             if by_work_type.exists() {
-                by_work_type
+                by_work_type.into()
             } else {
                 // デフォルトマスタ
                 let default = PathBuf::from("master/construction_hierarchy.csv");
                 if default.exists() {
-                    default
+                    default.into()
                 } else {
                     return Err(error::PhotoAiError::MasterLoad("マスタファイルが見つかりません".to_string()));
                 }
@@ -43,8 +43,7 @@ async fn run_analysis(
         };
 
         println!("{} 1ステップ解析中 (工種: {})...", step_prefix, wt);
-        let hierarchy = HierarchyMaster::from_csv(&master_path_buf)
-            .map_err(|e| error::PhotoAiError::MasterLoad(e.to_string()))?;
+        let hierarchy = master_stack.load()?;
 
         // 指定工種でマスタをフィルタ
         let filtered = hierarchy.filter_by_work_types(&[wt.to_string()]);
@@ -74,6 +73,50 @@ async fn run_analysis(
     }
 }
 
+/// 写真をスキャンし、スキップしたファイルを理由付きで報告
+///
+/// 除外ルールで対象外にしたファイルは件数のみ、verbose 時は一致したルールも表示
+fn scan_images(folder: &Path, options: &scanner::ScanOptions, verbose: bool) -> Result<Vec<scanner::ImageInfo>> {
+    let report = scanner::scan(folder, options)?;
+    if !report.skipped.is_empty() {
+        println!("  ⚠ スキップ: {}件", report.skipped.len());
+        for skipped in &report.skipped {
+            println!("    {} ({})", skipped.path.display(), skipped.reason);
+        }
+    }
+    if !report.excluded.is_empty() {
+        println!("  除外: {}件 (--include-all で含める)", report.excluded.len());
+        if verbose {
+            for excluded in &report.excluded {
+                println!(
+                    "    {} ({} [{}])",
+                    excluded.path.display(),
+                    excluded.rule.pattern,
+                    excluded.rule.source
+                );
+            }
+        }
+    }
+    Ok(report.images)
+}
+
+/// 類似写真を検出し、ベストショット以外を解析対象から外す
+fn dedupe_images(images: Vec<scanner::ImageInfo>, verbose: bool) -> Vec<scanner::ImageInfo> {
+    use scanner::dedupe;
+
+    let mut images = images;
+    let failed = dedupe::compute_fingerprints(&mut images);
+    if verbose {
+        for name in &failed {
+            println!("  ⚠ ハッシュ計算失敗: {}", name);
+        }
+    }
+    let clusters = dedupe::find_clusters(&images, dedupe::DEFAULT_THRESHOLD);
+    let kept = dedupe::exclude_duplicates(&images, &clusters);
+    println!("  類似写真: {}グループ, {}枚を解析対象外", clusters.len(), images.len() - kept.len());
+    kept
+}
+
 /// 測点を一括適用
 fn apply_station(results: &mut [analyzer::AnalysisResult], station: &str) {
     for result in results {
@@ -81,14 +124,77 @@ fn apply_station(results: &mut [analyzer::AnalysisResult], station: &str) {
     }
 }
 
-fn resolve_master_path(master: Option<PathBuf>, interactive: bool) -> Option<master_selector::MasterSelection> {
-    if let Some(path) = master {
-        // パスからwork_typeを推定（by_work_type/xxx.csv → xxx）
-        let work_type = path.file_stem()
+/// 工種マスタの行順 → 測点 → 撮影日時で並べ替え
+fn sort_by_master(results: &mut [analyzer::AnalysisResult], master: &master_tool::MasterStack) -> Result<()> {
+    let hierarchy = master.load()?;
+    ordering::sort_by_master(results, &hierarchy);
+    println!("  台帳順に並べ替え: {}", master);
+    Ok(())
+}
+
+/// フォルダ構成テンプレートを決定（CLI引数 → config.json の順）
+fn resolve_path_template(
+    arg: Option<String>,
+    config: &Config,
+    recursive: bool,
+) -> Result<Option<scanner::PathTemplate>> {
+    let Some(spec) = arg.or_else(|| config.path_template.clone()) else {
+        return Ok(None);
+    };
+    if !recursive {
+        println!("  ⚠ フォルダ構成テンプレートはサブフォルダの写真にのみ適用されます (--recursive)");
+    }
+    println!("  フォルダ構成: {}", spec);
+    scanner::PathTemplate::parse(&spec).map(Some)
+}
+
+/// フォルダ情報とAIの判断が矛盾した写真を報告（フォルダ情報は適用していない）
+fn report_hint_conflicts(results: &[analyzer::AnalysisResult]) {
+    let conflicts: Vec<_> = results.iter().filter(|r| !r.hint_conflict.is_empty()).collect();
+    if conflicts.is_empty() {
+        return;
+    }
+    println!("  ⚠ フォルダ情報と矛盾: {}枚（AIの判断を採用、要確認）", conflicts.len());
+    for r in conflicts {
+        println!("    {}: {}", r.file_name, r.hint_conflict);
+    }
+}
+
+/// 道路中心線から GPS 位置の測点を推定（測点が空の写真のみ）
+fn apply_alignment(
+    results: &mut [analyzer::AnalysisResult],
+    alignment_path: &Path,
+    max_offset: f64,
+    verbose: bool,
+) -> Result<()> {
+    let centerline = alignment::Alignment::from_csv(alignment_path)?;
+    let report = alignment::assign_stations(results, &centerline, max_offset);
+    println!(
+        "  GPS測点推定: {}枚 (GPSなし: {}枚, 中心線から{}m超: {}枚)",
+        report.assigned.len(),
+        report.no_gps.len(),
+        max_offset,
+        report.too_far.len()
+    );
+    for (name, p) in &report.too_far {
+        println!("    ⚠ {} は中心線から{:.1}m離れています（{} 付近）", name, p.offset, p.station());
+    }
+    if verbose {
+        for (name, p) in &report.assigned {
+            println!("    {} → {} (中心線から{:.1}m)", name, p.station(), p.offset);
+        }
+    }
+    Ok(())
+}
+
+fn resolve_master_path(master: Option<master_tool::MasterStack>, interactive: bool) -> Option<master_selector::MasterSelection> {
+    if let Some(master) = master {
+        // ベースのパスからwork_typeを推定（by_work_type/xxx.csv → xxx）
+        let work_type = master.base.file_stem()
             .and_then(|s| s.to_str())
             .filter(|s| *s != "construction_hierarchy")
             .map(|s| s.to_string());
-        return Some(master_selector::MasterSelection { path, work_type });
+        return Some(master_selector::MasterSelection { master, work_type });
     }
 
     // 対話式選択
@@ -99,7 +205,7 @@ fn resolve_master_path(master: Option<PathBuf>, interactive: bool) -> Option<mas
     // デフォルトマスタ
     let default_path = PathBuf::from("master").join("construction_hierarchy.csv");
     if default_path.exists() {
-        Some(master_selector::MasterSelection { path: default_path, work_type: None })
+        Some(master_selector::MasterSelection { master: default_path.into(), work_type: None })
     } else {
         None
     }
@@ -111,7 +217,7 @@ async fn main() -> Result<()> {
     let config = Config::load()?;
 
     match cli.command {
-        Commands::Analyze { folder, output, batch_size, master, work_type, variety, station, use_cache, recursive, include_all } => {
+        Commands::Analyze { folder, output, batch_size, master, work_type, variety, station, alignment, max_offset, use_cache, recursive, include_all, dedupe, heic, path_template, sort, camera_offset, skip } => {
             println!("📸 photo-ai-rust - 写真解析\n");
 
             // マスタ選択（対話式または引数から）
@@ -120,7 +226,7 @@ async fn main() -> Result<()> {
 
             // work_type: CLI引数優先、なければ選択結果から
             let effective_work_type = work_type.or_else(|| selection.as_ref().and_then(|s| s.work_type.clone()));
-            let master_path = selection.map(|s| s.path);
+            let master_path = selection.map(|s| s.master);
             if variety.is_some() && effective_work_type.is_none() {
                 return Err(error::PhotoAiError::InvalidMaster(
                     "variety指定にはwork_typeが必要です".to_string(),
@@ -131,12 +237,28 @@ async fn main() -> Result<()> {
                     "work_type指定にはマスタが必要です".to_string(),
                 ));
             }
+            if sort.needs_analysis() && master_path.is_none() {
+                return Err(error::PhotoAiError::MasterLoad(
+                    "--sort master にはマスタが必要です".to_string(),
+                ));
+            }
+
+            // 正規化の規則（.photo-ai/normalize.json）は解析前に読み込み、誤りがあれば解析せずに止める
+            let mut normalize_options = photo_ai_rust::normalizer::NormalizationOptions::without(&skip);
+            normalize_options.rules = photo_ai_rust::normalizer::NormalizationRules::load(None)?;
 
             // 1. 画像スキャン
             println!("[1/3] 写真をスキャン中...{}", if recursive { " (再帰)" } else { "" });
-            let images = scanner::scan_folder_full(&folder, recursive, !include_all)?;
+            let path_template = resolve_path_template(path_template, &config, recursive)?;
+            let scan_options = scanner::ScanOptions { recursive, exclude: !include_all, convert_heic: heic, path_template };
+            let mut images = scan_images(&folder, &scan_options, cli.verbose)?;
+            ordering::sort_images(&mut images, sort, &camera_offset);
             println!("✔ {}枚の写真を検出\n", images.len());
 
+            if dedupe {
+                images = dedupe_images(images, cli.verbose);
+            }
+
             if images.is_empty() {
                 return Err(error::PhotoAiError::NoImagesFound(
                     folder.display().to_string()
@@ -149,7 +271,7 @@ async fn main() -> Result<()> {
                 &folder,
                 batch_size,
                 cli.verbose,
-                master_path.as_deref(),
+                master_path.as_ref(),
                 use_cache,
                 cli.ai_provider,
                 effective_work_type.as_deref(),
@@ -158,6 +280,12 @@ async fn main() -> Result<()> {
                 "[2/3]",
             ).await?;
             println!("✔ 解析完了\n");
+            report_hint_conflicts(&results);
+
+            // GPS位置から測点推定
+            if let Some(ref path) = alignment {
+                apply_alignment(&mut results, path, max_offset, cli.verbose)?;
+            }
 
             // 測点一括適用
             if let Some(ref st) = station {
@@ -165,22 +293,26 @@ async fn main() -> Result<()> {
                 apply_station(&mut results, st);
             }
 
-            // 正規化（3枚セット内で黒板アップの値に統一）
+            // 正規化（写真セット・作業単位で項目を統一）
             {
-                use photo_ai_rust::normalizer::{self, NormalizationOptions};
-                let options = NormalizationOptions::default();
-                let norm_result = normalizer::normalize_results(&results, &options);
+                use photo_ai_rust::normalizer;
+                let norm_result = normalizer::normalize_results(&results, &normalize_options);
                 if !norm_result.corrections.is_empty() {
+                    println!("  正規化: {}件 (--skip で段を省略)", norm_result.corrections.len());
                     if cli.verbose {
-                        println!("  計測値統一: {}件", norm_result.stats.measurement_corrections);
                         for c in &norm_result.corrections {
-                            println!("    {} → {} ({})", c.file_name, c.corrected, c.reason);
+                            println!("    {} [{}] → {} ({})", c.file_name, c.field, c.corrected, c.reason_with_rule());
                         }
                     }
                     normalizer::apply_corrections(&mut results, &norm_result.corrections);
                 }
             }
 
+            // 台帳順に並べ替え（工種マスタの行順 → 測点 → 撮影日時）
+            if let (true, Some(path)) = (sort.needs_analysis(), master_path.as_ref()) {
+                sort_by_master(&mut results, path)?;
+            }
+
             // 3. 結果保存
             println!("[3/3] 結果を保存中...");
             let output_path = output.unwrap_or_else(|| folder.join("result.json"));
@@ -191,7 +323,7 @@ async fn main() -> Result<()> {
             println!("\n✅ 解析完了");
         }
 
-        Commands::Export { input, format, output, photos_per_page, title, pdf_quality, preset, alias } => {
+        Commands::Export { input, format, output, photos_per_page, title, pdf_quality, preset, alias, sort, camera_offset, master } => {
             println!("📄 photo-ai-rust - エクスポート\n");
 
             let content = std::fs::read_to_string(&input)?;
@@ -211,14 +343,29 @@ async fn main() -> Result<()> {
             }
 
             // エイリアス変換を適用
-            if preset.is_some() || alias.is_some() {
+            if !preset.is_empty() || alias.is_some() {
                 println!("- エイリアス変換中...");
-                results = matcher::apply_aliases(
+                let registry = matcher::alias::load_registry();
+                let (aliased, report) = matcher::apply_aliases(
                     &results,
-                    preset.as_deref(),
+                    &registry,
+                    &preset,
                     alias.as_deref(),
                 )?;
-                println!("✔ エイリアス変換完了");
+                results = aliased;
+                println!("✔ エイリアス変換完了（{}件）", report.hits.len());
+                matcher::alias::print_report(&report);
+            }
+
+            if let Some(sort) = sort {
+                if sort.needs_analysis() && master.is_none() {
+                    return Err(error::PhotoAiError::MasterLoad(
+                        "--sort master には --master が必要です".to_string(),
+                    ));
+                }
+                let hierarchy = master.as_ref().map(master_tool::MasterStack::load).transpose()?;
+                ordering::sort_results(&mut results, sort, &camera_offset, hierarchy.as_ref());
+                println!("- 並び順: {}", sort);
             }
 
             let output_dir = output.unwrap_or_else(|| std::path::PathBuf::from("."));
@@ -228,7 +375,7 @@ async fn main() -> Result<()> {
             println!("\n✅ エクスポート完了");
         }
 
-        Commands::Run { folder, output, format, batch_size, master, work_type, variety, station, pdf_quality, use_cache, recursive, include_all } => {
+        Commands::Run { folder, output, format, batch_size, master, work_type, variety, station, alignment, max_offset, pdf_quality, use_cache, recursive, include_all, dedupe, heic, path_template, sort, camera_offset, skip } => {
             println!("🚀 photo-ai-rust - 一括処理\n");
 
             // マスタ選択（対話式または引数から）
@@ -237,7 +384,7 @@ async fn main() -> Result<()> {
 
             // work_type: CLI引数優先、なければ選択結果から
             let effective_work_type = work_type.or_else(|| selection.as_ref().and_then(|s| s.work_type.clone()));
-            let master_path = selection.map(|s| s.path);
+            let master_path = selection.map(|s| s.master);
             if variety.is_some() && effective_work_type.is_none() {
                 return Err(error::PhotoAiError::InvalidMaster(
                     "variety指定にはwork_typeが必要です".to_string(),
@@ -248,12 +395,28 @@ async fn main() -> Result<()> {
                     "work_type指定にはマスタが必要です".to_string(),
                 ));
             }
+            if sort.needs_analysis() && master_path.is_none() {
+                return Err(error::PhotoAiError::MasterLoad(
+                    "--sort master にはマスタが必要です".to_string(),
+                ));
+            }
+
+            // 正規化の規則（.photo-ai/normalize.json）は解析前に読み込み、誤りがあれば解析せずに止める
+            let mut normalize_options = photo_ai_rust::normalizer::NormalizationOptions::without(&skip);
+            normalize_options.rules = photo_ai_rust::normalizer::NormalizationRules::load(None)?;
 
             // 1. Scan
             println!("[1/4] 写真をスキャン中...{}", if recursive { " (再帰)" } else { "" });
-            let images = scanner::scan_folder_full(&folder, recursive, !include_all)?;
+            let path_template = resolve_path_template(path_template, &config, recursive)?;
+            let scan_options = scanner::ScanOptions { recursive, exclude: !include_all, convert_heic: heic, path_template };
+            let mut images = scan_images(&folder, &scan_options, cli.verbose)?;
+            ordering::sort_images(&mut images, sort, &camera_offset);
             println!("✔ {}枚の写真を検出\n", images.len());
 
+            if dedupe {
+                images = dedupe_images(images, cli.verbose);
+            }
+
             if images.is_empty() {
                 return Err(error::PhotoAiError::NoImagesFound(
                     folder.display().to_string()
@@ -266,7 +429,7 @@ async fn main() -> Result<()> {
                 &folder,
                 batch_size,
                 cli.verbose,
-                master_path.as_deref(),
+                master_path.as_ref(),
                 use_cache,
                 cli.ai_provider,
                 effective_work_type.as_deref(),
@@ -275,6 +438,12 @@ async fn main() -> Result<()> {
                 "[2/4]",
             ).await?;
             println!("✔ 解析完了\n");
+            report_hint_conflicts(&results);
+
+            // GPS位置から測点推定
+            if let Some(ref path) = alignment {
+                apply_alignment(&mut results, path, max_offset, cli.verbose)?;
+            }
 
             // 測点一括適用
             if let Some(ref st) = station {
@@ -282,22 +451,26 @@ async fn main() -> Result<()> {
                 apply_station(&mut results, st);
             }
 
-            // 正規化（3枚セット内で黒板アップの値に統一）
+            // 正規化（写真セット・作業単位で項目を統一）
             {
-                use photo_ai_rust::normalizer::{self, NormalizationOptions};
-                let options = NormalizationOptions::default();
-                let norm_result = normalizer::normalize_results(&results, &options);
+                use photo_ai_rust::normalizer;
+                let norm_result = normalizer::normalize_results(&results, &normalize_options);
                 if !norm_result.corrections.is_empty() {
+                    println!("  正規化: {}件 (--skip で段を省略)", norm_result.corrections.len());
                     if cli.verbose {
-                        println!("  計測値統一: {}件", norm_result.stats.measurement_corrections);
                         for c in &norm_result.corrections {
-                            println!("    {} → {} ({})", c.file_name, c.corrected, c.reason);
+                            println!("    {} [{}] → {} ({})", c.file_name, c.field, c.corrected, c.reason_with_rule());
                         }
                     }
                     normalizer::apply_corrections(&mut results, &norm_result.corrections);
                 }
             }
 
+            // 台帳順に並べ替え（工種マスタの行順 → 測点 → 撮影日時）
+            if let (true, Some(path)) = (sort.needs_analysis(), master_path.as_ref()) {
+                sort_by_master(&mut results, path)?;
+            }
+
             // 3. 結果保存
             // output がファイルパス(.pdf等)の場合、result.json は入力フォルダに保存
             let (json_dir, export_path) = if let Some(ref out) = output {
@@ -325,6 +498,353 @@ async fn main() -> Result<()> {
             println!("\n✅ 完了");
         }
 
+        Commands::Watch { folder, output, batch_size, debounce, pdf_every, pdf_output, title, pdf_quality, recursive, include_all, heic, path_template } => {
+            println!("📸 photo-ai-rust - フォルダ監視\n");
+
+            let path_template = resolve_path_template(path_template, &config, recursive)?;
+            let scan = scanner::ScanOptions { recursive, exclude: !include_all, convert_heic: heic, path_template };
+            let pdf = pdf_every.map(|minutes| watch::PdfSchedule {
+                interval: std::time::Duration::from_secs(minutes * 60),
+                output: pdf_output.unwrap_or_else(|| folder.clone()),
+                title,
+                quality: pdf_quality,
+            });
+            let options = watch::WatchOptions {
+                scan,
+                output: output.unwrap_or_else(|| folder.join("result.json")),
+                batch_size,
+                debounce: std::time::Duration::from_secs(debounce),
+                pdf,
+                provider: cli.ai_provider,
+                verbose: cli.verbose,
+            };
+            watch::run(&folder, &options).await?;
+        }
+
+        Commands::Lint { folder, result, report, blur_threshold, min_pixels, recursive, include_all, heic } => {
+            println!("🔎 photo-ai-rust - 画質チェック\n");
+
+            let scan_options = scanner::ScanOptions { recursive, exclude: !include_all, convert_heic: heic, ..Default::default() };
+            let images = scan_images(&folder, &scan_options, cli.verbose)?;
+            if images.is_empty() {
+                return Err(error::PhotoAiError::NoImagesFound(
+                    folder.display().to_string()
+                ));
+            }
+
+            println!("- {}枚を検査中...", images.len());
+            let thresholds = lint::LintThresholds { blur: blur_threshold, min_pixels, ..Default::default() };
+            let mut findings = lint::lint_images(&images, &thresholds);
+
+            if let Some(ref result_path) = result {
+                let content = std::fs::read_to_string(result_path)?;
+                let mut results: Vec<analyzer::AnalysisResult> = serde_json::from_str(&content)?;
+                lint::add_board_findings(&mut findings, &results);
+                let warned = lint::apply_warnings(&mut results, &findings);
+                std::fs::write(result_path, serde_json::to_string_pretty(&results)?)?;
+                println!("✔ 警告を書き込み: {}件 ({})", warned, result_path.display());
+            }
+
+            for finding in &findings {
+                println!("  ⚠ {}", finding.file_name);
+                for issue in &finding.issues {
+                    println!("      {}", issue);
+                }
+            }
+
+            if let Some(ref report_path) = report {
+                lint::write_report(&findings, report_path)?;
+                println!("✔ レポート出力: {}", report_path.display());
+            }
+
+            println!("\n✅ 検査完了: {}枚中 {}枚に問題", images.len(), findings.len());
+        }
+
+        Commands::Qc { input, spec, report, write } => {
+            println!("📏 photo-ai-rust - 品質管理チェック\n");
+
+            let spec_path = spec.unwrap_or_else(|| PathBuf::from(qc::PROJECT_SPEC_FILE));
+            let table = qc::QcSpecTable::from_file(&spec_path)?;
+            println!("- 規格表: {} ({}件)", spec_path.display(), table.specs.len());
+
+            let content = std::fs::read_to_string(&input)?;
+            let mut results: Vec<analyzer::AnalysisResult> = serde_json::from_str(&content)?;
+            let checks = table.evaluate(&results)?;
+
+            for check in checks.iter().filter(|c| c.status != qc::QcStatus::Pass) {
+                println!("  ✗ [{}] {}: {}", check.status, check.file_name, check);
+            }
+
+            if write {
+                let failed = qc::apply_failures(&mut results, &checks);
+                std::fs::write(&input, serde_json::to_string_pretty(&results)?)?;
+                println!("✔ 不合格を書き込み: {}枚 ({})", failed, input.display());
+            }
+
+            if let Some(ref report_path) = report {
+                qc::write_report(&checks, report_path)?;
+                println!("✔ レポート出力: {}", report_path.display());
+            }
+
+            let (pass, fail, missing) = qc::summarize(&checks);
+            println!("\n✅ 判定完了: 合格 {} / 不合格 {} / 未測定 {}", pass, fail, missing);
+        }
+
+        Commands::Alias { command } => match command {
+            AliasCommand::List => {
+                println!("📚 photo-ai-rust - エイリアスプリセット\n");
+                matcher::alias::print_presets(&matcher::alias::load_registry());
+            }
+
+            AliasCommand::Show { name } => {
+                let registry = matcher::alias::load_registry();
+                let preset = registry.find(&name)?;
+                eprintln!("# {} [{}]", preset.name, preset.source);
+                println!("{}", serde_json::to_string_pretty(preset)?);
+            }
+
+            AliasCommand::Suggest { sources, output, min_support } => {
+                println!("💡 photo-ai-rust - 修正からエイリアスを提案\n");
+
+                let mut observations = Vec::new();
+                for source in &sources {
+                    match alias_suggest::read_source(source) {
+                        Ok(found) => {
+                            let changed = found.iter().filter(|o| o.before != o.after).count();
+                            println!("  {} ({}件中 修正{}件)", source, found.len(), changed);
+                            observations.extend(found);
+                        }
+                        Err(e) => eprintln!("  ⚠ {}: {}", source, e),
+                    }
+                }
+
+                let suggestions = alias_suggest::suggest(&observations, min_support);
+                println!();
+                for s in &suggestions {
+                    println!("  {}: {} → {}  ({}/{}件)", s.field, s.from, s.to, s.support, s.seen);
+                }
+                if suggestions.is_empty() {
+                    println!("  提案できる規則はありません（--min-support {}）", min_support);
+                    return Ok(());
+                }
+
+                match output {
+                    Some(path) => {
+                        let config = alias_suggest::to_config(&suggestions);
+                        std::fs::write(&path, serde_json::to_string_pretty(&config)? + "\n")?;
+                        println!("\n✅ {}件の規則 → {}", suggestions.len(), path.display());
+                    }
+                    None => println!("\n{}件の規則（-o で書き出し）", suggestions.len()),
+                }
+            }
+        },
+
+        Commands::Master { command } => match command {
+            MasterCommand::Validate { file, strict } => {
+                println!("🔎 photo-ai-rust - マスタ検査\n");
+
+                let loaded = HierarchyMaster::load_path(&file)?;
+                for issue in &loaded.issues {
+                    let mark = if issue.is_error() { "✖" } else { "⚠" };
+                    println!("  {} {}", mark, issue);
+                }
+
+                let errors = loaded.issues.iter().filter(|i| i.is_error()).count();
+                let warnings = loaded.issues.len() - errors;
+                println!(
+                    "\n{}: {}行, 工種{}件, エラー{}件, 警告{}件",
+                    file.display(),
+                    loaded.master.rows().len(),
+                    loaded.master.get_work_types().len(),
+                    errors,
+                    warnings
+                );
+                if errors > 0 || (strict && warnings > 0) {
+                    return Err(error::PhotoAiError::InvalidMaster(format!(
+                        "{} (エラー{}件, 警告{}件)",
+                        file.display(),
+                        errors,
+                        warnings
+                    )));
+                }
+                println!("✅ {}", if warnings > 0 { "エラーなし（警告あり）" } else { "問題なし" });
+            }
+
+            MasterCommand::Diff { before, after } => {
+                let diff = photo_ai_common::master_diff::diff(&master_tool::load(&before)?, &master_tool::load(&after)?);
+                println!("{} → {}", before.display(), after.display());
+                master_tool::print_diff(&diff);
+                println!("\n{}", master_tool::summary(&diff));
+            }
+
+            MasterCommand::Sync { master, dir, merge, dry_run } => {
+                println!("🔄 photo-ai-rust - マスタ同期{}\n", if dry_run { "（確認のみ）" } else { "" });
+
+                if merge {
+                    let diff = master_tool::sync_merge(&master, &dir, dry_run)?;
+                    println!("\n✅ {} に書き戻し: {}", master.display(), master_tool::summary(&diff));
+                } else {
+                    let updated = master_tool::sync_split(&master, &dir, dry_run)?;
+                    println!("\n✅ 工種別マスタ: {}件を更新", updated);
+                }
+            }
+
+            MasterCommand::Tree { master, depth, resolved } => {
+                let base = master.load_base()?;
+                let depth = depth.unwrap_or(photo_ai_common::master_edit::LEVEL_COUNT);
+                if resolved {
+                    let (effective, warnings) = master.resolve()?;
+                    master_tool::print_tree(&photo_ai_common::master_edit::tree(&effective), depth);
+                    println!("\n{}: {}行", master, effective.rows().len());
+                    let diff = photo_ai_common::master_diff::diff(&base, &effective);
+                    println!("  オーバーレイによる変更: {}", master_tool::summary(&diff));
+                    for warning in &warnings {
+                        println!("  ⚠ {}", warning);
+                    }
+                } else {
+                    master_tool::print_tree(&photo_ai_common::master_edit::tree(&base), depth);
+                    println!("\n{}: {}行", master.base.display(), base.rows().len());
+                    if !master.overlays.is_empty() {
+                        println!("  （--resolved でオーバーレイを重ねた結果を表示）");
+                    }
+                }
+            }
+
+            MasterCommand::Add { node, patterns, division, master, dry_run } => {
+                let before = master_tool::load(&master)?;
+                let node = photo_ai_common::NodePath::parse(&node)?;
+                let after = photo_ai_common::master_edit::add(&before, &node, &division, &patterns)?;
+                master_tool::write_edit(&master, &before, &after, dry_run)?;
+                println!("\n✅ {}「{}」を追加{}", node.level_name(), node.name(), if dry_run { "（確認のみ）" } else { "" });
+            }
+
+            MasterCommand::Rename { node, new_name, master, dry_run } => {
+                let before = master_tool::load(&master)?;
+                let node = photo_ai_common::NodePath::parse(&node)?;
+                let (after, count) = photo_ai_common::master_edit::rename(&before, &node, &new_name)?;
+                master_tool::write_edit(&master, &before, &after, dry_run)?;
+                println!(
+                    "\n✅ {}「{}」→「{}」: {}行{}",
+                    node.level_name(),
+                    node.name(),
+                    new_name,
+                    count,
+                    if dry_run { "（確認のみ）" } else { "" }
+                );
+            }
+
+            MasterCommand::Remove { node, master, dry_run } => {
+                let before = master_tool::load(&master)?;
+                let node = photo_ai_common::NodePath::parse(&node)?;
+                let (after, count) = photo_ai_common::master_edit::remove(&before, &node)?;
+                master_tool::write_edit(&master, &before, &after, dry_run)?;
+                println!(
+                    "\n✅ {}「{}」を削除: {}行{}",
+                    node.level_name(),
+                    node.name(),
+                    count,
+                    if dry_run { "（確認のみ）" } else { "" }
+                );
+            }
+
+            MasterCommand::Search { text, master } => {
+                let count = master_tool::print_search(&master.load()?, &text);
+                println!("\n{}件一致", count);
+            }
+
+            MasterCommand::Learn { inputs, output, base, min_count, max_patterns } => {
+                println!("📚 photo-ai-rust - 納品物からマスタ作成\n");
+
+                let mut photos = Vec::new();
+                for path in master_learn::collect_inputs(&inputs) {
+                    match master_learn::read_deliverable(&path) {
+                        Ok(found) => {
+                            println!("  {} ({}枚)", path.display(), found.len());
+                            photos.extend(found);
+                        }
+                        Err(e) => eprintln!("  ⚠ {}: {}", path.display(), e),
+                    }
+                }
+                if photos.is_empty() {
+                    return Err(error::PhotoAiError::InvalidMaster(
+                        "工種の入った写真が見つかりません".to_string(),
+                    ));
+                }
+
+                let options = master_learn::LearnOptions { min_count, max_patterns };
+                let learned = master_learn::learn(&photos, &options);
+                let base_master = match &base {
+                    Some(path) => master_tool::load(path)?,
+                    None => HierarchyMaster::default(),
+                };
+                let master = master_learn::extend_master(&base_master, &learned);
+
+                let diff = photo_ai_common::master_diff::diff(&base_master, &master);
+                master_tool::print_diff(&diff);
+                master
+                    .write_path(&output)
+                    .map_err(|e| error::PhotoAiError::MasterLoad(e.to_string()))?;
+                println!(
+                    "\n✅ {}枚から{}分類 → {} ({})",
+                    photos.len(),
+                    learned.len(),
+                    output.display(),
+                    master_tool::summary(&diff)
+                );
+            }
+        },
+
+        Commands::Dedupe { folder, threshold, move_rejected, exclude_dir, recursive, include_all, heic } => {
+            use scanner::dedupe;
+
+            println!("🔍 photo-ai-rust - 類似写真検出\n");
+
+            let scan_options = scanner::ScanOptions { recursive, exclude: !include_all, convert_heic: heic, ..Default::default() };
+            let mut images = scan_images(&folder, &scan_options, cli.verbose)?;
+            if images.is_empty() {
+                return Err(error::PhotoAiError::NoImagesFound(
+                    folder.display().to_string()
+                ));
+            }
+            println!("{}枚の写真をハッシュ計算中...", images.len());
+
+            let failed = dedupe::compute_fingerprints(&mut images);
+            for name in &failed {
+                println!("  ⚠ 読み込み失敗: {}", name);
+            }
+
+            let clusters = dedupe::find_clusters(&images, threshold);
+            if clusters.is_empty() {
+                println!("\n✓ 類似写真は見つかりませんでした");
+                return Ok(());
+            }
+
+            println!("\n📷 類似写真グループ: {}件", clusters.len());
+            for (i, cluster) in clusters.iter().enumerate() {
+                println!("\n[{}] {}枚", i + 1, cluster.members.len());
+                for &idx in &cluster.members {
+                    let img = &images[idx];
+                    let mark = if idx == cluster.best { "★" } else { "  " };
+                    match img.fingerprint {
+                        Some(fp) if cli.verbose => println!(
+                            "  {} {} (スコア {:.0}, シャープネス {:.0}, 輝度 {:.2}, hash {:016x})",
+                            mark, img.file_name, fp.quality_score(), fp.sharpness, fp.brightness, fp.phash
+                        ),
+                        Some(fp) => println!("  {} {} (スコア {:.0})", mark, img.file_name, fp.quality_score()),
+                        None => println!("  {} {}", mark, img.file_name),
+                    }
+                }
+            }
+
+            let rejected: usize = clusters.iter().map(|c| c.members.len() - 1).sum();
+            if move_rejected {
+                let moved = dedupe::move_duplicates(&images, &clusters, &exclude_dir)?;
+                println!("\n✔ {}枚を「{}」へ移動しました", moved.len(), exclude_dir);
+            } else {
+                println!("\n★ = ベストショット候補。残り{}枚は --move で「{}」へ移動できます", rejected, exclude_dir);
+            }
+        }
+
         Commands::Config { set_api_key, show } => {
             let mut config = config;
 
@@ -342,9 +862,17 @@ async fn main() -> Result<()> {
             }
         }
 
-        Commands::Station { input, output } => {
+        Commands::Station { input, output, alignment, max_offset } => {
             println!("📍 photo-ai-rust - 測点入力\n");
-            station::run_interactive_station(&input, output.as_deref())?;
+            let centerline = alignment
+                .as_deref()
+                .map(alignment::Alignment::from_csv)
+                .transpose()?;
+            station::run_interactive_station(
+                &input,
+                output.as_deref(),
+                centerline.as_ref().map(|c| (c, max_offset)),
+            )?;
         }
 
         Commands::Cache { clear, folder, info } => {
@@ -375,8 +903,8 @@ async fn main() -> Result<()> {
             }
         }
 
-        Commands::Normalize { input, output, station, dry_run } => {
-            use photo_ai_rust::normalizer::{self, NormalizationOptions};
+        Commands::Normalize { input, output, station, dry_run, skip, rules } => {
+            use photo_ai_rust::normalizer::{self, NormalizationOptions, NormalizationRules};
 
             println!("🔧 photo-ai-rust - 正規化\n");
 
@@ -392,7 +920,16 @@ async fn main() -> Result<()> {
             }
 
             // 正規化オプション
-            let options = NormalizationOptions::default();
+            let mut options = NormalizationOptions::without(&skip);
+            if !skip.is_empty() {
+                let names: Vec<String> = skip.iter().map(|p| p.to_string()).collect();
+                println!("省略する段: {}", names.join(", "));
+            }
+            options.rules = NormalizationRules::load(rules.as_deref())?;
+            if !options.rules.is_empty() {
+                let path = rules.unwrap_or_else(|| PathBuf::from(normalizer::rules::PROJECT_RULES_FILE));
+                println!("規則ファイル: {} ({}件)", path.display(), options.rules.len());
+            }
 
             // 正規化実行
             let result = normalizer::normalize_results(&results, &options);
@@ -401,18 +938,27 @@ async fn main() -> Result<()> {
             println!("\n📊 正規化結果:");
             println!("  総レコード数: {}", result.stats.total_records);
             println!("  修正対象: {}件", result.stats.corrected_records);
+            println!("  - 日付表記: {}件", result.stats.date_corrections);
+            println!("  - 備考（黒板優先）: {}件", result.stats.remarks_corrections);
+            println!("  - 測点（表記・補完）: {}件", result.stats.station_corrections);
+            println!("  - 写真区分: {}件", result.stats.category_corrections);
+            println!("  - 工種・種別・細別: {}件", result.stats.classification_corrections);
             println!("  - 計測値修正: {}件", result.stats.measurement_corrections);
+            if !options.rules.is_empty() {
+                println!("  （うち規則ファイルによる修正: {}件）", result.stats.rule_corrections);
+            }
 
             // 修正内容を表示
             if !result.corrections.is_empty() {
                 println!("\n📝 修正内容:");
                 for correction in &result.corrections {
                     println!(
-                        "  {} [{}]: {} → {}",
+                        "  {} [{}]: {} → {} ({})",
                         correction.file_name,
                         correction.field,
                         correction.original,
-                        correction.corrected
+                        correction.corrected,
+                        correction.reason_with_rule()
                     );
                 }
             }
@@ -0,0 +1,487 @@
+//! 過去の納品物から工種マスタを作る（`master learn`）
+//!
+//! PHOTO.XML（`export::photo_xml` の形式、電子納品の入れ子形式も可）と写真台帳Excelから
+//! 写真ごとの (写真種別, 工種, 種別, 細別, 備考) を集め、チェーンごとに写真タイトル・説明に
+//! よく出る語を検索パターンとして提案する。
+
+use crate::error::{PhotoAiError, Result};
+use calamine::{open_workbook_auto, Data, Reader};
+use photo_ai_common::master_diff::chain_key;
+use photo_ai_common::pattern::merge_patterns;
+use photo_ai_common::{HierarchyMaster, HierarchyRow};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// 検索パターンにしない語
+const STOP_WORDS: &[&str] = &["写真", "状況", "工事", "撮影"];
+
+/// 納品物から読み取った写真1枚分
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LearnedPhoto {
+    pub photo_type: String,
+    pub work_type: String,
+    pub variety: String,
+    pub subphase: String,
+    pub remarks: String,
+    pub title: String,
+    pub description: String,
+}
+
+/// 学習の設定
+#[derive(Debug, Clone)]
+pub struct LearnOptions {
+    /// 検索パターンにする語の最小出現写真数
+    pub min_count: usize,
+    /// 1チェーンあたりの検索パターン数の上限
+    pub max_patterns: usize,
+}
+
+impl Default for LearnOptions {
+    fn default() -> Self {
+        Self { min_count: 2, max_patterns: 5 }
+    }
+}
+
+/// 読み込み対象のファイルを集める（フォルダは再帰的に *.xml・*.xlsx を探す）
+pub fn collect_inputs(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut found: Vec<PathBuf> = walkdir::WalkDir::new(path)
+                .into_iter()
+                .flatten()
+                .map(|entry| entry.into_path())
+                .filter(|p| p.is_file() && input_kind(p).is_some())
+                .collect();
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InputKind {
+    PhotoXml,
+    Ledger,
+}
+
+fn input_kind(path: &Path) -> Option<InputKind> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "xml" => Some(InputKind::PhotoXml),
+        "xlsx" | "xlsm" | "xls" => Some(InputKind::Ledger),
+        _ => None,
+    }
+}
+
+/// 納品物1件を読み込む（形式は拡張子で判定）
+pub fn read_deliverable(path: &Path) -> Result<Vec<LearnedPhoto>> {
+    match input_kind(path) {
+        Some(InputKind::PhotoXml) => {
+            let bytes = std::fs::read(path)?;
+            read_photo_xml(&photo_ai_common::csv::decode_text(&bytes))
+        }
+        Some(InputKind::Ledger) => read_ledger(path),
+        None => Err(PhotoAiError::InvalidMaster(format!(
+            "未対応のファイル形式: {} (xml, xlsxのみ対応)",
+            path.display()
+        ))),
+    }
+}
+
+/// PHOTO.XML を読み込む
+///
+/// `<写真>` ごとに、配下の `<写真区分>`・`<工種>`・`<種別>`・`<細別>`・`<備考>`・`<写真タイトル>`・`<写真説明>` を読む。
+/// `<備考>` のないもの（`export::photo_xml` の出力など）は `<写真タイトル>` を備考とする
+pub fn read_photo_xml(content: &str) -> Result<Vec<LearnedPhoto>> {
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let doc = roxmltree::Document::parse_with_options(content, options)
+        .map_err(|e| PhotoAiError::InvalidMaster(format!("PHOTO.XMLの解析エラー: {}", e)))?;
+
+    let photos = doc
+        .descendants()
+        .filter(|node| node.has_tag_name("写真"))
+        .map(|photo| {
+            let tag_text = |tag: &str| {
+                photo
+                    .descendants()
+                    .find(|n| n.has_tag_name(tag))
+                    .map(|n| n.text().unwrap_or_default().trim().to_string())
+            };
+            let text = |tag: &str| tag_text(tag).unwrap_or_default();
+            LearnedPhoto {
+                photo_type: text("写真区分"),
+                work_type: text("工種"),
+                variety: text("種別"),
+                subphase: text("細別"),
+                remarks: tag_text("備考").unwrap_or_else(|| text("写真タイトル")),
+                title: text("写真タイトル"),
+                description: text("写真説明"),
+            }
+        })
+        .filter(|photo| !photo.work_type.is_empty())
+        .collect();
+    Ok(photos)
+}
+
+/// 台帳の項目名 → 読み取り先
+fn ledger_field(label: &str) -> Option<fn(&mut LearnedPhoto) -> &mut String> {
+    match label {
+        "区分" | "写真区分" | "写真種別" => Some(|p| &mut p.photo_type),
+        "工種" => Some(|p| &mut p.work_type),
+        "種別" => Some(|p| &mut p.variety),
+        "細別" | "作業段階" => Some(|p| &mut p.subphase),
+        "備考" => Some(|p| &mut p.remarks),
+        "写真タイトル" => Some(|p| &mut p.title),
+        "測定値" | "写真説明" => Some(|p| &mut p.description),
+        _ => None,
+    }
+}
+
+/// 写真台帳Excelを読み込む（全シート）
+pub fn read_ledger(path: &Path) -> Result<Vec<LearnedPhoto>> {
+    let mut workbook = open_workbook_auto(path)
+        .map_err(|e| PhotoAiError::InvalidMaster(format!("Excel読み込みエラー: {}", e)))?;
+
+    let mut photos = Vec::new();
+    for (_, range) in workbook.worksheets() {
+        let rows: Vec<Vec<String>> = range
+            .rows()
+            .map(|row| row.iter().map(cell_text).collect())
+            .collect();
+        photos.extend(ledger_photos(&rows));
+    }
+    Ok(photos)
+}
+
+fn cell_text(cell: &Data) -> String {
+    cell.to_string().trim().to_string()
+}
+
+/// シートの表から写真を取り出す
+///
+/// 1行に項目名が2つ以上ある行は一覧表のヘッダーとみなし、以降を1行1写真で読む。
+/// それ以外は「項目名 | 値」が縦に並ぶ台帳形式として、同じ項目が再び現れた時点で次の写真に移る
+fn ledger_photos(rows: &[Vec<String>]) -> Vec<LearnedPhoto> {
+    let mut photos = Vec::new();
+    let mut current = LearnedPhoto::default();
+    let mut filled: HashSet<&str> = HashSet::new();
+    let mut header: Option<Vec<(usize, &str)>> = None;
+
+    for row in rows {
+        if let Some(columns) = &header {
+            let mut photo = LearnedPhoto::default();
+            for (col, label) in columns {
+                if let (Some(field), Some(value)) = (ledger_field(label), row.get(*col)) {
+                    *field(&mut photo) = value.clone();
+                }
+            }
+            photos.push(photo);
+            continue;
+        }
+
+        let labels: Vec<(usize, &str)> = row
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| ledger_field(cell).is_some())
+            .map(|(col, cell)| (col, cell.as_str()))
+            .collect();
+        if labels.len() >= 2 {
+            header = Some(labels);
+            continue;
+        }
+
+        for (col, label) in labels {
+            let Some(value) = row[col + 1..].iter().find(|cell| !cell.is_empty()) else {
+                continue;
+            };
+            if !filled.insert(label) {
+                photos.push(std::mem::take(&mut current));
+                filled.clear();
+                filled.insert(label);
+            }
+            if let Some(field) = ledger_field(label) {
+                *field(&mut current) = if value == "-" { String::new() } else { value.clone() };
+            }
+        }
+    }
+    photos.push(current);
+    photos.retain(|photo| !photo.work_type.is_empty());
+    photos
+}
+
+/// 文から検索パターン候補の語を取り出す
+///
+/// 漢字・カタカナ・英字の連続を1語とし、ひらがな・数字・記号で区切る（「RI計器による密度測定」→ RI計器, 密度測定）。
+/// 漢字に挟まれた助詞以外のひらがな1文字は送り仮名として語に含める（敷均し温度）
+pub fn tokenize(text: &str) -> Vec<String> {
+    let is_kanji = |c: char| matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3005}');
+    let is_word_char = |c: char| {
+        is_kanji(c) || matches!(c, '\u{30A0}'..='\u{30FF}' | '\u{FF21}'..='\u{FF5A}') || c.is_ascii_alphabetic()
+    };
+    let is_okurigana = |c: char| matches!(c, '\u{3041}'..='\u{3096}') && !"のをにはがでとへもや".contains(c);
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for i in 0..=chars.len() {
+        let c = chars.get(i).copied().unwrap_or(' ');
+        let joins = is_okurigana(c)
+            && i > 0
+            && is_kanji(chars[i - 1])
+            && chars.get(i + 1).is_some_and(|&next| is_kanji(next));
+        if is_word_char(c) || joins {
+            current.push(c);
+            continue;
+        }
+        let word = std::mem::take(&mut current);
+        let short_ascii = word.is_ascii() && word.len() <= 2;
+        if word.chars().count() >= 2 && !short_ascii && !STOP_WORDS.contains(&word.as_str()) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// 写真をチェーンごとにまとめ、検索パターン付きの行にする（チェーンの初出順）
+pub fn learn(photos: &[LearnedPhoto], options: &LearnOptions) -> Vec<HierarchyRow> {
+    type Key = (String, String, String, String, String);
+    let mut order: Vec<Key> = Vec::new();
+    let mut counts: HashMap<Key, HashMap<String, usize>> = HashMap::new();
+    for photo in photos {
+        let key = (
+            photo.photo_type.clone(),
+            photo.work_type.clone(),
+            photo.variety.clone(),
+            photo.subphase.clone(),
+            photo.remarks.clone(),
+        );
+        let words: HashSet<String> = tokenize(&format!("{} {} {}", photo.remarks, photo.title, photo.description))
+            .into_iter()
+            .collect();
+        let entry = counts.entry(key.clone()).or_insert_with(|| {
+            order.push(key);
+            HashMap::new()
+        });
+        for word in words {
+            *entry.entry(word).or_default() += 1;
+        }
+    }
+
+    // 半数を超えるチェーンに出る語は区別に役立たないため除く
+    let mut spread: HashMap<&str, usize> = HashMap::new();
+    for words in counts.values() {
+        for word in words.keys() {
+            *spread.entry(word.as_str()).or_default() += 1;
+        }
+    }
+    let common_limit = if counts.len() >= 4 { counts.len() / 2 } else { usize::MAX };
+
+    order
+        .iter()
+        .map(|key| {
+            let mut words: Vec<(&String, &usize)> = counts[key]
+                .iter()
+                .filter(|(word, count)| **count >= options.min_count && spread[word.as_str()] <= common_limit)
+                .collect();
+            words.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            let patterns: Vec<&str> = words
+                .iter()
+                .take(options.max_patterns)
+                .map(|(word, _)| word.as_str())
+                .collect();
+
+            let (photo_type, work_type, variety, subphase, remarks) = key.clone();
+            HierarchyRow {
+                photo_division: "直接工事費".to_string(),
+                photo_type,
+                work_type,
+                variety,
+                subphase,
+                remarks,
+                search_patterns: patterns.join("|"),
+            }
+        })
+        .collect()
+}
+
+/// 既存マスタに学習結果を加える
+///
+/// 同じチェーン（工種, 種別, 細別, 備考）の行には足りない検索パターンだけを追記し、
+/// ない行は末尾に追加する
+pub fn extend_master(base: &HierarchyMaster, learned: &[HierarchyRow]) -> HierarchyMaster {
+    let mut rows = base.rows().to_vec();
+    for row in learned {
+        match rows.iter_mut().find(|r| chain_key(r) == chain_key(row)) {
+            Some(existing) => {
+                existing.search_patterns = merge_patterns(&existing.search_patterns, &row.search_patterns);
+            }
+            None => rows.push(row.clone()),
+        }
+    }
+    HierarchyMaster::from_rows(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn photo(work_type: &str, variety: &str, subphase: &str, title: &str) -> LearnedPhoto {
+        LearnedPhoto {
+            photo_type: "品質管理写真".to_string(),
+            work_type: work_type.to_string(),
+            variety: variety.to_string(),
+            subphase: subphase.to_string(),
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("RI計器による密度測定"), vec!["RI計器", "密度測定"]);
+        assert_eq!(tokenize("No.5 到着温度 160.4℃ 状況写真"), vec!["到着温度", "状況写真"]);
+        assert_eq!(tokenize("敷均し温度と取壊し状況"), vec!["敷均し温度", "取壊し状況"]);
+        assert!(tokenize("写真 No.1 m").is_empty());
+    }
+
+    #[test]
+    fn test_read_photo_xml() {
+        let result = |work_type: &str, variety: &str, subphase: &str, remarks: &str, description: &str| {
+            crate::analyzer::AnalysisResult {
+                work_type: work_type.to_string(),
+                variety: variety.to_string(),
+                subphase: subphase.to_string(),
+                remarks: remarks.to_string(),
+                description: description.to_string(),
+                ..Default::default()
+            }
+        };
+        let results = vec![
+            result("舗装工", "舗装打換え工", "表層工", "アスファルト混合物温度測定", "合材到着温度 162℃"),
+            result("区画線工", "区画線工", "溶融式区画線", "塗料温度測定", ""),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let xml = crate::export::photo_xml::generate_photo_xml(&results, &dir.path().join("PHOTO.XML")).unwrap();
+
+        // 自分の出力した PHOTO.XML は写真タイトルを備考として読む
+        let photos = read_deliverable(&xml).unwrap();
+        assert_eq!(photos.len(), 2);
+        assert_eq!(photos[0].subphase, "表層工");
+        assert_eq!(photos[0].remarks, "アスファルト混合物温度測定");
+        assert_eq!(photos[0].description, "合材到着温度 162℃");
+        assert_eq!(photos[1].work_type, "区画線工");
+        assert_eq!(photos[1].remarks, "塗料温度測定");
+
+        // 既存マスタの同じチェーンにまとまり、行は増えない
+        let base = HierarchyMaster::from_rows(vec![HierarchyRow {
+            photo_division: "直接工事費".to_string(),
+            photo_type: "品質管理写真".to_string(),
+            work_type: "舗装工".to_string(),
+            variety: "舗装打換え工".to_string(),
+            subphase: "表層工".to_string(),
+            remarks: "アスファルト混合物温度測定".to_string(),
+            search_patterns: String::new(),
+        }]);
+        let learned = learn(&photos[..1], &LearnOptions { min_count: 1, ..Default::default() });
+        assert_eq!(extend_master(&base, &learned).rows().len(), 1);
+
+        // 電子納品の入れ子形式は写真区分・備考を読む
+        let nested = r#"<写真情報><写真>
+            <写真ファイル情報><写真区分>品質管理写真</写真区分></写真ファイル情報>
+            <撮影工種区分><工種>区画線工</工種><種別>区画線工</種別><細別>溶融式区画線</細別><備考>塗料温度</備考></撮影工種区分>
+            <写真タイトル>塗料温度 210℃</写真タイトル>
+        </写真></写真情報>"#;
+        let photos = read_photo_xml(nested).unwrap();
+        assert_eq!(photos[0].photo_type, "品質管理写真");
+        assert_eq!(photos[0].remarks, "塗料温度");
+    }
+
+    #[test]
+    fn test_ledger_block_and_table_layouts() {
+        let cells = |row: &[&str]| row.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // 写真台帳（B列: 項目名, C列: 値）
+        let block = vec![
+            cells(&["", "区分", "品質管理写真"]),
+            cells(&["", "工種", "舗装工"]),
+            cells(&["", "細別", "表層工"]),
+            cells(&["", "備考", "到着温度"]),
+            cells(&["", "区分", "施工状況写真"]),
+            cells(&["", "工種", "区画線工"]),
+            cells(&["", "備考", "-"]),
+        ];
+        let photos = ledger_photos(&block);
+        assert_eq!(photos.len(), 2);
+        assert_eq!(photos[0].remarks, "到着温度");
+        assert_eq!(photos[1].work_type, "区画線工");
+        assert_eq!(photos[1].remarks, "");
+
+        // 一覧表
+        let table = vec![
+            cells(&["No", "工種", "種別", "細別", "写真タイトル"]),
+            cells(&["1", "舗装工", "舗装打換え工", "表層工", "到着温度"]),
+            cells(&["2", "", "", "", ""]),
+        ];
+        let photos = ledger_photos(&table);
+        assert_eq!(photos.len(), 1);
+        assert_eq!(photos[0].variety, "舗装打換え工");
+    }
+
+    #[test]
+    fn test_learn_and_extend() {
+        let photos = vec![
+            photo("舗装工", "舗装打換え工", "表層工", "到着温度 158℃"),
+            photo("舗装工", "舗装打換え工", "表層工", "到着温度 160℃ 敷均し温度"),
+            photo("舗装工", "舗装打換え工", "表層工", "敷均し温度"),
+            photo("舗装工", "舗装打換え工", "上層路盤工", "RI計器による密度測定"),
+        ];
+        let learned = learn(&photos, &LearnOptions::default());
+        assert_eq!(learned.len(), 2);
+        assert_eq!(learned[0].search_patterns, "到着温度|敷均し温度");
+        assert_eq!(learned[1].subphase, "上層路盤工");
+        assert_eq!(learned[1].search_patterns, "");
+
+        let base = HierarchyMaster::from_rows(vec![HierarchyRow {
+            photo_type: "品質管理写真".to_string(),
+            work_type: "舗装工".to_string(),
+            variety: "舗装打換え工".to_string(),
+            subphase: "表層工".to_string(),
+            search_patterns: "温度管理|到着温度".to_string(),
+            ..Default::default()
+        }]);
+        let extended = extend_master(&base, &learned);
+        assert_eq!(extended.rows().len(), 2);
+        assert_eq!(extended.rows()[0].search_patterns, "温度管理|到着温度|敷均し温度");
+    }
+
+    #[test]
+    fn test_extend_shipped_master_without_duplicates() {
+        let base_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("master/construction_hierarchy.csv");
+        let base = HierarchyMaster::from_path(&base_path).unwrap();
+
+        // 台帳の備考は出荷マスタの備考と同じ表記
+        let cells = |row: &[&str]| row.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let table = vec![
+            cells(&["写真区分", "工種", "種別", "細別", "備考", "写真説明"]),
+            cells(&["品質管理写真", "舗装工", "舗装打換え工", "表層工", "到着温度", "合材温度 162℃"]),
+            cells(&["品質管理写真", "舗装工", "舗装打換え工", "表層工", "到着温度", "合材温度 158℃"]),
+            cells(&["品質管理写真", "舗装工", "舗装打換え工", "表層工", "敷均し温度", "敷均し温度 150℃"]),
+        ];
+        let learned = learn(&ledger_photos(&table), &LearnOptions::default());
+        assert_eq!(learned.len(), 2);
+        assert_eq!(learned[0].remarks, "到着温度");
+
+        let extended = extend_master(&base, &learned);
+        assert_eq!(extended.rows().len(), base.rows().len());
+        let arrival = extended
+            .rows()
+            .iter()
+            .find(|r| r.subphase == "表層工" && r.remarks == "到着温度")
+            .unwrap();
+        assert_eq!(arrival.search_patterns, "到着温度|出荷温度|合材到着|合材温度");
+    }
+}
@@ -1,6 +1,8 @@
 //! マスタファイル対話式選択モジュール
 
-use std::path::PathBuf;
+use crate::master_tool::MasterStack;
+use photo_ai_common::{HierarchyMaster, MasterFormat};
+use std::path::{Path, PathBuf};
 use std::io::{self, Write};
 
 /// master/by_work_type/ から利用可能なマスタ一覧を取得
@@ -16,7 +18,7 @@ pub fn list_available_masters() -> Vec<(String, PathBuf)> {
     if let Ok(entries) = std::fs::read_dir(&master_dir) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.extension().map(|e| e == "csv").unwrap_or(false) {
+            if MasterFormat::from_path(&path).is_ok() {
                 if let Some(name) = path.file_stem() {
                     masters.push((name.to_string_lossy().to_string(), path));
                 }
@@ -31,7 +33,7 @@ pub fn list_available_masters() -> Vec<(String, PathBuf)> {
 
 /// マスタ選択結果
 pub struct MasterSelection {
-    pub path: PathBuf,
+    pub master: MasterStack,
     pub work_type: Option<String>,  // 工種名（全工種の場合はNone）
 }
 
@@ -44,7 +46,7 @@ pub fn select_master_interactive() -> Option<MasterSelection> {
         println!("  デフォルトマスタ (master/construction_hierarchy.csv) を使用します");
         let default = PathBuf::from("master/construction_hierarchy.csv");
         if default.exists() {
-            return Some(MasterSelection { path: default, work_type: None });
+            return Some(MasterSelection { master: default.into(), work_type: None });
         }
         return None;
     }
@@ -54,7 +56,7 @@ pub fn select_master_interactive() -> Option<MasterSelection> {
 
     for (i, (name, path)) in masters.iter().enumerate() {
         // 件数を取得
-        let count = count_csv_rows(path);
+        let count = count_master_rows(path);
         println!("  {}) {} ({}件)", i + 1, name, count);
     }
 
@@ -73,7 +75,7 @@ pub fn select_master_interactive() -> Option<MasterSelection> {
     if input.is_empty() {
         println!("→ 全工種マスタを使用");
         return Some(MasterSelection {
-            path: PathBuf::from("master/construction_hierarchy.csv"),
+            master: PathBuf::from("master/construction_hierarchy.csv").into(),
             work_type: None,
         });
     }
@@ -82,7 +84,7 @@ pub fn select_master_interactive() -> Option<MasterSelection> {
         Ok(0) => {
             println!("→ 全工種マスタを使用");
             Some(MasterSelection {
-                path: PathBuf::from("master/construction_hierarchy.csv"),
+                master: PathBuf::from("master/construction_hierarchy.csv").into(),
                 work_type: None,
             })
         }
@@ -90,23 +92,23 @@ pub fn select_master_interactive() -> Option<MasterSelection> {
             let (name, path) = &masters[n - 1];
             println!("→ {} を使用", name);
             Some(MasterSelection {
-                path: path.clone(),
+                master: path.clone().into(),
                 work_type: Some(name.clone()),
             })
         }
         _ => {
             println!("⚠ 無効な入力です。全工種マスタを使用します");
             Some(MasterSelection {
-                path: PathBuf::from("master/construction_hierarchy.csv"),
+                master: PathBuf::from("master/construction_hierarchy.csv").into(),
                 work_type: None,
             })
         }
     }
 }
 
-/// CSVの行数を取得（ヘッダー除く）
-fn count_csv_rows(path: &PathBuf) -> usize {
-    std::fs::read_to_string(path)
-        .map(|content| content.lines().count().saturating_sub(1))
+/// マスタの行数を取得（ヘッダー除く）
+fn count_master_rows(path: &Path) -> usize {
+    HierarchyMaster::load_path(path)
+        .map(|loaded| loaded.master.rows().len())
         .unwrap_or(0)
 }
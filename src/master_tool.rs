@@ -0,0 +1,309 @@
+//! 工種マスタの保守コマンド（`master diff` / `sync` / `tree` / `add` / `rename` / `remove` / `search`）
+//!
+//! 統合マスタ（`master/construction_hierarchy.csv`）と工種別マスタ（`master/by_work_type/*.csv`）を
+//! 行単位で比較し、どちらか一方から他方を作り直す。行はそのまま複写するため検索パターンも保持される。
+//! 編集結果は正規形のCSV（固定の7列、全フィールドを引用符で囲む、UTF-8・LF）で書き戻す。
+//! `--master` にはベースのマスタに続けて工事ごとのオーバーレイ（JSON）をカンマ区切りで指定できる（[`MasterStack`]）。
+
+use crate::error::{PhotoAiError, Result};
+use photo_ai_common::master_diff::{self, chain_label, MasterDiff};
+use photo_ai_common::master_overlay;
+use photo_ai_common::{HierarchyMaster, HierarchyRow, MasterFormat, MasterOverlay, PatternMatch, PatternText, TreeNode};
+use std::path::{Path, PathBuf};
+
+/// マスタを読み込む（不正な行があればエラー）
+pub fn load(path: &Path) -> Result<HierarchyMaster> {
+    HierarchyMaster::from_path(path).map_err(|e| PhotoAiError::MasterLoad(format!("{}: {}", path.display(), e)))
+}
+
+/// ベースのマスタと、その上に順に重ねるオーバーレイ
+///
+/// `base.csv,project.json` のようにカンマ区切りで指定する（先頭がベース）
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasterStack {
+    pub base: PathBuf,
+    pub overlays: Vec<PathBuf>,
+}
+
+impl MasterStack {
+    /// ベースのみ読み込む
+    pub fn load_base(&self) -> Result<HierarchyMaster> {
+        load(&self.base)
+    }
+
+    /// オーバーレイを重ねたマスタと、適用できなかった項目の警告を返す
+    pub fn resolve(&self) -> Result<(HierarchyMaster, Vec<String>)> {
+        let base = self.load_base()?;
+        let overlays = self
+            .overlays
+            .iter()
+            .map(|path| {
+                let overlay = MasterOverlay::from_path(path)
+                    .map_err(|e| PhotoAiError::MasterLoad(format!("{}: {}", path.display(), e)))?;
+                Ok((path.display().to_string(), overlay))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(master_overlay::resolve(&base, &overlays))
+    }
+
+    /// オーバーレイを重ねたマスタ（警告は表示のみ）
+    pub fn load(&self) -> Result<HierarchyMaster> {
+        let (master, warnings) = self.resolve()?;
+        for warning in &warnings {
+            eprintln!("  ⚠ {}", warning);
+        }
+        Ok(master)
+    }
+}
+
+impl From<PathBuf> for MasterStack {
+    fn from(base: PathBuf) -> Self {
+        Self { base, overlays: Vec::new() }
+    }
+}
+
+impl std::str::FromStr for MasterStack {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut paths = s.split(',').map(str::trim).filter(|p| !p.is_empty()).map(PathBuf::from);
+        let base = paths.next().ok_or_else(|| "マスタファイルを指定してください".to_string())?;
+        Ok(Self { base, overlays: paths.collect() })
+    }
+}
+
+impl std::fmt::Display for MasterStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.base.display())?;
+        for overlay in &self.overlays {
+            write!(f, " + {}", overlay.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// 差分を表示（+ 追加 / - 削除 / ~ 変更）
+pub fn print_diff(diff: &MasterDiff) {
+    for row in &diff.added {
+        println!("  + {} [{}] {}", chain_label(row), row.photo_type, row.search_patterns);
+    }
+    for row in &diff.removed {
+        println!("  - {} [{}] {}", chain_label(row), row.photo_type, row.search_patterns);
+    }
+    for changed in &diff.changed {
+        println!("  ~ {}", chain_label(&changed.after));
+        for field in &changed.fields {
+            let (before, after) = match *field {
+                "写真区分" => (&changed.before.photo_division, &changed.after.photo_division),
+                "写真種別" => (&changed.before.photo_type, &changed.after.photo_type),
+                _ => (&changed.before.search_patterns, &changed.after.search_patterns),
+            };
+            println!("      {}: \"{}\" → \"{}\"", field, before, after);
+        }
+    }
+}
+
+/// 階層ツリーを表示（`depth` 段まで）
+pub fn print_tree(nodes: &[TreeNode], depth: usize) {
+    fn walk(nodes: &[TreeNode], indent: usize, depth: usize) {
+        for node in nodes {
+            let name = if node.name.is_empty() { "（なし）" } else { node.name.as_str() };
+            println!("{}{} ({})", "  ".repeat(indent), name, node.rows);
+            if indent + 1 < depth {
+                walk(&node.children, indent + 1, depth);
+            }
+        }
+    }
+    walk(nodes, 0, depth);
+}
+
+/// `find_by_pattern` が一致する行を点数順に、一致した検索パターンとともに表示し、件数を返す
+pub fn print_search(master: &HierarchyMaster, text: &str) -> usize {
+    let text = PatternText::plain(text);
+    let mut found: Vec<(&HierarchyRow, PatternMatch)> = master
+        .rows_with_patterns()
+        .filter_map(|(row, patterns)| Some((row, patterns.find(&text)?)))
+        .collect();
+    // 照合と同じく重みの合計が高い順
+    found.sort_by_key(|(_, hit)| std::cmp::Reverse(hit.score));
+    for (row, hit) in &found {
+        println!("  {} [{}] {}点 ← {}", chain_label(row), row.photo_type, hit.score, hit.hits.join(", "));
+    }
+    found.len()
+}
+
+/// 編集結果の差分を表示し、`dry_run` でなければ書き戻す
+pub fn write_edit(path: &Path, before: &HierarchyMaster, after: &HierarchyMaster, dry_run: bool) -> Result<MasterDiff> {
+    let diff = master_diff::diff(before, after);
+    print_diff(&diff);
+    if !dry_run {
+        after
+            .write_path(path)
+            .map_err(|e| PhotoAiError::MasterLoad(format!("{}: {}", path.display(), e)))?;
+    }
+    Ok(diff)
+}
+
+/// 差分の件数表示
+pub fn summary(diff: &MasterDiff) -> String {
+    format!("追加{}件, 削除{}件, 変更{}件", diff.added.len(), diff.removed.len(), diff.changed.len())
+}
+
+/// 工種別マスタ（フォルダ内のCSV）を名前順に読み込む
+pub fn load_splits(dir: &Path) -> Result<Vec<(PathBuf, HierarchyMaster)>> {
+    if !dir.is_dir() {
+        return Err(PhotoAiError::FolderNotFound(dir.display().to_string()));
+    }
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| MasterFormat::from_path(path).ok() == Some(MasterFormat::Csv))
+        .collect();
+    paths.sort();
+    paths.into_iter().map(|path| Ok((path.clone(), load(&path)?))).collect()
+}
+
+/// 統合マスタから工種別マスタを作り直し、変更のあったファイル数を返す
+///
+/// 統合マスタにない工種のファイルは削除せず報告のみ
+pub fn sync_split(master_path: &Path, dir: &Path, dry_run: bool) -> Result<usize> {
+    let combined = load(master_path)?;
+    std::fs::create_dir_all(dir)?;
+    let existing = load_splits(dir)?;
+
+    let mut updated = 0;
+    let splits = master_diff::split_by_work_type(&combined);
+    for (work_type, split) in &splits {
+        let path = dir.join(format!("{}.csv", work_type));
+        let before = existing
+            .iter()
+            .find(|(p, _)| *p == path)
+            .map(|(_, master)| master.clone())
+            .unwrap_or_default();
+        let diff = master_diff::diff(&before, split);
+        let content = split.to_csv_string();
+        if std::fs::read_to_string(&path).ok().as_deref() == Some(content.as_str()) {
+            continue;
+        }
+
+        println!("{} ({})", path.display(), summary(&diff));
+        print_diff(&diff);
+        if !dry_run {
+            std::fs::write(&path, content)?;
+        }
+        updated += 1;
+    }
+
+    let skipped = combined.rows().iter().filter(|row| row.work_type.is_empty()).count();
+    if skipped > 0 {
+        println!("  ⚠ 工種が空の{}行は工種別マスタに含めません", skipped);
+    }
+    for (path, _) in &existing {
+        let known = splits.iter().any(|(work_type, _)| dir.join(format!("{}.csv", work_type)) == *path);
+        if !known {
+            println!("  ⚠ 統合マスタにない工種のファイル: {}", path.display());
+        }
+    }
+    Ok(updated)
+}
+
+/// 工種別マスタを統合マスタに書き戻し、差分を返す
+pub fn sync_merge(master_path: &Path, dir: &Path, dry_run: bool) -> Result<MasterDiff> {
+    let combined = if master_path.exists() { load(master_path)? } else { HierarchyMaster::default() };
+    let splits: Vec<HierarchyMaster> = load_splits(dir)?.into_iter().map(|(_, master)| master).collect();
+
+    let merged = master_diff::merge_splits(&combined, &splits);
+    let diff = master_diff::diff(&combined, &merged);
+    println!("{} ({})", master_path.display(), summary(&diff));
+    print_diff(&diff);
+
+    if !dry_run && std::fs::read(master_path).ok() != Some(merged.to_csv_string().into_bytes()) {
+        merged
+            .write_path(master_path)
+            .map_err(|e| PhotoAiError::MasterLoad(e.to_string()))?;
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMBINED: &str = "写真区分,写真種別,工種,種別,細別,備考,検索パターン
+\"直接工事費\",\"施工状況写真\",\"舗装工\",\"舗装打換え工\",\"表層工\",\"舗設状況\",\"\"
+\"直接工事費\",\"安全管理写真\",\"\",\"\",\"朝礼状況\",\"\",\"\"
+\"直接工事費\",\"品質管理写真\",\"区画線工\",\"区画線工\",\"溶融式区画線\",\"温度測定\",\"温度|溶解\"
+";
+
+    #[test]
+    fn test_split_then_merge_keeps_combined_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let master_path = dir.path().join("construction_hierarchy.csv");
+        let split_dir = dir.path().join("by_work_type");
+        std::fs::write(&master_path, COMBINED).unwrap();
+
+        assert_eq!(sync_split(&master_path, &split_dir, false).unwrap(), 2);
+        let marking = std::fs::read_to_string(split_dir.join("区画線工.csv")).unwrap();
+        assert!(marking.contains("\"温度|溶解\""));
+        // 変更がなければ書き直さない
+        assert_eq!(sync_split(&master_path, &split_dir, false).unwrap(), 0);
+
+        let diff = sync_merge(&master_path, &split_dir, false).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(std::fs::read_to_string(&master_path).unwrap(), COMBINED);
+    }
+
+    #[test]
+    fn test_write_edit_canonicalizes_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("master.csv");
+        std::fs::write(&path, "\u{feff}写真区分,写真種別,工種,種別,細別,備考,検索パターン\r\n直接工事費,施工状況写真,舗装工,舗装打換え工,表層工,舗設状況,\r\n").unwrap();
+
+        let before = load(&path).unwrap();
+        let node = photo_ai_common::NodePath::parse("施工状況写真/舗装工").unwrap();
+        let (after, _) = photo_ai_common::master_edit::rename(&before, &node, "舗装工事").unwrap();
+        write_edit(&path, &before, &after, true).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with('\u{feff}'));
+
+        write_edit(&path, &before, &after, false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "写真区分,写真種別,工種,種別,細別,備考,検索パターン\n\"直接工事費\",\"施工状況写真\",\"舗装工事\",\"舗装打換え工\",\"表層工\",\"舗設状況\",\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_master_stack_resolves_overlays() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.csv");
+        let overlay = dir.path().join("project.json");
+        std::fs::write(&base, COMBINED).unwrap();
+        std::fs::write(&overlay, r#"{"hide": ["*/区画線工"], "rename": [{"node": "*/道路土工", "to": "土工"}]}"#).unwrap();
+
+        let stack: MasterStack = format!("{}, {}", base.display(), overlay.display()).parse().unwrap();
+        assert_eq!(stack.overlays, vec![overlay]);
+        let (master, warnings) = stack.resolve().unwrap();
+        assert_eq!(master.rows().len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(stack.load_base().unwrap().rows().len(), 3);
+    }
+
+    #[test]
+    fn test_merge_picks_up_split_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let master_path = dir.path().join("construction_hierarchy.csv");
+        let split_dir = dir.path().join("by_work_type");
+        std::fs::write(&master_path, COMBINED).unwrap();
+        sync_split(&master_path, &split_dir, false).unwrap();
+
+        let paving = split_dir.join("舗装工.csv");
+        let edited = std::fs::read_to_string(&paving).unwrap().replace("\"舗設状況\",\"\"", "\"舗設状況\",\"舗設|敷均し\"");
+        std::fs::write(&paving, edited).unwrap();
+
+        let diff = sync_merge(&master_path, &split_dir, true).unwrap();
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].after.search_patterns, "舗設|敷均し");
+        // --dry-run では書き込まない
+        assert_eq!(std::fs::read_to_string(&master_path).unwrap(), COMBINED);
+    }
+}
@@ -1,199 +1,114 @@
 //! エイリアス変換モジュール
 //!
-//! 写真区分や工種の表記ゆれを正規化する。
+//! 写真区分や工種などの表記ゆれを正規化する。規則の書式と適用順は [`photo_ai_common::alias`] を参照。
+//!
+//! プリセットは 組み込み → `~/.config/photo-ai/aliases/*.json` → カレントディレクトリの
+//! `.photo-ai/aliases/*.json` の順に登録し、同じ名前は後のものが優先される。
 
 use crate::analyzer::AnalysisResult;
 use crate::error::Result;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
-
-/// エイリアス定義
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
-pub struct AliasConfig {
-    /// 写真区分のエイリアス
-    #[serde(default)]
-    pub photo_category: HashMap<String, String>,
-    /// 工種のエイリアス
-    #[serde(default)]
-    pub work_type: HashMap<String, String>,
-    /// 種別のエイリアス
-    #[serde(default)]
-    pub variety: HashMap<String, String>,
-    /// 作業段階のエイリアス
-    #[serde(default)]
-    pub subphase: HashMap<String, String>,
-}
-
-impl AliasConfig {
-    /// 組み込みプリセットを取得
-    pub fn from_preset(name: &str) -> Option<Self> {
-        match name.to_lowercase().as_str() {
-            "pavement" | "舗装" => Some(Self::pavement_preset()),
-            "marking" | "区画線" => Some(Self::marking_preset()),
-            "general" | "汎用" => Some(Self::general_preset()),
-            _ => None,
-        }
-    }
-
-    /// JSONファイルから読み込み
-    pub fn from_file(path: &Path) -> Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Self = serde_json::from_str(&content)?;
-        Ok(config)
-    }
-
-    /// 舗装工事用プリセット
-    fn pavement_preset() -> Self {
-        let mut config = Self::default();
-
-        // 写真区分
-        config.photo_category.insert("品質".into(), "品質管理写真".into());
-        config.photo_category.insert("品質管理".into(), "品質管理写真".into());
-        config.photo_category.insert("出来形".into(), "出来形管理写真".into());
-        config.photo_category.insert("出来形管理".into(), "出来形管理写真".into());
-        config.photo_category.insert("施工状況".into(), "施工状況写真".into());
-        config.photo_category.insert("施工中".into(), "施工状況写真".into());
-        config.photo_category.insert("安全".into(), "安全管理写真".into());
-        config.photo_category.insert("安全管理".into(), "安全管理写真".into());
-        config.photo_category.insert("材料".into(), "使用材料写真".into());
-        config.photo_category.insert("使用材料".into(), "使用材料写真".into());
-
-        // 工種
-        config.work_type.insert("舗装".into(), "舗装工".into());
-        config.work_type.insert("As".into(), "舗装工".into());
-        config.work_type.insert("アスファルト".into(), "舗装工".into());
-
-        // 種別
-        config.variety.insert("打換え".into(), "舗装打換え工".into());
-        config.variety.insert("打換".into(), "舗装打換え工".into());
-        config.variety.insert("オーバーレイ".into(), "舗装オーバーレイ工".into());
-
-        // 作業段階
-        config.subphase.insert("表層".into(), "表層工".into());
-        config.subphase.insert("基層".into(), "基層工".into());
-        config.subphase.insert("上層路盤".into(), "上層路盤工".into());
-        config.subphase.insert("下層路盤".into(), "下層路盤工".into());
-
-        config
-    }
-
-    /// 区画線工事用プリセット
-    fn marking_preset() -> Self {
-        let mut config = Self::default();
-
-        // 写真区分
-        config.photo_category.insert("品質".into(), "品質管理写真".into());
-        config.photo_category.insert("出来形".into(), "出来形管理写真".into());
-        config.photo_category.insert("施工状況".into(), "施工状況写真".into());
-
-        // 工種
-        config.work_type.insert("区画線".into(), "区画線工".into());
-        config.work_type.insert("ライン".into(), "区画線工".into());
-        config.work_type.insert("白線".into(), "区画線工".into());
-
-        // 種別
-        config.variety.insert("溶融式".into(), "溶融式区画線".into());
-        config.variety.insert("ペイント".into(), "ペイント式区画線".into());
-
-        config
-    }
-
-    /// 汎用プリセット
-    fn general_preset() -> Self {
-        let mut config = Self::default();
-
-        // 写真区分のみ
-        config.photo_category.insert("品質".into(), "品質管理写真".into());
-        config.photo_category.insert("出来形".into(), "出来形管理写真".into());
-        config.photo_category.insert("施工".into(), "施工状況写真".into());
-        config.photo_category.insert("安全".into(), "安全管理写真".into());
-        config.photo_category.insert("材料".into(), "使用材料写真".into());
-        config.photo_category.insert("着工".into(), "着工前写真".into());
-        config.photo_category.insert("完成".into(), "完成写真".into());
-
-        config
-    }
+use photo_ai_common::{AliasPreset, PresetSource};
+use std::path::{Path, PathBuf};
 
-    /// フィールドを変換（部分一致で最長マッチ）
-    fn transform_field(&self, value: &str, aliases: &HashMap<String, String>) -> String {
-        if value.is_empty() {
-            return value.to_string();
-        }
+pub use photo_ai_common::alias::{AliasConfig, AliasReport};
+pub use photo_ai_common::AliasRegistry;
 
-        // 完全一致を優先
-        if let Some(replacement) = aliases.get(value) {
-            return replacement.clone();
-        }
+/// 工事ごとのプリセットのフォルダ（カレントディレクトリからの相対パス）
+pub const PROJECT_PRESET_DIR: &str = ".photo-ai/aliases";
 
-        // 部分一致（最長マッチ）
-        let mut best_match: Option<(&str, &str)> = None;
-        for (pattern, replacement) in aliases {
-            if value.contains(pattern.as_str())
-                && (best_match.is_none() || pattern.len() > best_match.unwrap().0.len())
-            {
-                best_match = Some((pattern.as_str(), replacement.as_str()));
-            }
-        }
+/// 利用者のプリセットのフォルダ（`~/.config/photo-ai/aliases`）
+pub fn user_preset_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config").join("photo-ai").join("aliases"))
+}
 
-        if let Some((_, replacement)) = best_match {
-            replacement.to_string()
-        } else {
-            value.to_string()
+/// フォルダ内の `*.json` をファイル名順に登録し、読めなかったファイルの警告を返す
+pub fn register_dir(registry: &mut AliasRegistry, dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")))
+        .collect();
+    paths.sort();
+
+    let mut warnings = Vec::new();
+    for path in paths {
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let loaded = std::fs::read_to_string(&path)
+            .map_err(photo_ai_common::Error::from)
+            .and_then(|json| AliasPreset::from_json(&json, &stem, PresetSource::File(path.clone())));
+        match loaded {
+            Ok(preset) => registry.register(preset),
+            Err(e) => warnings.push(format!("{}: {}", path.display(), e)),
         }
     }
+    warnings
+}
 
-    /// 解析結果にエイリアス変換を適用
-    pub fn apply(&self, result: &AnalysisResult) -> AnalysisResult {
-        let mut updated = result.clone();
-
-        updated.photo_category = self.transform_field(&result.photo_category, &self.photo_category);
-        updated.work_type = self.transform_field(&result.work_type, &self.work_type);
-        updated.variety = self.transform_field(&result.variety, &self.variety);
-        updated.subphase = self.transform_field(&result.subphase, &self.subphase);
-
-        updated
+/// 組み込み・利用者・工事のプリセットを登録した一覧（読めなかったファイルは警告を表示）
+pub fn load_registry() -> AliasRegistry {
+    let mut registry = AliasRegistry::builtin();
+    let dirs = user_preset_dir().into_iter().chain(std::iter::once(PathBuf::from(PROJECT_PRESET_DIR)));
+    for dir in dirs {
+        for warning in register_dir(&mut registry, &dir) {
+            eprintln!("警告: エイリアスプリセット {}", warning);
+        }
     }
+    registry
 }
 
-/// 解析結果にエイリアスを適用
+/// 解析結果にエイリアスを適用し、適用した規則の報告を返す
+///
+/// `presets` は指定順に重ね（後のものが優先）、`alias_file` はさらにその上に重ねる。
+/// 不明なプリセットは警告して飛ばす
 pub fn apply_aliases(
     results: &[AnalysisResult],
-    preset: Option<&str>,
+    registry: &AliasRegistry,
+    presets: &[String],
     alias_file: Option<&Path>,
-) -> Result<Vec<AnalysisResult>> {
-    // エイリアス設定を構築
-    let mut config = AliasConfig::default();
-
-    // プリセットを適用
-    if let Some(preset_name) = preset {
-        if let Some(preset_config) = AliasConfig::from_preset(preset_name) {
-            // プリセットをマージ
-            config.photo_category.extend(preset_config.photo_category);
-            config.work_type.extend(preset_config.work_type);
-            config.variety.extend(preset_config.variety);
-            config.subphase.extend(preset_config.subphase);
-        } else {
-            eprintln!("警告: 不明なプリセット '{}' (pavement/marking/general)", preset_name);
-        }
+) -> Result<(Vec<AnalysisResult>, AliasReport)> {
+    // プリセットを重ねる
+    let (mut config, warnings) = registry.stack_known(presets);
+    for warning in warnings {
+        eprintln!("警告: {}", warning);
     }
 
-    // カスタムエイリアスファイルを適用（プリセットを上書き）
+    // カスタムエイリアスファイルを適用（プリセットより優先）
     if let Some(path) = alias_file {
-        let custom_config = AliasConfig::from_file(path)?;
-        config.photo_category.extend(custom_config.photo_category);
-        config.work_type.extend(custom_config.work_type);
-        config.variety.extend(custom_config.variety);
-        config.subphase.extend(custom_config.subphase);
+        let custom_config = AliasConfig::from_json(&std::fs::read_to_string(path)?)?;
+        config.merge(&custom_config);
     }
 
     // 変換を適用
-    let transformed: Vec<AnalysisResult> = results
-        .iter()
-        .map(|r| config.apply(r))
-        .collect();
+    Ok(config.compile().apply_all(results))
+}
 
-    Ok(transformed)
+/// プリセットの一覧を表示
+pub fn print_presets(registry: &AliasRegistry) {
+    for preset in registry.presets() {
+        let names = if preset.names.is_empty() { String::new() } else { format!("（{}）", preset.names.join("・")) };
+        println!("  {}{}  規則 {}件  [{}]", preset.name, names, preset.config.compile().len(), preset.source);
+        if !preset.description.is_empty() {
+            println!("      {}", preset.description);
+        }
+    }
+}
+
+/// 適用した規則と件数を表示
+pub fn print_report(report: &AliasReport) {
+    let fired = report.fired();
+    if fired.is_empty() {
+        println!("  適用された規則はありません（{}件中）", report.rules.len());
+        return;
+    }
+    for (rule, count) in fired {
+        println!("  {}: {}件", rule, count);
+    }
+    let unused = report.unused().len();
+    if unused > 0 {
+        println!("  未使用の規則: {}件", unused);
+    }
 }
 
 #[cfg(test)]
@@ -213,7 +128,7 @@ mod tests {
 
     #[test]
     fn test_transform_exact_match() {
-        let config = AliasConfig::pavement_preset();
+        let config = AliasConfig::from_preset("pavement").unwrap();
 
         let result = AnalysisResult {
             file_name: "test.jpg".to_string(),
@@ -229,7 +144,7 @@ mod tests {
 
     #[test]
     fn test_transform_partial_match() {
-        let config = AliasConfig::pavement_preset();
+        let config = AliasConfig::from_preset("pavement").unwrap();
 
         let result = AnalysisResult {
             file_name: "test.jpg".to_string(),
@@ -256,9 +171,50 @@ mod tests {
             },
         ];
 
-        let transformed = apply_aliases(&results, Some("pavement"), None).unwrap();
+        let (transformed, report) = apply_aliases(&results, &AliasRegistry::builtin(), &["pavement".to_string()], None).unwrap();
 
         assert_eq!(transformed[0].photo_category, "品質管理写真");
         assert_eq!(transformed[1].photo_category, "出来形管理写真");
+        assert_eq!(report.fired(), vec![("写真区分: 出来形 → 出来形管理写真", 1), ("写真区分: 品質 → 品質管理写真", 1)]);
+
+        // 不明なプリセットは飛ばして出力を続ける
+        let presets = vec!["nope".to_string(), "pavement".to_string()];
+        let (skipped, _) = apply_aliases(&results, &AliasRegistry::builtin(), &presets, None).unwrap();
+        assert_eq!(skipped[0].photo_category, "品質管理写真");
+    }
+
+    #[test]
+    fn test_alias_file_overrides_preset() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alias.json");
+        std::fs::write(
+            &path,
+            r#"{ "rules": [{ "name": "品質→品質写真", "field": "photo_category", "from": "re:^品質$", "to": "品質写真" }] }"#,
+        )
+        .unwrap();
+        let results = vec![AnalysisResult { photo_category: "品質".to_string(), ..Default::default() }];
+
+        let (transformed, report) = apply_aliases(&results, &AliasRegistry::builtin(), &["pavement".to_string()], Some(&path)).unwrap();
+        assert_eq!(transformed[0].photo_category, "品質写真");
+        assert_eq!(report.fired(), vec![("品質→品質写真", 1)]);
+    }
+
+    #[test]
+    fn test_register_dir_overrides_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("general.json"), r#"{ "names": ["汎用"], "photo_category": { "完成": "竣工写真" } }"#).unwrap();
+        std::fs::write(dir.path().join("broken.json"), "{").unwrap();
+        std::fs::write(dir.path().join("memo.txt"), "").unwrap();
+
+        let mut registry = AliasRegistry::builtin();
+        let warnings = register_dir(&mut registry, dir.path());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("broken.json"));
+
+        let results = vec![AnalysisResult { photo_category: "完成".to_string(), ..Default::default() }];
+        let presets = vec!["pavement".to_string(), "汎用".to_string()];
+        let (transformed, _) = apply_aliases(&results, &registry, &presets, None).unwrap();
+        assert_eq!(transformed[0].photo_category, "竣工写真");
+        assert!(matches!(registry.get("general").unwrap().source, PresetSource::File(_)));
     }
 }
@@ -0,0 +1,81 @@
+//! 日付表記の統一
+//!
+//! EXIF（`2025:01:18 10:00:00`）・フォルダ名（`20250118`, `2025年1月18日`）・AIの出力など
+//! 表記の混ざった日付を `YYYY-MM-DD`（時刻があれば `YYYY-MM-DD HH:MM:SS`）に揃える。
+
+use super::{CorrectionField, NormalizationCorrection};
+use crate::analyzer::AnalysisResult;
+use chrono::NaiveDate;
+use regex::Regex;
+
+/// 日付を `YYYY-MM-DD[ HH:MM:SS]` に揃える（日付として解釈できなければ None）
+pub fn normalize_date(s: &str) -> Option<String> {
+    lazy_static::lazy_static! {
+        static ref SEPARATED: Regex = Regex::new(
+            r"^(\d{4})[-_./:年](\d{1,2})[-_./:月](\d{1,2})日?(?:[ T_]+(\d{1,2})[:時](\d{2})(?:[:分](\d{2})?秒?)?)?$"
+        ).unwrap();
+        static ref COMPACT: Regex = Regex::new(
+            r"^(\d{4})(\d{2})(\d{2})(?:[ T_-]?(\d{2})(\d{2})(\d{2}))?$"
+        ).unwrap();
+    }
+
+    let s = s.trim();
+    let caps = SEPARATED.captures(s).or_else(|| COMPACT.captures(s))?;
+    let n = |i: usize| caps.get(i).map_or(Some(0), |m| m.as_str().parse::<u32>().ok());
+    let date = NaiveDate::from_ymd_opt(n(1)? as i32, n(2)?, n(3)?)?;
+    if caps.get(4).is_none() {
+        return Some(date.format("%Y-%m-%d").to_string());
+    }
+    let time = date.and_hms_opt(n(4)?, n(5)?, n(6)?)?;
+    Some(time.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// 表記の違う日付を修正する
+pub fn normalize_dates(results: &[AnalysisResult]) -> Vec<NormalizationCorrection> {
+    results
+        .iter()
+        .filter_map(|result| {
+            let corrected = normalize_date(&result.date).filter(|d| *d != result.date)?;
+            Some(NormalizationCorrection {
+                file_name: result.file_name.clone(),
+                file_path: result.file_path.clone(),
+                field: CorrectionField::Date,
+                original: result.date.clone(),
+                corrected,
+                reason: "日付表記を統一".to_string(),
+                rule: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_date() {
+        assert_eq!(normalize_date("2025:01:18 10:05:09").as_deref(), Some("2025-01-18 10:05:09"));
+        assert_eq!(normalize_date("2025/1/8").as_deref(), Some("2025-01-08"));
+        assert_eq!(normalize_date("2025年1月18日 9時05分").as_deref(), Some("2025-01-18 09:05:00"));
+        assert_eq!(normalize_date("20250118").as_deref(), Some("2025-01-18"));
+        assert_eq!(normalize_date("20250118_100509").as_deref(), Some("2025-01-18 10:05:09"));
+        assert_eq!(normalize_date("2025-01-18T10:05").as_deref(), Some("2025-01-18 10:05:00"));
+        assert_eq!(normalize_date("2025-13-01"), None);
+        assert_eq!(normalize_date("12月26日"), None);
+        assert_eq!(normalize_date(""), None);
+    }
+
+    #[test]
+    fn test_normalize_dates_skips_unchanged() {
+        let results = vec![
+            AnalysisResult { file_name: "a.jpg".to_string(), date: "2025-01-18 10:00:00".to_string(), ..Default::default() },
+            AnalysisResult { file_name: "b.jpg".to_string(), date: "2025:01:18 10:01:00".to_string(), ..Default::default() },
+            AnalysisResult { file_name: "c.jpg".to_string(), date: "不明".to_string(), ..Default::default() },
+        ];
+        let corrections = normalize_dates(&results);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].file_name, "b.jpg");
+        assert_eq!(corrections[0].corrected, "2025-01-18 10:01:00");
+    }
+}
@@ -1,14 +1,31 @@
 //! 後解析（正規化）モジュール
 //!
-//! 個別画像解析後に、グループ単位で計測値を統一する。
+//! 個別画像解析後に、写真セット・作業単位で項目を揃える。
 //!
-//! ## 処理フロー（予定）
-//! - 温度管理: 3枚単位（全景+ボードアップ+温度計アップ）で統一
-//! - 出来形管理: 同一測点のセット全体で統一
+//! ## 処理の順序（[`NormalizationPass`]、それぞれ [`NormalizationOptions`] で無効にできる）
+//! 1. 日付表記の統一（`YYYY-MM-DD[ HH:MM:SS]`）
+//! 2. 測点表記の統一（`NO.10+5.0m` → `No.10+5`、[`photo_ai_common::Station`]）
+//! 3. 黒板に書かれた備考候補を AI の備考より優先
+//! 4. 写真セット（連続する同じ備考）内で空欄の測点を補完
+//! 5. 写真セット内で写真区分を黒板アップ（なければ過半数）に統一
+//! 6. 同じ作業（連続する同じ細別・備考）の工種・種別を過半数に統一
+//! 7. 温度管理: 3枚単位（全景+ボードアップ+温度計アップ）で計測値を統一
+//! 8. 規則ファイル（`.photo-ai/normalize.json`）の現場ごとの規則（[`rules`]）
+//!
+//! 各段は前段の修正を反映した結果に対して行う。
 
+pub mod dates;
 pub mod measurements;
+pub mod remarks;
+pub mod rules;
+pub mod sets;
 
 use crate::analyzer::AnalysisResult;
+use photo_ai_common::AliasField;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+pub use rules::NormalizationRules;
 
 /// 正規化結果
 #[derive(Debug, Clone)]
@@ -24,6 +41,8 @@ pub struct NormalizationResult {
 pub struct NormalizationCorrection {
     /// ファイル名
     pub file_name: String,
+    /// ファイルパス（`--recursive` で別フォルダの同名写真と区別する）
+    pub file_path: String,
     /// 修正対象フィールド
     pub field: CorrectionField,
     /// 修正前の値
@@ -32,18 +51,83 @@ pub struct NormalizationCorrection {
     pub corrected: String,
     /// 修正理由
     pub reason: String,
+    /// 修正した規則のID（規則ファイルによる修正のみ）
+    pub rule: Option<String>,
+}
+
+impl NormalizationCorrection {
+    /// 修正理由（規則による修正は規則IDつき）
+    pub fn reason_with_rule(&self) -> String {
+        match &self.rule {
+            Some(rule) => format!("{}、規則: {}", self.reason, rule),
+            None => self.reason.clone(),
+        }
+    }
 }
 
 /// 修正対象フィールド
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CorrectionField {
     Measurements,
+    Station,
+    #[serde(alias = "photoCategory")]
+    PhotoCategory,
+    Date,
+    #[serde(alias = "workType")]
+    WorkType,
+    Variety,
+    #[serde(alias = "detail")]
+    Subphase,
+    Remarks,
+}
+
+impl CorrectionField {
+    /// 解析結果のこの項目の値
+    pub fn get(self, result: &AnalysisResult) -> &str {
+        match self {
+            CorrectionField::Measurements => &result.measurements,
+            CorrectionField::Station => &result.station,
+            CorrectionField::PhotoCategory => &result.photo_category,
+            CorrectionField::Date => &result.date,
+            CorrectionField::WorkType => &result.work_type,
+            CorrectionField::Variety => &result.variety,
+            CorrectionField::Subphase => &result.subphase,
+            CorrectionField::Remarks => &result.remarks,
+        }
+    }
+}
+
+impl TryFrom<AliasField> for CorrectionField {
+    type Error = AliasField;
+
+    /// 規則ファイルの項目名（エイリアスと共通）から。写真説明は直す対象にしない
+    fn try_from(field: AliasField) -> std::result::Result<Self, AliasField> {
+        Ok(match field {
+            AliasField::PhotoCategory => CorrectionField::PhotoCategory,
+            AliasField::WorkType => CorrectionField::WorkType,
+            AliasField::Variety => CorrectionField::Variety,
+            AliasField::Subphase => CorrectionField::Subphase,
+            AliasField::Remarks => CorrectionField::Remarks,
+            AliasField::Station => CorrectionField::Station,
+            AliasField::Date => CorrectionField::Date,
+            AliasField::Measurements => CorrectionField::Measurements,
+            AliasField::Description => return Err(field),
+        })
+    }
 }
 
 impl std::fmt::Display for CorrectionField {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CorrectionField::Measurements => write!(f, "計測値"),
+            CorrectionField::Station => write!(f, "測点"),
+            CorrectionField::PhotoCategory => write!(f, "写真区分"),
+            CorrectionField::Date => write!(f, "日付"),
+            CorrectionField::WorkType => write!(f, "工種"),
+            CorrectionField::Variety => write!(f, "種別"),
+            CorrectionField::Subphase => write!(f, "細別"),
+            CorrectionField::Remarks => write!(f, "備考"),
         }
     }
 }
@@ -57,6 +141,107 @@ pub struct NormalizationStats {
     pub corrected_records: usize,
     /// 計測値の修正数
     pub measurement_corrections: usize,
+    /// 測点の修正数（表記の統一・補完）
+    pub station_corrections: usize,
+    /// 写真区分の修正数
+    pub category_corrections: usize,
+    /// 日付表記の修正数
+    pub date_corrections: usize,
+    /// 工種・種別・細別の修正数
+    pub classification_corrections: usize,
+    /// 備考の修正数
+    pub remarks_corrections: usize,
+    /// 規則ファイルによる修正数（項目ごとの数にも含む）
+    pub rule_corrections: usize,
+}
+
+impl NormalizationStats {
+    fn count(&mut self, correction: &NormalizationCorrection) {
+        if correction.rule.is_some() {
+            self.rule_corrections += 1;
+        }
+        let counter = match correction.field {
+            CorrectionField::Measurements => &mut self.measurement_corrections,
+            CorrectionField::Station => &mut self.station_corrections,
+            CorrectionField::PhotoCategory => &mut self.category_corrections,
+            CorrectionField::Date => &mut self.date_corrections,
+            CorrectionField::WorkType | CorrectionField::Variety | CorrectionField::Subphase => {
+                &mut self.classification_corrections
+            }
+            CorrectionField::Remarks => &mut self.remarks_corrections,
+        };
+        *counter += 1;
+    }
+}
+
+/// 正規化の段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationPass {
+    /// 日付表記の統一
+    Dates,
+    /// 測点表記の統一
+    StationFormat,
+    /// 黒板の備考を優先
+    BoardRemarks,
+    /// 写真セット内の測点補完
+    Station,
+    /// 写真セット内の写真区分統一
+    Category,
+    /// 同じ作業の工種・種別統一
+    WorkType,
+    /// 計測値の検証とグループ統一
+    Measurements,
+    /// 規則ファイルの規則
+    Rules,
+}
+
+impl NormalizationPass {
+    pub const ALL: [NormalizationPass; 8] = [
+        NormalizationPass::Dates,
+        NormalizationPass::StationFormat,
+        NormalizationPass::BoardRemarks,
+        NormalizationPass::Station,
+        NormalizationPass::Category,
+        NormalizationPass::WorkType,
+        NormalizationPass::Measurements,
+        NormalizationPass::Rules,
+    ];
+}
+
+impl std::str::FromStr for NormalizationPass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "date" | "dates" => Ok(NormalizationPass::Dates),
+            "station-format" => Ok(NormalizationPass::StationFormat),
+            "remarks" | "board-remarks" => Ok(NormalizationPass::BoardRemarks),
+            "station" => Ok(NormalizationPass::Station),
+            "category" | "photo-category" => Ok(NormalizationPass::Category),
+            "work-type" | "variety" => Ok(NormalizationPass::WorkType),
+            "measurements" => Ok(NormalizationPass::Measurements),
+            "rules" => Ok(NormalizationPass::Rules),
+            _ => Err(format!(
+                "Unknown normalization pass: {}. Use dates, station-format, remarks, station, category, work-type, measurements, or rules",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for NormalizationPass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NormalizationPass::Dates => write!(f, "dates"),
+            NormalizationPass::StationFormat => write!(f, "station-format"),
+            NormalizationPass::BoardRemarks => write!(f, "remarks"),
+            NormalizationPass::Station => write!(f, "station"),
+            NormalizationPass::Category => write!(f, "category"),
+            NormalizationPass::WorkType => write!(f, "work-type"),
+            NormalizationPass::Measurements => write!(f, "measurements"),
+            NormalizationPass::Rules => write!(f, "rules"),
+        }
+    }
 }
 
 /// 正規化オプション
@@ -64,12 +249,74 @@ pub struct NormalizationStats {
 pub struct NormalizationOptions {
     /// 計測値グループ統一を有効にする
     pub unify_measurements: bool,
+    /// 測点表記を揃える
+    pub canonicalize_station: bool,
+    /// 写真セット内で空欄の測点を補う
+    pub propagate_station: bool,
+    /// 写真セット内で写真区分を揃える
+    pub unify_category: bool,
+    /// 日付表記を揃える
+    pub normalize_dates: bool,
+    /// 同じ作業の連続する写真で工種・種別を揃える
+    pub unify_work_type: bool,
+    /// 黒板に書かれた備考候補を優先する
+    pub prefer_board_remarks: bool,
+    /// 規則ファイルの規則を適用する
+    pub apply_rules: bool,
+    /// 現場ごとの規則（組み込みの段の後に適用）
+    pub rules: NormalizationRules,
 }
 
 impl Default for NormalizationOptions {
     fn default() -> Self {
         Self {
             unify_measurements: true,
+            canonicalize_station: true,
+            propagate_station: true,
+            unify_category: true,
+            normalize_dates: true,
+            unify_work_type: true,
+            prefer_board_remarks: true,
+            apply_rules: true,
+            rules: NormalizationRules::default(),
+        }
+    }
+}
+
+impl NormalizationOptions {
+    /// 指定した段を無効にしたオプション
+    pub fn without(passes: &[NormalizationPass]) -> Self {
+        let mut options = Self::default();
+        for pass in passes {
+            *options.flag(*pass) = false;
+        }
+        options
+    }
+
+    /// 段が有効か
+    pub fn enabled(&self, pass: NormalizationPass) -> bool {
+        match pass {
+            NormalizationPass::Dates => self.normalize_dates,
+            NormalizationPass::StationFormat => self.canonicalize_station,
+            NormalizationPass::BoardRemarks => self.prefer_board_remarks,
+            NormalizationPass::Station => self.propagate_station,
+            NormalizationPass::Category => self.unify_category,
+            NormalizationPass::WorkType => self.unify_work_type,
+            NormalizationPass::Measurements => self.unify_measurements,
+            NormalizationPass::Rules => self.apply_rules,
+        }
+    }
+
+    fn flag(&mut self, pass: NormalizationPass) -> &mut bool {
+        match pass {
+            NormalizationPass::Dates => &mut self.normalize_dates,
+            NormalizationPass::StationFormat => &mut self.canonicalize_station,
+            NormalizationPass::BoardRemarks => &mut self.prefer_board_remarks,
+            NormalizationPass::Station => &mut self.propagate_station,
+            NormalizationPass::Category => &mut self.unify_category,
+            NormalizationPass::WorkType => &mut self.unify_work_type,
+            NormalizationPass::Measurements => &mut self.unify_measurements,
+            NormalizationPass::Rules => &mut self.apply_rules,
         }
     }
 }
@@ -86,53 +333,82 @@ pub fn normalize_results(
     results: &[AnalysisResult],
     options: &NormalizationOptions,
 ) -> NormalizationResult {
+    // 後の段が前の段の修正を見られるよう、修正を反映しながら進める
+    let mut working = results.to_vec();
     let mut corrections = Vec::new();
+
+    for pass in NormalizationPass::ALL {
+        if !options.enabled(pass) {
+            continue;
+        }
+        let found = match pass {
+            NormalizationPass::Dates => dates::normalize_dates(&working),
+            NormalizationPass::StationFormat => sets::canonicalize_station(&working),
+            NormalizationPass::BoardRemarks => remarks::prefer_board_remarks(&working),
+            NormalizationPass::Station => sets::propagate_station(&working),
+            NormalizationPass::Category => sets::unify_category(&working),
+            NormalizationPass::WorkType => sets::unify_work_type_variety(&working),
+            NormalizationPass::Measurements => measurement_corrections(&working),
+            NormalizationPass::Rules => options.rules.corrections(&working),
+        };
+        apply_corrections(&mut working, &found);
+        corrections.extend(found);
+    }
+
     let mut stats = NormalizationStats {
         total_records: results.len(),
+        corrected_records: corrections
+            .iter()
+            .map(|c| (c.file_path.as_str(), c.file_name.as_str()))
+            .collect::<BTreeSet<_>>()
+            .len(),
         ..Default::default()
     };
+    for correction in &corrections {
+        stats.count(correction);
+    }
 
-    if options.unify_measurements {
-        // 温度値のバリデーションと修正
-        for result in results {
-            // 温度写真かどうか判定
-            let combined_text = format!("{} {} {}", result.remarks, result.description, result.detected_text);
-            if measurements::is_temperature_photo(&combined_text) {
-                // 温度種別を判定
-                let temp_type = measurements::TemperatureType::from_text(&combined_text);
-
-                // measurements フィールドの温度値を検証
-                if !result.measurements.is_empty() {
-                    if let Some(corrected) = measurements::validate_temperature(&result.measurements, temp_type.clone()) {
-                        corrections.push(NormalizationCorrection {
-                            file_name: result.file_name.clone(),
-                            field: CorrectionField::Measurements,
-                            original: result.measurements.clone(),
-                            corrected: corrected.clone(),
-                            reason: format!("温度値修正 ({:?}の妥当範囲外)", temp_type),
-                        });
-                        stats.measurement_corrections += 1;
-                        stats.corrected_records += 1;
-                    }
+    NormalizationResult { corrections, stats }
+}
+
+/// 温度値の検証と、3枚セット内での計測値の統一
+fn measurement_corrections(results: &[AnalysisResult]) -> Vec<NormalizationCorrection> {
+    let mut corrections = Vec::new();
+
+    // 温度値のバリデーションと修正
+    for result in results {
+        // 温度写真かどうか判定
+        let combined_text = format!("{} {} {}", result.remarks, result.description, result.detected_text);
+        if measurements::is_temperature_photo(&combined_text) {
+            // 温度種別を判定
+            let temp_type = measurements::TemperatureType::from_text(&combined_text);
+
+            // measurements フィールドの温度値を検証
+            if !result.measurements.is_empty() {
+                if let Some(corrected) = measurements::validate_temperature(&result.measurements, temp_type.clone()) {
+                    corrections.push(NormalizationCorrection {
+                        file_name: result.file_name.clone(),
+                        file_path: result.file_path.clone(),
+                        field: CorrectionField::Measurements,
+                        original: result.measurements.clone(),
+                        corrected: corrected.clone(),
+                        reason: format!("温度値修正 ({:?}の妥当範囲外)", temp_type),
+                        rule: None,
+                    });
                 }
             }
         }
     }
 
-    if options.unify_measurements {
-        // グループ単位での計測値統一
-        // 温度管理: 3枚セット（全景+黒板アップ+温度計アップ）で黒板アップの値に統一
-        let group_corrections = unify_measurements_by_group(results);
-        for correction in group_corrections {
-            if !corrections.iter().any(|c| c.file_name == correction.file_name) {
-                stats.measurement_corrections += 1;
-                stats.corrected_records += 1;
-                corrections.push(correction);
-            }
+    // グループ単位での計測値統一
+    // 温度管理: 3枚セット（全景+黒板アップ+温度計アップ）で黒板アップの値に統一
+    for correction in unify_measurements_by_group(results) {
+        if !corrections.iter().any(|c| c.file_path == correction.file_path && c.file_name == correction.file_name) {
+            corrections.push(correction);
         }
     }
 
-    NormalizationResult { corrections, stats }
+    corrections
 }
 
 /// 3枚セット内で黒板アップの計測値に統一する
@@ -198,6 +474,7 @@ fn unify_measurements_by_group(results: &[AnalysisResult]) -> Vec<NormalizationC
                 if target.measurements != *source_value && !target.measurements.is_empty() {
                     corrections.push(NormalizationCorrection {
                         file_name: target.file_name.clone(),
+                        file_path: target.file_path.clone(),
                         field: CorrectionField::Measurements,
                         original: target.measurements.clone(),
                         corrected: source_value.clone(),
@@ -205,6 +482,7 @@ fn unify_measurements_by_group(results: &[AnalysisResult]) -> Vec<NormalizationC
                             "黒板アップ({})の値に統一",
                             results[board_idx].file_name
                         ),
+                        rule: None,
                     });
                 }
             }
@@ -216,6 +494,8 @@ fn unify_measurements_by_group(results: &[AnalysisResult]) -> Vec<NormalizationC
 
 /// 修正を適用する
 ///
+/// 写真はパスで照合する（パスのない結果はファイル名で照合）
+///
 /// # Arguments
 /// * `results` - 解析結果（変更される）
 /// * `corrections` - 適用する修正リスト
@@ -224,9 +504,20 @@ pub fn apply_corrections(
     corrections: &[NormalizationCorrection],
 ) {
     for correction in corrections {
-        if let Some(result) = results.iter_mut().find(|r| r.file_name == correction.file_name) {
+        let target = results
+            .iter_mut()
+            .find(|r| r.file_path == correction.file_path && r.file_name == correction.file_name);
+        if let Some(result) = target {
             match correction.field {
                 CorrectionField::Measurements => result.measurements = correction.corrected.clone(),
+                CorrectionField::Station => result.station = correction.corrected.clone(),
+                CorrectionField::PhotoCategory => result.photo_category = correction.corrected.clone(),
+                CorrectionField::Date => result.date = correction.corrected.clone(),
+                CorrectionField::WorkType => result.work_type = correction.corrected.clone(),
+                CorrectionField::Variety => result.variety = correction.corrected.clone(),
+                CorrectionField::Subphase => result.subphase = correction.corrected.clone(),
+                // 元の備考は候補に戻す
+                CorrectionField::Remarks => photo_ai_common::switch_remarks(result, &correction.corrected, None),
             }
         }
     }
@@ -337,4 +628,71 @@ mod tests {
         assert_eq!(corrections[1].file_name, "IMG003.JPG");
         assert_eq!(corrections[1].corrected, "155.4℃");
     }
+
+    #[test]
+    fn test_passes_see_earlier_corrections() {
+        // 黒板の備考で全景と同じセットになり、測点が補われる
+        let results = vec![
+            AnalysisResult {
+                file_name: "1.jpg".to_string(),
+                date: "2025:01:18 10:00:00".to_string(),
+                remarks: "敷均し温度測定".to_string(),
+                focus_target: "全景".to_string(),
+                ..Default::default()
+            },
+            AnalysisResult {
+                file_name: "2.jpg".to_string(),
+                date: "2025-01-18 10:00:30".to_string(),
+                remarks: "到着温度測定".to_string(),
+                remarks_candidates: vec!["敷均し温度測定".to_string()],
+                has_board: true,
+                detected_text: "No.3 敷均し温度測定".to_string(),
+                station: "No.3".to_string(),
+                focus_target: "黒板アップ".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let result = normalize_results(&results, &NormalizationOptions::default());
+        let fields: Vec<(&str, &CorrectionField)> = result.corrections.iter().map(|c| (c.file_name.as_str(), &c.field)).collect();
+        assert_eq!(
+            fields,
+            vec![("1.jpg", &CorrectionField::Date), ("2.jpg", &CorrectionField::Remarks), ("1.jpg", &CorrectionField::Station)]
+        );
+        assert_eq!(result.stats.corrected_records, 2);
+        assert_eq!(result.stats.remarks_corrections, 1);
+
+        let mut applied = results.clone();
+        apply_corrections(&mut applied, &result.corrections);
+        assert_eq!(applied[1].remarks, "敷均し温度測定");
+        assert_eq!(applied[1].remarks_candidates, vec!["到着温度測定"]);
+
+        // 備考を直さなければ別のセットのまま
+        let options = NormalizationOptions::without(&[NormalizationPass::BoardRemarks]);
+        assert!(!options.enabled(NormalizationPass::BoardRemarks));
+        let result = normalize_results(&results, &options);
+        assert_eq!(result.corrections.len(), 1);
+        assert_eq!("work-type".parse::<NormalizationPass>(), Ok(NormalizationPass::WorkType));
+    }
+
+    #[test]
+    fn test_corrections_follow_the_photo_path() {
+        // 別フォルダの同名写真には適用しない
+        let photo = |file_path: &str, date: &str| AnalysisResult {
+            file_name: "IMG_1.jpg".to_string(),
+            file_path: file_path.to_string(),
+            date: date.to_string(),
+            ..Default::default()
+        };
+        let mut results = vec![photo("day1/IMG_1.jpg", "2025-01-18 10:00:00"), photo("day2/IMG_1.jpg", "2025:01:19 09:00:00")];
+
+        let result = normalize_results(&results, &NormalizationOptions::default());
+        assert_eq!(result.corrections.len(), 1);
+        assert_eq!(result.corrections[0].file_path, "day2/IMG_1.jpg");
+        assert_eq!(result.stats.corrected_records, 1);
+
+        apply_corrections(&mut results, &result.corrections);
+        assert_eq!(results[0].date, "2025-01-18 10:00:00");
+        assert_eq!(results[1].date, "2025-01-19 09:00:00");
+    }
 }
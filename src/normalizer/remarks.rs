@@ -0,0 +1,76 @@
+//! 黒板の文字を優先した備考の修正
+//!
+//! 黒板に書かれた備考は AI の推定より確か。黒板の文字（OCR）に備考候補の1つが書かれていて、
+//! 現在の備考が書かれていなければ、その候補に切り替える。
+
+use super::{CorrectionField, NormalizationCorrection};
+use crate::analyzer::AnalysisResult;
+
+/// 空白を除いた文字列（OCRは空白の入り方がまちまち）
+fn compact(s: &str) -> String {
+    s.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// 黒板の文字に書かれた備考候補（複数あれば最も長いもの）
+fn remarks_on_board(result: &AnalysisResult) -> Option<&str> {
+    if !result.has_board || result.detected_text.is_empty() {
+        return None;
+    }
+    let board = compact(&result.detected_text);
+    if !result.remarks.is_empty() && board.contains(&compact(&result.remarks)) {
+        return None;
+    }
+    result
+        .remarks_candidates
+        .iter()
+        .filter(|candidate| !candidate.is_empty() && board.contains(&compact(candidate)))
+        .max_by_key(|candidate| candidate.chars().count())
+        .map(String::as_str)
+}
+
+/// 黒板に書かれた備考候補を AI の備考より優先する
+pub fn prefer_board_remarks(results: &[AnalysisResult]) -> Vec<NormalizationCorrection> {
+    results
+        .iter()
+        .filter_map(|result| {
+            let remarks = remarks_on_board(result)?;
+            Some(NormalizationCorrection {
+                file_name: result.file_name.clone(),
+                file_path: result.file_path.clone(),
+                field: CorrectionField::Remarks,
+                original: result.remarks.clone(),
+                corrected: remarks.to_string(),
+                reason: "黒板の記載を優先".to_string(),
+                rule: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefer_board_remarks() {
+        let result = |file_name: &str, has_board: bool, detected_text: &str| AnalysisResult {
+            file_name: file_name.to_string(),
+            has_board,
+            detected_text: detected_text.to_string(),
+            remarks: "到着温度測定".to_string(),
+            remarks_candidates: vec!["温度測定".to_string(), "敷均し温度測定".to_string()],
+            ..Default::default()
+        };
+        let results = vec![
+            result("1.jpg", true, "工種 舗装工 敷均し 温度測定 148℃"),
+            result("2.jpg", true, "到着温度測定 162℃"),
+            result("3.jpg", false, "敷均し温度測定"),
+        ];
+
+        let corrections = prefer_board_remarks(&results);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].file_name, "1.jpg");
+        assert_eq!(corrections[0].original, "到着温度測定");
+        assert_eq!(corrections[0].corrected, "敷均し温度測定");
+    }
+}
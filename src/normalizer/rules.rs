@@ -0,0 +1,328 @@
+//! 規則ファイルによる現場ごとの正規化
+//!
+//! 組み込みの段にない現場ごとの直し方を、リリースを待たずに JSON で書けるようにする。
+//!
+//! ```json
+//! { "rules": [
+//!     { "id": "temp-category", "when": { "remarks": "re:温度測定$" },
+//!       "field": "photo_category", "action": "set", "value": "品質管理写真", "reason": "温度管理は品質管理写真" },
+//!     { "id": "set-subphase", "field": "subphase", "action": "copy_from_leader", "group": "set" },
+//!     { "id": "overview-measurements", "when": { "photo_category": "施工状況写真" },
+//!       "field": "measurements", "action": "clear" },
+//!     { "id": "station-prefix", "field": "station", "action": "replace", "from": "re:^測点\\s*", "to": "No." }
+//! ] }
+//! ```
+//!
+//! - `when` はエイリアス・qc と共通の条件（[`Conditions`]。完全一致、`re:` で正規表現）。すべて満たす写真を直す
+//! - `action`: `set`（値を設定）/ `copy_from_leader`（写真セット・作業の代表写真の値を写す）/
+//!   `clear`（空にする）/ `replace`（`from` を `to` に置換。`re:` なら正規表現で `$1` を使える）
+//! - 代表写真は黒板アップ、なければ先頭の、その項目が空でない写真
+//! - 規則は書いた順に、前の規則の修正を反映した結果に対して適用する
+
+use super::{apply_corrections, sets, CorrectionField, NormalizationCorrection};
+use crate::analyzer::AnalysisResult;
+use crate::config;
+use crate::error::{PhotoAiError, Result};
+use photo_ai_common::{AliasField, AliasMatcher, Conditions};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// 工事ごとの規則ファイルの置き場所（`--rules` 省略時、あれば読む）
+pub const PROJECT_RULES_FILE: &str = ".photo-ai/normalize.json";
+
+/// 代表写真を決めるまとまり
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleGroup {
+    /// 写真セット（連続する同じ備考）
+    #[default]
+    Set,
+    /// 作業（連続する同じ細別・備考）
+    Activity,
+}
+
+/// 規則の動作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RuleAction {
+    /// 値を設定
+    Set { value: String },
+    /// まとまりの代表写真の値を写す
+    CopyFromLeader {
+        #[serde(default)]
+        group: RuleGroup,
+    },
+    /// 空にする
+    Clear,
+    /// 置換（`re:` で始まれば正規表現）
+    Replace { from: String, to: String },
+}
+
+/// 規則1件（ファイルの記述）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleDefinition {
+    /// 規則ID（`--dry-run` の表示に使う）
+    pub id: String,
+    /// 適用する写真の条件（項目 → 完全一致 or `re:正規表現`）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub when: BTreeMap<AliasField, String>,
+    /// 直す項目（`when` と同じ項目名。写真説明は直せない）
+    pub field: AliasField,
+    #[serde(flatten)]
+    pub action: RuleAction,
+    /// 修正理由（省略時は動作から作る）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<RuleDefinition>,
+}
+
+/// 読み込み済みの規則（正規表現は解釈済み）
+#[derive(Debug, Clone, Default)]
+pub struct NormalizationRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl NormalizationRules {
+    /// JSONから読み込み（IDの重複・不正な正規表現はエラー）
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: RulesFile = serde_json::from_str(json.trim_start_matches('\u{feff}'))?;
+        let mut ids = HashSet::new();
+        let rules = file
+            .rules
+            .into_iter()
+            .map(|definition| {
+                if definition.id.trim().is_empty() {
+                    return Err(PhotoAiError::Config("規則に id がありません".to_string()));
+                }
+                if !ids.insert(definition.id.clone()) {
+                    return Err(PhotoAiError::Config(format!("規則「{}」: id が重複しています", definition.id)));
+                }
+                CompiledRule::new(definition)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// ファイルから読み込み
+    pub fn from_file(path: &Path) -> Result<Self> {
+        config::load_project_file(path, Self::from_json)
+    }
+
+    /// 指定のファイル、指定がなければ工事の規則ファイル（なければ規則なし）を読む
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::from_file(path),
+            None if Path::new(PROJECT_RULES_FILE).is_file() => Self::from_file(Path::new(PROJECT_RULES_FILE)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 規則を順に適用した修正（各規則は前の規則の修正を反映した結果に対して適用）
+    pub fn corrections(&self, results: &[AnalysisResult]) -> Vec<NormalizationCorrection> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+        let mut working = results.to_vec();
+        let mut corrections = Vec::new();
+        for rule in &self.rules {
+            let found = rule.corrections(&working);
+            apply_corrections(&mut working, &found);
+            corrections.extend(found);
+        }
+        corrections
+    }
+}
+
+/// 条件・置換元を解釈済みの規則
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    definition: RuleDefinition,
+    field: CorrectionField,
+    when: Conditions,
+    /// `replace` の置換元
+    from: Option<AliasMatcher>,
+}
+
+impl CompiledRule {
+    fn new(definition: RuleDefinition) -> Result<Self> {
+        let label = format!("規則「{}」", definition.id);
+        let field = CorrectionField::try_from(definition.field)
+            .map_err(|field| PhotoAiError::Config(format!("{}: {}は直せません", label, field)))?;
+        let when = Conditions::parse(&definition.when, &label)?;
+        let from = match &definition.action {
+            RuleAction::Replace { from, .. } if from.is_empty() => {
+                return Err(PhotoAiError::Config(format!("{}: replace の from が空です", label)));
+            }
+            RuleAction::Replace { from, .. } => Some(AliasMatcher::parse(from, &label)?),
+            _ => None,
+        };
+        Ok(Self { definition, field, when, from })
+    }
+
+    /// 写真ごとの修正後の値（変わらなければ None）
+    fn new_value(&self, result: &AnalysisResult, leaders: &[Option<&str>], index: usize) -> Option<String> {
+        let current = self.field.get(result);
+        let value = match (&self.definition.action, &self.from) {
+            (RuleAction::Set { value }, _) => value.clone(),
+            (RuleAction::CopyFromLeader { .. }, _) => leaders[index]?.to_string(),
+            (RuleAction::Clear, _) => String::new(),
+            (RuleAction::Replace { to, .. }, Some(AliasMatcher::Regex(re))) => re.replace_all(current, to.as_str()).into_owned(),
+            (RuleAction::Replace { to, .. }, Some(AliasMatcher::Text(from))) => current.replace(from.as_str(), to),
+            (RuleAction::Replace { .. }, None) => return None,
+        };
+        (value != current).then_some(value)
+    }
+
+    /// 写真ごとの代表写真の値（まとまりに入らない写真は None）
+    fn leaders<'a>(&self, results: &'a [AnalysisResult]) -> Vec<Option<&'a str>> {
+        let mut leaders = vec![None; results.len()];
+        let RuleAction::CopyFromLeader { group } = self.definition.action else {
+            return leaders;
+        };
+        let groups = match group {
+            RuleGroup::Set => sets::photo_sets(results),
+            RuleGroup::Activity => sets::activities(results),
+        };
+        let field = self.field;
+        for members in groups {
+            let filled = || members.iter().filter(|&&i| !field.get(&results[i]).is_empty());
+            let Some(leader) = filled().find(|&&i| sets::is_board_up(&results[i])).or_else(|| filled().next()) else {
+                continue;
+            };
+            for &i in &members {
+                leaders[i] = Some(field.get(&results[*leader]));
+            }
+        }
+        leaders
+    }
+
+    fn reason(&self) -> String {
+        if !self.definition.reason.is_empty() {
+            return self.definition.reason.clone();
+        }
+        match &self.definition.action {
+            RuleAction::Set { value } => format!("「{}」に設定", value),
+            RuleAction::CopyFromLeader { group: RuleGroup::Set } => "同じセットの代表写真の値に統一".to_string(),
+            RuleAction::CopyFromLeader { group: RuleGroup::Activity } => "同じ作業の代表写真の値に統一".to_string(),
+            RuleAction::Clear => "空欄にする".to_string(),
+            RuleAction::Replace { from, to } => format!("「{}」を「{}」に置換", from, to),
+        }
+    }
+
+    fn corrections(&self, results: &[AnalysisResult]) -> Vec<NormalizationCorrection> {
+        let leaders = self.leaders(results);
+        let reason = self.reason();
+        results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| self.when.matches(result))
+            .filter_map(|(i, result)| {
+                let corrected = self.new_value(result, &leaders, i)?;
+                Some(NormalizationCorrection {
+                    file_name: result.file_name.clone(),
+                    file_path: result.file_path.clone(),
+                    field: self.field,
+                    original: self.field.get(result).to_string(),
+                    corrected,
+                    reason: reason.clone(),
+                    rule: Some(self.definition.id.clone()),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn photo(file_name: &str, remarks: &str, focus_target: &str, subphase: &str, station: &str) -> AnalysisResult {
+        AnalysisResult {
+            file_name: file_name.to_string(),
+            remarks: remarks.to_string(),
+            focus_target: focus_target.to_string(),
+            subphase: subphase.to_string(),
+            station: station.to_string(),
+            photo_category: "施工状況写真".to_string(),
+            measurements: "160℃".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rules_actions_in_order() {
+        let rules = NormalizationRules::from_json(
+            r#"{ "rules": [
+                { "id": "temp-category", "when": { "remarks": "re:温度測定$" },
+                  "field": "photo_category", "action": "set", "value": "品質管理写真" },
+                { "id": "overview-measurements", "when": { "photo_category": "施工状況写真" },
+                  "field": "measurements", "action": "clear", "reason": "状況写真に計測値は書かない" },
+                { "id": "set-subphase", "field": "subphase", "action": "copy_from_leader" },
+                { "id": "station-prefix", "field": "station", "action": "replace", "from": "re:^測点\\s*(\\d+)$", "to": "No.$1" }
+            ] }"#,
+        )
+        .unwrap();
+        assert_eq!(rules.len(), 4);
+
+        let results = vec![
+            photo("1.jpg", "到着温度測定", "全景", "表層工", ""),
+            photo("2.jpg", "到着温度測定", "黒板アップ", "基層工", "No.3"),
+            photo("3.jpg", "舗設状況", "全景", "", "測点 4"),
+        ];
+        let corrections = rules.corrections(&results);
+        let changes: Vec<(&str, &str, CorrectionField, &str)> = corrections
+            .iter()
+            .map(|c| (c.rule.as_deref().unwrap(), c.file_name.as_str(), c.field, c.corrected.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("temp-category", "1.jpg", CorrectionField::PhotoCategory, "品質管理写真"),
+                ("temp-category", "2.jpg", CorrectionField::PhotoCategory, "品質管理写真"),
+                // 前の規則で写真区分が変わった写真は対象外
+                ("overview-measurements", "3.jpg", CorrectionField::Measurements, ""),
+                ("set-subphase", "1.jpg", CorrectionField::Subphase, "基層工"),
+                ("station-prefix", "3.jpg", CorrectionField::Station, "No.4"),
+            ]
+        );
+        assert_eq!(corrections[0].reason, "「品質管理写真」に設定");
+        assert_eq!(corrections[2].reason_with_rule(), "状況写真に計測値は書かない、規則: overview-measurements");
+    }
+
+    #[test]
+    fn test_rules_file_errors() {
+        let duplicate = r#"{ "rules": [
+            { "id": "a", "field": "remarks", "action": "clear" },
+            { "id": "a", "field": "station", "action": "clear" }
+        ] }"#;
+        assert!(NormalizationRules::from_json(duplicate).unwrap_err().to_string().contains("重複"));
+
+        let bad_regex = r#"{ "rules": [{ "id": "b", "field": "station", "action": "replace", "from": "re:(", "to": "" }] }"#;
+        assert!(NormalizationRules::from_json(bad_regex).unwrap_err().to_string().contains("規則「b」"));
+
+        let unknown_action = r#"{ "rules": [{ "id": "c", "field": "station", "action": "drop" }] }"#;
+        assert!(NormalizationRules::from_json(unknown_action).is_err());
+
+        // 項目名は when と共通。写真説明は条件にだけ使える
+        let description = r#"{ "rules": [{ "id": "d", "when": { "description": "re:温度" }, "field": "description", "action": "clear" }] }"#;
+        assert!(NormalizationRules::from_json(description).unwrap_err().to_string().contains("規則「d」: 写真説明は直せません"));
+        let condition_only = r#"{ "rules": [{ "id": "e", "when": { "description": "re:温度" }, "field": "measurements", "action": "clear" }] }"#;
+        assert_eq!(NormalizationRules::from_json(condition_only).unwrap().len(), 1);
+
+        assert!(NormalizationRules::from_json(r#"{ "rules": [] }"#).unwrap().is_empty());
+    }
+}
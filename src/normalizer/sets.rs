@@ -0,0 +1,252 @@
+//! 写真セット・作業単位での項目の統一
+//!
+//! - 写真セット: 連続する同じ備考の写真（全景 + 黒板アップ + 計測器アップ など）。
+//!   測点が入っている写真どうしで測点が食い違えば別のセットとする
+//! - 作業: 連続する同じ細別・備考の写真（測点をまたいでよい）
+
+use super::{CorrectionField, NormalizationCorrection};
+use crate::analyzer::AnalysisResult;
+use photo_ai_common::canonical_station;
+use std::collections::BTreeMap;
+
+/// 黒板アップの写真か
+pub(super) fn is_board_up(result: &AnalysisResult) -> bool {
+    result.focus_target == "黒板アップ"
+}
+
+/// 連続する写真を `same` が真の間まとめる（1枚だけのまとまりは除く）
+fn runs(results: &[AnalysisResult], same: impl Fn(&[usize], &AnalysisResult) -> bool) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    for (i, result) in results.iter().enumerate() {
+        if !current.is_empty() && !same(&current, result) {
+            groups.push(std::mem::take(&mut current));
+        }
+        current.push(i);
+    }
+    groups.push(current);
+    groups.retain(|g| g.len() > 1);
+    groups
+}
+
+/// 写真セット（連続する同じ備考、測点の食い違いで区切る）
+pub fn photo_sets(results: &[AnalysisResult]) -> Vec<Vec<usize>> {
+    runs(results, |group, result| {
+        let first = &results[group[0]];
+        let station = group.iter().map(|&i| results[i].station.as_str()).find(|s| !s.is_empty());
+        !result.remarks.is_empty()
+            && result.remarks == first.remarks
+            && (result.station.is_empty() || station.is_none_or(|s| s == result.station))
+    })
+}
+
+/// 作業（連続する同じ細別・備考）
+pub fn activities(results: &[AnalysisResult]) -> Vec<Vec<usize>> {
+    runs(results, |group, result| {
+        let first = &results[group[0]];
+        !result.subphase.is_empty() && result.subphase == first.subphase && result.remarks == first.remarks
+    })
+}
+
+/// 過半数の値（空欄は数えない）
+fn majority<'a>(values: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut total = 0;
+    for value in values.filter(|v| !v.is_empty()) {
+        *counts.entry(value).or_default() += 1;
+        total += 1;
+    }
+    counts.into_iter().find(|(_, n)| n * 2 > total).map(|(value, _)| value)
+}
+
+/// 測点を `No.10+5`・`No.5〜No.8` の表記に揃える（解釈できない値はそのまま）
+pub fn canonicalize_station(results: &[AnalysisResult]) -> Vec<NormalizationCorrection> {
+    results
+        .iter()
+        .filter_map(|result| {
+            let corrected = canonical_station(&result.station).filter(|s| *s != result.station)?;
+            Some(NormalizationCorrection {
+                file_name: result.file_name.clone(),
+                file_path: result.file_path.clone(),
+                field: CorrectionField::Station,
+                original: result.station.clone(),
+                corrected,
+                reason: "測点表記を統一".to_string(),
+                rule: None,
+            })
+        })
+        .collect()
+}
+
+/// 写真セット内の空欄の測点をセットの測点で補う
+pub fn propagate_station(results: &[AnalysisResult]) -> Vec<NormalizationCorrection> {
+    let mut corrections = Vec::new();
+    for set in photo_sets(results) {
+        // セット内の測点は食い違わないので最初に見つかったもの
+        let Some(&source) = set.iter().find(|&&i| !results[i].station.is_empty()) else {
+            continue;
+        };
+        for &i in set.iter().filter(|&&i| results[i].station.is_empty()) {
+            corrections.push(NormalizationCorrection {
+                file_name: results[i].file_name.clone(),
+                file_path: results[i].file_path.clone(),
+                field: CorrectionField::Station,
+                original: String::new(),
+                corrected: results[source].station.clone(),
+                reason: format!("同じセット({})の測点を補完", results[source].file_name),
+                rule: None,
+            });
+        }
+    }
+    corrections
+}
+
+/// 写真セット内の写真区分を黒板アップ（なければ過半数）に揃える
+pub fn unify_category(results: &[AnalysisResult]) -> Vec<NormalizationCorrection> {
+    let mut corrections = Vec::new();
+    for set in photo_sets(results) {
+        let board = set.iter().find(|&&i| is_board_up(&results[i]) && !results[i].photo_category.is_empty());
+        let (category, reason) = match board {
+            Some(&i) => (results[i].photo_category.as_str(), format!("黒板アップ({})の写真区分に統一", results[i].file_name)),
+            None => match majority(set.iter().map(|&i| results[i].photo_category.as_str())) {
+                Some(category) => (category, "同じセットの多数の写真区分に統一".to_string()),
+                None => continue,
+            },
+        };
+        for &i in &set {
+            if results[i].photo_category != category {
+                corrections.push(NormalizationCorrection {
+                    file_name: results[i].file_name.clone(),
+                    file_path: results[i].file_path.clone(),
+                    field: CorrectionField::PhotoCategory,
+                    original: results[i].photo_category.clone(),
+                    corrected: category.to_string(),
+                    reason: reason.clone(),
+                    rule: None,
+                });
+            }
+        }
+    }
+    corrections
+}
+
+type Getter = fn(&AnalysisResult) -> &str;
+
+fn work_type(result: &AnalysisResult) -> &str {
+    &result.work_type
+}
+
+fn variety(result: &AnalysisResult) -> &str {
+    &result.variety
+}
+
+/// 同じ作業の連続する写真で、工種・種別を過半数の値に揃える
+pub fn unify_work_type_variety(results: &[AnalysisResult]) -> Vec<NormalizationCorrection> {
+    let mut corrections = Vec::new();
+    for activity in activities(results) {
+        for (field, get) in [(CorrectionField::WorkType, work_type as Getter), (CorrectionField::Variety, variety)] {
+            let Some(value) = majority(activity.iter().map(|&i| get(&results[i]))) else {
+                continue;
+            };
+            for &i in activity.iter().filter(|&&i| get(&results[i]) != value) {
+                corrections.push(NormalizationCorrection {
+                    file_name: results[i].file_name.clone(),
+                    file_path: results[i].file_path.clone(),
+                    field,
+                    original: get(&results[i]).to_string(),
+                    corrected: value.to_string(),
+                    reason: format!("同じ作業（{} {}）の{}枚中の多数に統一", results[i].subphase, results[i].remarks, activity.len()),
+                    rule: None,
+                });
+            }
+        }
+    }
+    corrections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn photo(file_name: &str, remarks: &str, station: &str, focus_target: &str, photo_category: &str) -> AnalysisResult {
+        AnalysisResult {
+            file_name: file_name.to_string(),
+            remarks: remarks.to_string(),
+            station: station.to_string(),
+            focus_target: focus_target.to_string(),
+            photo_category: photo_category.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_station_and_category_within_set() {
+        let results = vec![
+            photo("1.jpg", "出来形測定", "", "全景", "施工状況写真"),
+            photo("2.jpg", "出来形測定", "No.1", "黒板アップ", "品質管理写真"),
+            photo("3.jpg", "出来形測定", "", "計測器アップ", "品質管理写真"),
+            // 測点が変われば別のセット
+            photo("4.jpg", "出来形測定", "No.2", "黒板アップ", "品質管理写真"),
+            photo("5.jpg", "出来形測定", "", "全景", ""),
+            photo("6.jpg", "舗設状況", "", "全景", "施工状況写真"),
+        ];
+        assert_eq!(photo_sets(&results), vec![vec![0, 1, 2], vec![3, 4]]);
+
+        let stations = propagate_station(&results);
+        let stations: Vec<(&str, &str)> = stations
+            .iter()
+            .map(|c| (c.file_name.as_str(), c.corrected.as_str()))
+            .collect();
+        assert_eq!(stations, vec![("1.jpg", "No.1"), ("3.jpg", "No.1"), ("5.jpg", "No.2")]);
+
+        let categories = unify_category(&results);
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories[0].file_name, "1.jpg");
+        assert_eq!(categories[0].original, "施工状況写真");
+        assert_eq!(categories[0].corrected, "品質管理写真");
+        assert_eq!(categories[1].file_name, "5.jpg");
+
+        // 表記違いの測点は揃えてからセットを作る
+        let results = vec![
+            photo("1.jpg", "出来形測定", "NO.1+5.0m", "全景", ""),
+            photo("2.jpg", "出来形測定", "No.1+5", "黒板アップ", ""),
+            photo("3.jpg", "出来形測定", "起点", "全景", ""),
+        ];
+        let stations = canonicalize_station(&results);
+        assert_eq!(stations.len(), 1);
+        assert_eq!((stations[0].file_name.as_str(), stations[0].corrected.as_str()), ("1.jpg", "No.1+5"));
+    }
+
+    #[test]
+    fn test_work_type_variety_by_activity() {
+        let activity = |file_name: &str, work_type: &str, variety: &str| AnalysisResult {
+            file_name: file_name.to_string(),
+            work_type: work_type.to_string(),
+            variety: variety.to_string(),
+            subphase: "表層工".to_string(),
+            remarks: "舗設状況".to_string(),
+            ..Default::default()
+        };
+        let results = vec![
+            activity("1.jpg", "舗装工", "舗装打換え工"),
+            activity("2.jpg", "区画線工", "舗装打換え工"),
+            activity("3.jpg", "舗装工", ""),
+            // 2枚で割れた作業は直さない
+            AnalysisResult { subphase: "基層工".to_string(), work_type: "舗装工".to_string(), ..Default::default() },
+            AnalysisResult { subphase: "基層工".to_string(), work_type: "区画線工".to_string(), ..Default::default() },
+        ];
+
+        let corrections = unify_work_type_variety(&results);
+        let fixed: Vec<(&str, CorrectionField, &str)> = corrections
+            .iter()
+            .map(|c| (c.file_name.as_str(), c.field, c.corrected.as_str()))
+            .collect();
+        assert_eq!(
+            fixed,
+            vec![
+                ("2.jpg", CorrectionField::WorkType, "舗装工"),
+                ("3.jpg", CorrectionField::Variety, "舗装打換え工"),
+            ]
+        );
+    }
+}
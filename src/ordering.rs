@@ -0,0 +1,413 @@
+//! 写真の並び順
+//!
+//! スキャン直後の並び（解析・result.json・台帳出力の順序）を決める。
+//!
+//! - `natural`: ファイル名の自然順（`IMG_9` → `IMG_10`）
+//! - `name`: ファイル名の文字コード順（従来の並び）
+//! - `exif`: 撮影日時順。カメラごとの時計ずれは `--camera-offset` で補正
+//! - `folder`: フォルダ → ファイル名（自然順）
+//! - `master`: 解析後、工種マスタの行順 → 測点 → 撮影日時（台帳の並び）
+
+use crate::scanner::ImageInfo;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use photo_ai_common::{AnalysisResult, HierarchyMaster, StationRange};
+use std::cmp::Ordering;
+use std::path::Path;
+
+/// 並び順
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// ファイル名の文字コード順
+    Name,
+    /// ファイル名の自然順（デフォルト）
+    #[default]
+    Natural,
+    /// 撮影日時順（カメラ別の時計補正あり）
+    ExifTime,
+    /// フォルダ → ファイル名
+    Folder,
+    /// 工種マスタの行順 → 測点 → 撮影日時（解析後に適用）
+    Master,
+}
+
+impl SortOrder {
+    /// 解析結果が必要な並び順か
+    pub fn needs_analysis(&self) -> bool {
+        matches!(self, SortOrder::Master)
+    }
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "name" => Ok(SortOrder::Name),
+            "natural" => Ok(SortOrder::Natural),
+            "exif" | "exif-datetime" | "date" => Ok(SortOrder::ExifTime),
+            "folder" => Ok(SortOrder::Folder),
+            "master" | "ledger" => Ok(SortOrder::Master),
+            _ => Err(format!("Unknown sort order: {}. Use natural, name, exif, folder, or master", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortOrder::Name => write!(f, "name"),
+            SortOrder::Natural => write!(f, "natural"),
+            SortOrder::ExifTime => write!(f, "exif"),
+            SortOrder::Folder => write!(f, "folder"),
+            SortOrder::Master => write!(f, "master"),
+        }
+    }
+}
+
+/// カメラ別の時計補正（`機種名=+秒` または `機種名=-H:MM:SS`）
+///
+/// 補正値はカメラの時刻に加算する。機種名は EXIF の Model（または Make + Model）と
+/// 大文字小文字を区別せずに照合する。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CameraOffset {
+    pub camera: String,
+    pub seconds: i64,
+}
+
+impl CameraOffset {
+    fn applies_to(&self, make: &str, model: &str) -> bool {
+        let camera = self.camera.trim();
+        camera.eq_ignore_ascii_case(model.trim())
+            || camera.eq_ignore_ascii_case(format!("{} {}", make.trim(), model.trim()).trim())
+    }
+}
+
+impl std::str::FromStr for CameraOffset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (camera, offset) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("カメラ補正は 機種名=秒 の形式で指定: {}", s))?;
+        let camera = camera.trim();
+        if camera.is_empty() {
+            return Err(format!("カメラ補正の機種名が空です: {}", s));
+        }
+        let seconds = parse_offset(offset.trim()).ok_or_else(|| format!("カメラ補正の時間が不正: {}", offset))?;
+        Ok(Self { camera: camera.to_string(), seconds })
+    }
+}
+
+/// `+90`・`-1:30`・`+0:01:30` を秒に変換
+fn parse_offset(s: &str) -> Option<i64> {
+    let (sign, body) = match s.as_bytes().first()? {
+        b'-' => (-1, &s[1..]),
+        b'+' => (1, &s[1..]),
+        _ => (1, s),
+    };
+    let parts: Vec<i64> = body.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let seconds = match parts.as_slice() {
+        [s] => *s,
+        [m, s] => m * 60 + s,
+        [h, m, s] => h * 3600 + m * 60 + s,
+        _ => return None,
+    };
+    Some(sign * seconds)
+}
+
+/// EXIF・フォルダ由来の日時文字列を解釈
+fn parse_datetime(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    ["%Y-%m-%d %H:%M:%S", "%Y:%m:%d %H:%M:%S", "%Y/%m/%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// 補正後の撮影日時
+fn corrected_time(date: &str, make: &str, model: &str, offsets: &[CameraOffset]) -> Option<NaiveDateTime> {
+    let time = parse_datetime(date)?;
+    let seconds = offsets
+        .iter()
+        .find(|o| o.applies_to(make, model))
+        .map(|o| o.seconds)
+        .unwrap_or(0);
+    Some(time + Duration::seconds(seconds))
+}
+
+/// 自然順の比較（数字部分は数値として比較、英字は大文字小文字を区別しない）
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    natural_cmp_chunks(a, b).then_with(|| a.cmp(b))
+}
+
+fn natural_cmp_chunks(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_digits = |it: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = it.next_if(|c| c.is_ascii_digit()) {
+                        digits.push(c);
+                    }
+                    digits
+                };
+                let (da, db) = (take_digits(&mut a), take_digits(&mut b));
+                let (ta, tb) = (da.trim_start_matches('0'), db.trim_start_matches('0'));
+                let ord = ta.len().cmp(&tb.len()).then_with(|| ta.cmp(tb));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ord = x.to_lowercase().cmp(y.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+/// 撮影日時の比較（日時のない写真は後ろ、同時刻はファイル名の自然順）
+fn time_cmp(a: Option<NaiveDateTime>, b: Option<NaiveDateTime>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn parent_of(path: &Path) -> String {
+    path.parent().map(|p| p.to_string_lossy().replace('\\', "/")).unwrap_or_default()
+}
+
+/// スキャンした写真を並べ替える（`master` は解析前なので自然順）
+pub fn sort_images(images: &mut [ImageInfo], order: SortOrder, offsets: &[CameraOffset]) {
+    match order {
+        SortOrder::Name => images.sort_by(|a, b| a.file_name.cmp(&b.file_name)),
+        SortOrder::Natural | SortOrder::Master => images.sort_by(|a, b| natural_cmp(&a.file_name, &b.file_name)),
+        SortOrder::Folder => images.sort_by(|a, b| {
+            natural_cmp(&parent_of(a.source_path()), &parent_of(b.source_path())).then_with(|| natural_cmp(&a.file_name, &b.file_name))
+        }),
+        SortOrder::ExifTime => images.sort_by_cached_key(|img| {
+            let time = img
+                .date
+                .as_deref()
+                .and_then(|d| corrected_time(d, &img.exif.camera_make, &img.exif.camera_model, offsets));
+            (time.is_none(), time, NaturalKey(img.file_name.clone()))
+        }),
+    }
+}
+
+/// 解析結果を並べ替える（`master` はマスタがなければ自然順）
+pub fn sort_results(results: &mut [AnalysisResult], order: SortOrder, offsets: &[CameraOffset], master: Option<&HierarchyMaster>) {
+    match order {
+        SortOrder::Name => results.sort_by(|a, b| a.file_name.cmp(&b.file_name)),
+        SortOrder::Natural => results.sort_by(|a, b| natural_cmp(&a.file_name, &b.file_name)),
+        SortOrder::Folder => results.sort_by(|a, b| {
+            natural_cmp(&parent_of(Path::new(&a.file_path)), &parent_of(Path::new(&b.file_path)))
+                .then_with(|| natural_cmp(&a.file_name, &b.file_name))
+        }),
+        SortOrder::ExifTime => results.sort_by(|a, b| {
+            let time = |r: &AnalysisResult| corrected_time(&r.date, &r.exif.camera_make, &r.exif.camera_model, offsets);
+            time_cmp(time(a), time(b)).then_with(|| natural_cmp(&a.file_name, &b.file_name))
+        }),
+        SortOrder::Master => match master {
+            Some(master) => sort_by_master(results, master),
+            None => results.sort_by(|a, b| natural_cmp(&a.file_name, &b.file_name)),
+        },
+    }
+}
+
+/// 台帳の並び: 工種マスタの行順 → 測点 → 撮影日時
+///
+/// マスタにない分類は末尾、同じ行の中では測点（No.x+y を距離に換算、範囲は始点）→ 日時の順。
+/// 比較できない項目が同じ場合は元の並びを保つ。
+pub fn sort_by_master(results: &mut [AnalysisResult], master: &HierarchyMaster) {
+    results.sort_by_cached_key(|r| {
+        let station = StationRange::parse(&r.station);
+        let time = parse_datetime(&r.date);
+        (
+            master_row_index(r, master),
+            station.is_none(),
+            station,
+            time.is_none(),
+            time,
+        )
+    });
+}
+
+/// 分類に最もよく一致するマスタ行の位置（備考まで → 作業段階まで → 種別まで → 工種のみ）
+fn master_row_index(result: &AnalysisResult, master: &HierarchyMaster) -> usize {
+    let rows = master.rows();
+    let find = |depth: usize| {
+        rows.iter().position(|row| {
+            let pairs = [
+                (&row.work_type, &result.work_type),
+                (&row.variety, &result.variety),
+                (&row.subphase, &result.subphase),
+                (&row.remarks, &result.remarks),
+            ];
+            pairs[..depth].iter().all(|(m, r)| !r.is_empty() && m == r)
+        })
+    };
+    if result.work_type.is_empty() {
+        return usize::MAX;
+    }
+    (1..=4).rev().find_map(find).unwrap_or(usize::MAX)
+}
+
+/// 並べ替えキー用の自然順ファイル名
+#[derive(PartialEq, Eq)]
+struct NaturalKey(String);
+
+impl Ord for NaturalKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        natural_cmp(&self.0, &other.0)
+    }
+}
+
+impl PartialOrd for NaturalKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(name: &str, date: Option<&str>, model: &str) -> ImageInfo {
+        ImageInfo {
+            path: Path::new("/photos").join(name),
+            file_name: name.rsplit('/').next().unwrap().to_string(),
+            date: date.map(String::from),
+            exif: photo_ai_common::ExifMetadata { camera_model: model.to_string(), ..Default::default() },
+            hints: Default::default(),
+            fingerprint: None,
+            format: None,
+            original_path: None,
+        }
+    }
+
+    fn names(images: &[ImageInfo]) -> Vec<&str> {
+        images.iter().map(|i| i.file_name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["IMG_10.jpg", "img_9.jpg", "IMG_1.jpg", "IMG_010a.jpg", "写真2.jpg", "写真11.jpg"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["IMG_1.jpg", "img_9.jpg", "IMG_10.jpg", "IMG_010a.jpg", "写真2.jpg", "写真11.jpg"]);
+        assert_eq!(natural_cmp("a01", "a1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_parse_sort_order_and_offset() {
+        assert_eq!("exif-datetime".parse::<SortOrder>().unwrap(), SortOrder::ExifTime);
+        assert!("random".parse::<SortOrder>().is_err());
+
+        let o: CameraOffset = "TG-6=-1:30".parse().unwrap();
+        assert_eq!(o, CameraOffset { camera: "TG-6".to_string(), seconds: -90 });
+        assert_eq!("Canon EOS R=+1:00:00".parse::<CameraOffset>().unwrap().seconds, 3600);
+        assert!("TG-6".parse::<CameraOffset>().is_err());
+        assert!("=30".parse::<CameraOffset>().is_err());
+        assert!("TG-6=abc".parse::<CameraOffset>().is_err());
+    }
+
+    #[test]
+    fn test_sort_images_by_exif_with_camera_offset() {
+        let mut images = vec![
+            image("A_1.jpg", Some("2025-01-18 10:00:30"), "TG-6"),
+            image("B_1.jpg", Some("2025-01-18 10:00:00"), "iPhone 15"),
+            image("B_2.jpg", Some("2025-01-18 10:01:00"), "iPhone 15"),
+            image("NODATE.jpg", None, "TG-6"),
+        ];
+
+        sort_images(&mut images, SortOrder::ExifTime, &[]);
+        assert_eq!(names(&images), vec!["B_1.jpg", "A_1.jpg", "B_2.jpg", "NODATE.jpg"]);
+
+        // TG-6 の時計が 1分遅れている
+        let offsets = vec!["tg-6=+60".parse().unwrap()];
+        sort_images(&mut images, SortOrder::ExifTime, &offsets);
+        assert_eq!(names(&images), vec!["B_1.jpg", "B_2.jpg", "A_1.jpg", "NODATE.jpg"]);
+    }
+
+    #[test]
+    fn test_sort_images_by_folder() {
+        let mut images = vec![
+            image("day10/IMG_1.jpg", None, ""),
+            image("day2/IMG_10.jpg", None, ""),
+            image("day2/IMG_9.jpg", None, ""),
+        ];
+        sort_images(&mut images, SortOrder::Folder, &[]);
+        let paths: Vec<_> = images.iter().map(|i| i.path.to_string_lossy().to_string()).collect();
+        assert_eq!(paths, vec!["/photos/day2/IMG_9.jpg", "/photos/day2/IMG_10.jpg", "/photos/day10/IMG_1.jpg"]);
+    }
+
+    #[test]
+    fn test_sort_by_master_row_station_date() {
+        let master = HierarchyMaster::from_csv_str(
+            "写真区分,写真種別,工種,種別,作業段階,備考,検索パターン\n\
+             直接工事費,施工状況写真,舗装工,舗装打換え工,舗設,表層工,\n\
+             直接工事費,品質管理写真,舗装工,舗装打換え工,温度管理,到着温度,\n",
+        )
+        .unwrap();
+        let result = |name: &str, subphase: &str, remarks: &str, station: &str, date: &str| AnalysisResult {
+            file_name: name.to_string(),
+            work_type: "舗装工".to_string(),
+            variety: "舗装打換え工".to_string(),
+            subphase: subphase.to_string(),
+            remarks: remarks.to_string(),
+            station: station.to_string(),
+            date: date.to_string(),
+            ..Default::default()
+        };
+        let mut results = vec![
+            result("temp.jpg", "温度管理", "到着温度", "No.1", "2025-01-18 09:00:00"),
+            result("far.jpg", "舗設", "表層工", "No.12+5", "2025-01-18 08:00:00"),
+            result("near_late.jpg", "舗設", "表層工", "No.3", "2025-01-18 11:00:00"),
+            result("near_early.jpg", "舗設", "", "No.3", "2025-01-18 10:00:00"),
+            AnalysisResult { file_name: "unknown.jpg".to_string(), ..Default::default() },
+        ];
+        sort_by_master(&mut results, &master);
+        let order: Vec<_> = results.iter().map(|r| r.file_name.as_str()).collect();
+        assert_eq!(order, vec!["near_early.jpg", "near_late.jpg", "far.jpg", "temp.jpg", "unknown.jpg"]);
+    }
+
+    #[test]
+    fn test_sort_by_master_station_notations() {
+        let master = HierarchyMaster::from_csv_str(
+            "写真区分,写真種別,工種,種別,細別,備考\n直接工事費,施工状況写真,舗装工,舗装打換え工,表層工,舗設\n",
+        )
+        .unwrap();
+        let result = |name: &str, station: &str| AnalysisResult {
+            file_name: name.to_string(),
+            work_type: "舗装工".to_string(),
+            station: station.to_string(),
+            ..Default::default()
+        };
+        // 表記が違っても距離で比べ、範囲は始点で並べる
+        let mut results = vec![
+            result("a.jpg", "NO.10"),
+            result("b.jpg", "No9+15.0m"),
+            result("c.jpg", "No.2〜No.4"),
+            result("d.jpg", "測点2"),
+        ];
+        sort_by_master(&mut results, &master);
+        let order: Vec<_> = results.iter().map(|r| r.file_name.as_str()).collect();
+        assert_eq!(order, vec!["d.jpg", "c.jpg", "b.jpg", "a.jpg"]);
+    }
+}
@@ -0,0 +1,439 @@
+//! 規格値による品質管理チェック（qc）
+//!
+//! 工種・細別・備考ごとの規格（到着温度 150℃以上、開放温度 50℃以下、厚さ 設計値±許容差、
+//! 締固め度 96%以上 など）をJSONで定義し、各写真の計測値を判定する。
+//!
+//! ```json
+//! { "specs": [
+//!     { "name": "到着温度", "when": { "remarks": "re:到着温度" }, "measure": "temperature", "min": 150 },
+//!     { "name": "表層厚さ", "when": { "subphase": "表層工", "remarks": "re:厚" },
+//!       "measure": "thickness", "design": 50, "tolerance": 7 }
+//! ] }
+//! ```
+//!
+//! - `when` はエイリアスの条件（[`Conditions`]）をそのまま使う（完全一致、`re:` で正規表現）。すべて満たす写真に適用する
+//! - 計測値は `measurements` から読む。条件に合うのに値がなければ「未測定」
+//!
+//! `normalizer::measurements` の妥当範囲は OCR の誤読検出用で、合否はこちらで判定する。
+
+use crate::analyzer::AnalysisResult;
+use crate::config;
+use crate::error::{PhotoAiError, Result};
+use crate::lint::csv_field;
+use crate::normalizer::measurements::{self, MeasurementType};
+use photo_ai_common::{AliasField, Conditions};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// 工事ごとの規格表の置き場所（`--spec` 省略時）
+pub const PROJECT_SPEC_FILE: &str = ".photo-ai/qc.json";
+
+/// 判定する計測値の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QcMeasure {
+    /// 温度（℃）
+    Temperature,
+    /// 厚さ・寸法（mm に換算）
+    Thickness,
+    /// 締固め度（%）
+    Compaction,
+}
+
+impl QcMeasure {
+    pub fn unit(self) -> &'static str {
+        match self {
+            QcMeasure::Temperature => "℃",
+            QcMeasure::Thickness => "mm",
+            QcMeasure::Compaction => "%",
+        }
+    }
+
+    /// 計測値の文字列から値を読む
+    pub fn extract(self, text: &str) -> Option<f64> {
+        match self {
+            QcMeasure::Temperature => measurements::extract_temperature(text),
+            QcMeasure::Thickness => measurements::extract_dimension_mm(text),
+            QcMeasure::Compaction => measurements::extract_measurements(text).into_iter().find_map(|m| match m {
+                MeasurementType::Density(value) => Some(value),
+                _ => None,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for QcMeasure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QcMeasure::Temperature => write!(f, "温度"),
+            QcMeasure::Thickness => write!(f, "厚さ"),
+            QcMeasure::Compaction => write!(f, "締固め度"),
+        }
+    }
+}
+
+/// 規格1件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QcSpec {
+    #[serde(default)]
+    pub name: String,
+    /// 適用する写真の条件（項目 → 完全一致 or `re:正規表現`）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub when: BTreeMap<AliasField, String>,
+    pub measure: QcMeasure,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// 設計値（`tolerance` と組で 設計値±許容差）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub design: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerance: Option<f64>,
+}
+
+impl QcSpec {
+    /// 表示名（`name` がなければ計測値の種類）
+    pub fn label(&self) -> String {
+        if self.name.is_empty() {
+            self.measure.to_string()
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// 下限・上限（設計値±許容差と `min`/`max` の狭いほう）
+    pub fn bounds(&self) -> (Option<f64>, Option<f64>) {
+        let (design_min, design_max) = match (self.design, self.tolerance) {
+            (Some(design), Some(tolerance)) => (Some(design - tolerance), Some(design + tolerance)),
+            _ => (None, None),
+        };
+        let narrow = |a: Option<f64>, b: Option<f64>, pick: fn(f64, f64) -> f64| match (a, b) {
+            (Some(a), Some(b)) => Some(pick(a, b)),
+            (a, b) => a.or(b),
+        };
+        (narrow(self.min, design_min, f64::max), narrow(self.max, design_max, f64::min))
+    }
+
+    /// 規格の表記（`150℃以上` / `50℃以下` / `50±7mm` / `45〜55mm`）
+    pub fn range_text(&self) -> String {
+        let unit = self.measure.unit();
+        if let (Some(design), Some(tolerance), None, None) = (self.design, self.tolerance, self.min, self.max) {
+            return format!("{}±{}{}", design, tolerance, unit);
+        }
+        match self.bounds() {
+            (Some(min), Some(max)) => format!("{}〜{}{}", min, max, unit),
+            (Some(min), None) => format!("{}{}以上", min, unit),
+            (None, Some(max)) => format!("{}{}以下", max, unit),
+            (None, None) => "規格なし".to_string(),
+        }
+    }
+
+    /// 値が規格内か
+    pub fn accepts(&self, value: f64) -> bool {
+        let (min, max) = self.bounds();
+        min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+    }
+}
+
+/// 規格表
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QcSpecTable {
+    #[serde(default)]
+    pub specs: Vec<QcSpec>,
+}
+
+impl QcSpecTable {
+    /// JSON文字列から読み込み（規格値のない規格・不正な条件はエラー）
+    pub fn from_json(json: &str) -> Result<Self> {
+        let table: Self = serde_json::from_str(json.trim_start_matches('\u{feff}'))?;
+        table.compile()?;
+        Ok(table)
+    }
+
+    /// ファイルから読み込み
+    pub fn from_file(path: &Path) -> Result<Self> {
+        config::load_project_file(path, Self::from_json)
+    }
+
+    fn compile(&self) -> Result<Vec<CompiledSpec<'_>>> {
+        self.specs.iter().map(CompiledSpec::new).collect()
+    }
+
+    /// 全写真を判定（規格の条件に合った組み合わせのみ、写真順 → 規格順）
+    pub fn evaluate<'a>(&'a self, results: &[AnalysisResult]) -> Result<Vec<QcCheck<'a>>> {
+        let specs = self.compile()?;
+        let mut checks = Vec::new();
+        for result in results {
+            for spec in specs.iter().filter(|s| s.when.matches(result)) {
+                let value = spec.spec.measure.extract(&result.measurements);
+                let status = match value {
+                    // 条件のない規格は値のある写真だけ判定する
+                    None if spec.when.is_empty() => continue,
+                    None => QcStatus::Missing,
+                    Some(v) if spec.spec.accepts(v) => QcStatus::Pass,
+                    Some(_) => QcStatus::Fail,
+                };
+                checks.push(QcCheck {
+                    file_name: result.file_name.clone(),
+                    file_path: result.file_path.clone(),
+                    spec: spec.spec,
+                    value,
+                    status,
+                });
+            }
+        }
+        Ok(checks)
+    }
+}
+
+/// 条件を解釈済みの規格
+struct CompiledSpec<'a> {
+    spec: &'a QcSpec,
+    when: Conditions,
+}
+
+impl<'a> CompiledSpec<'a> {
+    fn new(spec: &'a QcSpec) -> Result<Self> {
+        let label = format!("規格「{}」", spec.label());
+        let (min, max) = spec.bounds();
+        if min.is_none() && max.is_none() {
+            return Err(PhotoAiError::Config(format!("{}: min・max・design+tolerance のいずれかが必要です", label)));
+        }
+        let when = Conditions::parse(&spec.when, &label)?;
+        Ok(Self { spec, when })
+    }
+}
+
+/// 判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QcStatus {
+    Pass,
+    Fail,
+    /// 規格の対象だが計測値がない
+    Missing,
+}
+
+impl QcStatus {
+    /// レポート用の識別子
+    pub fn code(self) -> &'static str {
+        match self {
+            QcStatus::Pass => "pass",
+            QcStatus::Fail => "fail",
+            QcStatus::Missing => "missing",
+        }
+    }
+}
+
+impl fmt::Display for QcStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QcStatus::Pass => write!(f, "合格"),
+            QcStatus::Fail => write!(f, "不合格"),
+            QcStatus::Missing => write!(f, "未測定"),
+        }
+    }
+}
+
+/// 写真1枚・規格1件の判定結果
+#[derive(Debug, Clone)]
+pub struct QcCheck<'a> {
+    pub file_name: String,
+    /// ファイルパス（`--recursive` で別フォルダの同名写真と区別する）
+    pub file_path: String,
+    pub spec: &'a QcSpec,
+    pub value: Option<f64>,
+    pub status: QcStatus,
+}
+
+impl fmt::Display for QcCheck<'_> {
+    /// `到着温度: 145℃（規格 150℃以上）`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Some(value) => write!(f, "{}: {}{}（規格 {}）", self.spec.label(), value, self.spec.measure.unit(), self.spec.range_text()),
+            None => write!(f, "{}: 計測値なし（規格 {}）", self.spec.label(), self.spec.range_text()),
+        }
+    }
+}
+
+/// 判定の件数（合格, 不合格, 未測定）
+pub fn summarize(checks: &[QcCheck<'_>]) -> (usize, usize, usize) {
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    (count(QcStatus::Pass), count(QcStatus::Fail), count(QcStatus::Missing))
+}
+
+/// 解析結果の `qc_failures` を不合格の内容で置き換え、不合格のついた写真の数を返す
+///
+/// 写真はパスで照合する（パスのない結果はファイル名で照合）
+pub fn apply_failures(results: &mut [AnalysisResult], checks: &[QcCheck<'_>]) -> usize {
+    let mut failures: HashMap<(&str, &str), Vec<String>> = HashMap::new();
+    for check in checks.iter().filter(|c| c.status == QcStatus::Fail) {
+        failures.entry((&check.file_path, &check.file_name)).or_default().push(check.to_string());
+    }
+    let mut count = 0;
+    for result in results {
+        result.qc_failures = failures.remove(&(result.file_path.as_str(), result.file_name.as_str())).unwrap_or_default();
+        if !result.qc_failures.is_empty() {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// レポート1行
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportRow<'a> {
+    file_name: &'a str,
+    spec: String,
+    measure: QcMeasure,
+    value: Option<f64>,
+    range: String,
+    status: &'static str,
+}
+
+/// レポートを書き出す（拡張子 .json は JSON、それ以外は Excel で開ける BOM 付き CSV）
+pub fn write_report(checks: &[QcCheck<'_>], path: &Path) -> Result<()> {
+    let rows: Vec<ReportRow> = checks
+        .iter()
+        .map(|c| ReportRow {
+            file_name: &c.file_name,
+            spec: c.spec.label(),
+            measure: c.spec.measure,
+            value: c.value,
+            range: c.spec.range_text(),
+            status: c.status.code(),
+        })
+        .collect();
+
+    let is_json = path
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    let content = if is_json {
+        serde_json::to_string_pretty(&rows)?
+    } else {
+        let mut csv = String::from("\u{feff}ファイル名,規格,計測値,規格値,判定\n");
+        for (row, check) in rows.iter().zip(checks) {
+            let value = row.value.map(|v| format!("{}{}", v, check.spec.measure.unit())).unwrap_or_default();
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                csv_field(row.file_name),
+                csv_field(&row.spec),
+                csv_field(&value),
+                csv_field(&row.range),
+                check.status
+            ));
+        }
+        csv
+    };
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPECS: &str = r#"{ "specs": [
+        { "name": "到着温度", "when": { "remarks": "re:到着温度" }, "measure": "temperature", "min": 150 },
+        { "name": "開放温度", "when": { "remarks": "re:開放温度" }, "measure": "temperature", "max": 50 },
+        { "name": "表層厚さ", "when": { "subphase": "表層工", "remarks": "re:厚" }, "measure": "thickness", "design": 50, "tolerance": 7 },
+        { "name": "締固め度", "measure": "compaction", "min": 96 }
+    ] }"#;
+
+    fn result(file_name: &str, subphase: &str, remarks: &str, measurements: &str) -> AnalysisResult {
+        AnalysisResult {
+            file_name: file_name.to_string(),
+            subphase: subphase.to_string(),
+            remarks: remarks.to_string(),
+            measurements: measurements.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evaluate_specs() {
+        let table = QcSpecTable::from_json(SPECS).unwrap();
+        let results = vec![
+            result("1.jpg", "表層工", "到着温度測定", "162.5℃"),
+            result("2.jpg", "表層工", "到着温度測定", "145℃"),
+            result("3.jpg", "表層工", "開放温度測定", ""),
+            result("4.jpg", "表層工", "厚さ測定", "t=4.2cm 締固め度 97.1%"),
+            result("5.jpg", "基層工", "厚さ測定", "40mm"),
+        ];
+
+        let checks = table.evaluate(&results).unwrap();
+        let statuses: Vec<(&str, String, QcStatus)> =
+            checks.iter().map(|c| (c.file_name.as_str(), c.spec.label(), c.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("1.jpg", "到着温度".to_string(), QcStatus::Pass),
+                ("2.jpg", "到着温度".to_string(), QcStatus::Fail),
+                ("3.jpg", "開放温度".to_string(), QcStatus::Missing),
+                ("4.jpg", "表層厚さ".to_string(), QcStatus::Fail),
+                ("4.jpg", "締固め度".to_string(), QcStatus::Pass),
+            ]
+        );
+        assert_eq!(checks[1].to_string(), "到着温度: 145℃（規格 150℃以上）");
+        assert_eq!(checks[3].to_string(), "表層厚さ: 42mm（規格 50±7mm）");
+        assert_eq!(summarize(&checks), (2, 2, 1));
+
+        let mut results = results;
+        results[0].qc_failures = vec!["古い判定".to_string()];
+        assert_eq!(apply_failures(&mut results, &checks), 2);
+        assert!(results[0].qc_failures.is_empty());
+        assert_eq!(results[3].qc_failures, vec!["表層厚さ: 42mm（規格 50±7mm）"]);
+    }
+
+    #[test]
+    fn test_failures_follow_the_photo_path() {
+        let table = QcSpecTable::from_json(SPECS).unwrap();
+        let mut results = vec![
+            result("IMG_1.jpg", "表層工", "到着温度測定", "162℃"),
+            result("IMG_1.jpg", "表層工", "到着温度測定", "145℃"),
+        ];
+        results[0].file_path = "day1/IMG_1.jpg".to_string();
+        results[1].file_path = "day2/IMG_1.jpg".to_string();
+
+        let checks = table.evaluate(&results).unwrap();
+        assert_eq!(apply_failures(&mut results, &checks), 1);
+        assert!(results[0].qc_failures.is_empty());
+        assert_eq!(results[1].qc_failures, vec!["到着温度: 145℃（規格 150℃以上）"]);
+    }
+
+    #[test]
+    fn test_spec_bounds_and_validation() {
+        let spec = QcSpec {
+            name: String::new(),
+            when: BTreeMap::new(),
+            measure: QcMeasure::Thickness,
+            min: Some(45.0),
+            max: None,
+            design: Some(50.0),
+            tolerance: Some(7.0),
+        };
+        assert_eq!(spec.bounds(), (Some(45.0), Some(57.0)));
+        assert_eq!(spec.range_text(), "45〜57mm");
+        assert!(spec.accepts(57.0) && !spec.accepts(44.9));
+
+        let error = QcSpecTable::from_json(r#"{ "specs": [{ "name": "x", "measure": "temperature" }] }"#).unwrap_err();
+        assert!(error.to_string().contains("規格「x」"));
+        assert!(QcSpecTable::from_json(r#"{ "specs": [{ "when": { "remarks": "re:(" }, "measure": "compaction", "min": 96 }] }"#).is_err());
+
+        // 条件の項目はエイリアスと共通（日付も使える）
+        let table = QcSpecTable::from_json(
+            r#"{ "specs": [{ "when": { "date": "re:^2024-06" }, "measure": "temperature", "min": 150 }] }"#,
+        )
+        .unwrap();
+        let mut june = result("1.jpg", "表層工", "到着温度測定", "160℃");
+        june.date = "2024-06-01".to_string();
+        let mut july = result("2.jpg", "表層工", "到着温度測定", "160℃");
+        july.date = "2024-07-01".to_string();
+        let checks = table.evaluate(&[june, july]).unwrap();
+        assert_eq!(checks.iter().map(|c| c.file_name.as_str()).collect::<Vec<_>>(), vec!["1.jpg"]);
+    }
+}
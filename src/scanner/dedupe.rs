@@ -0,0 +1,257 @@
+//! 類似写真（連写・撮り直し）の検出
+//!
+//! 知覚ハッシュのハミング距離で写真をクラスタリングし、
+//! 各クラスタで最も鮮明・適正露出の1枚をベストショットとして提案する。
+//! 残りは解析対象から外すか、除外フォルダ（非使用）へ移動できる。
+
+use super::phash::{self, hamming_distance};
+use super::ImageInfo;
+use crate::error::Result;
+use std::path::{Path, PathBuf};
+
+/// 類似判定のデフォルト閾値（64bit中の異なるビット数）
+pub const DEFAULT_THRESHOLD: u32 = 6;
+
+/// 除外先フォルダのデフォルト名（スキャン時の除外パターンと一致）
+pub const DEFAULT_EXCLUDE_DIR: &str = "非使用";
+
+/// 類似写真のクラスタ
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateCluster {
+    /// クラスタに属する画像のインデックス（スキャン順）
+    pub members: Vec<usize>,
+    /// ベストショットのインデックス
+    pub best: usize,
+}
+
+impl DuplicateCluster {
+    /// ベストショット以外のインデックス
+    pub fn rejected(&self) -> impl Iterator<Item = usize> + '_ {
+        self.members.iter().copied().filter(move |&i| i != self.best)
+    }
+}
+
+/// 未計算の画像について指紋を計算する
+///
+/// 読み込みに失敗した画像は指紋なしのまま残り、クラスタリング対象外になる。
+/// 失敗したファイル名の一覧を返す。
+pub fn compute_fingerprints(images: &mut [ImageInfo]) -> Vec<String> {
+    let mut failed = Vec::new();
+    for img in images.iter_mut().filter(|img| img.fingerprint.is_none()) {
+        match phash::compute_fingerprint(&img.path) {
+            Ok(fp) => img.fingerprint = Some(fp),
+            Err(_) => failed.push(img.file_name.clone()),
+        }
+    }
+    failed
+}
+
+/// 類似写真をクラスタリング（2枚以上のクラスタのみ返す）
+pub fn find_clusters(images: &[ImageInfo], threshold: u32) -> Vec<DuplicateCluster> {
+    let n = images.len();
+    let mut parent: Vec<usize> = (0..n).collect();
+
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for (i, img_a) in images.iter().enumerate() {
+        let Some(a) = img_a.fingerprint else { continue };
+        for (j, img_b) in images.iter().enumerate().skip(i + 1) {
+            let Some(b) = img_b.fingerprint else { continue };
+            if hamming_distance(a.phash, b.phash) <= threshold {
+                let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                if ri != rj {
+                    parent[rj.max(ri)] = ri.min(rj);
+                }
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for i in 0..n {
+        let root = find(&mut parent, i);
+        groups[root].push(i);
+    }
+
+    groups
+        .into_iter()
+        .filter(|members| members.len() >= 2)
+        .map(|members| {
+            let best = members
+                .iter()
+                .copied()
+                .max_by(|&a, &b| {
+                    let score = |i: usize| images[i].fingerprint.map(|f| f.quality_score()).unwrap_or(0.0);
+                    score(a).total_cmp(&score(b)).then(b.cmp(&a))
+                })
+                .unwrap_or(members[0]);
+            DuplicateCluster { members, best }
+        })
+        .collect()
+}
+
+/// ベストショット以外を除外した画像リストを返す
+pub fn exclude_duplicates(images: &[ImageInfo], clusters: &[DuplicateCluster]) -> Vec<ImageInfo> {
+    let rejected: std::collections::HashSet<usize> = clusters.iter().flat_map(|c| c.rejected()).collect();
+    images
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !rejected.contains(i))
+        .map(|(_, img)| img.clone())
+        .collect()
+}
+
+/// ベストショット以外を除外フォルダへ移動する
+///
+/// 各画像の親フォルダ直下に `exclude_dir` を作成して移動する（HEIC は変換前のファイルを移動する）。
+/// 除外フォルダに同名のファイルがあれば `IMG_0001 (2).JPG` のように番号を付け、上書きしない。
+/// 移動後のパスを返す。
+pub fn move_duplicates(
+    images: &[ImageInfo],
+    clusters: &[DuplicateCluster],
+    exclude_dir: &str,
+) -> Result<Vec<PathBuf>> {
+    let mut moved = Vec::new();
+    for idx in clusters.iter().flat_map(|c| c.rejected()) {
+        let src = images[idx].source_path();
+        let parent = src.parent().unwrap_or_else(|| Path::new("."));
+        let dest_dir = parent.join(exclude_dir);
+        std::fs::create_dir_all(&dest_dir)?;
+        let file_name = src.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| images[idx].file_name.clone());
+        let dest = unique_destination(&dest_dir, &file_name);
+        if std::fs::rename(src, &dest).is_err() {
+            // 別ドライブ等でrenameできない場合はコピー＋削除
+            std::fs::copy(src, &dest)?;
+            std::fs::remove_file(src)?;
+        }
+        moved.push(dest);
+    }
+    Ok(moved)
+}
+
+/// `dir` 内で既存のファイルと重ならないパス（`name.jpg` → `name (2).jpg` → ...）
+fn unique_destination(dir: &Path, file_name: &str) -> PathBuf {
+    let dest = dir.join(file_name);
+    if !dest.exists() {
+        return dest;
+    }
+    let (stem, ext) = match file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (file_name, String::new()),
+    };
+    (2..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|path| !path.exists())
+        .expect("連番は尽きない")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::phash::ImageFingerprint;
+
+    fn image(name: &str, phash: u64, sharpness: f64, brightness: f64) -> ImageInfo {
+        ImageInfo {
+            path: PathBuf::from(name),
+            file_name: name.to_string(),
+            fingerprint: Some(ImageFingerprint { phash, sharpness, brightness }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_clusters_groups_near_duplicates() {
+        let images = vec![
+            image("a.jpg", 0xFFFF_0000_FFFF_0000, 50.0, 0.5),
+            image("b.jpg", 0xFFFF_0000_FFFF_0001, 120.0, 0.5),
+            image("c.jpg", 0x0000_FFFF_0000_FFFF, 80.0, 0.5),
+            image("d.jpg", 0xFFFF_0000_FFFF_0003, 90.0, 0.5),
+        ];
+
+        let clusters = find_clusters(&images, DEFAULT_THRESHOLD);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].members, vec![0, 1, 3]);
+        assert_eq!(clusters[0].best, 1);
+        assert_eq!(clusters[0].rejected().collect::<Vec<_>>(), vec![0, 3]);
+    }
+
+    #[test]
+    fn test_best_shot_penalizes_bad_exposure() {
+        let images = vec![
+            image("dark.jpg", 0, 100.0, 0.02),
+            image("ok.jpg", 1, 80.0, 0.5),
+        ];
+        let clusters = find_clusters(&images, DEFAULT_THRESHOLD);
+        assert_eq!(clusters[0].best, 1);
+    }
+
+    #[test]
+    fn test_images_without_fingerprint_are_ignored() {
+        let mut images = vec![image("a.jpg", 0, 1.0, 0.5), image("b.jpg", 0, 1.0, 0.5)];
+        images[1].fingerprint = None;
+        assert!(find_clusters(&images, DEFAULT_THRESHOLD).is_empty());
+    }
+
+    #[test]
+    fn test_exclude_duplicates_keeps_best_and_singletons() {
+        let images = vec![
+            image("a.jpg", 0, 10.0, 0.5),
+            image("b.jpg", 1, 20.0, 0.5),
+            image("c.jpg", u64::MAX, 5.0, 0.5),
+        ];
+        let clusters = find_clusters(&images, DEFAULT_THRESHOLD);
+        let kept = exclude_duplicates(&images, &clusters);
+        let names: Vec<_> = kept.iter().map(|i| i.file_name.as_str()).collect();
+        assert_eq!(names, vec!["b.jpg", "c.jpg"]);
+    }
+
+    #[test]
+    fn test_move_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.jpg");
+        let b = dir.path().join("b.jpg");
+        std::fs::write(&a, b"a").unwrap();
+        std::fs::write(&b, b"b").unwrap();
+
+        let mut images = vec![image("a.jpg", 0, 10.0, 0.5), image("b.jpg", 0, 20.0, 0.5)];
+        images[0].path = a.clone();
+        images[1].path = b.clone();
+
+        let clusters = find_clusters(&images, DEFAULT_THRESHOLD);
+        let moved = move_duplicates(&images, &clusters, DEFAULT_EXCLUDE_DIR).unwrap();
+
+        assert_eq!(moved, vec![dir.path().join(DEFAULT_EXCLUDE_DIR).join("a.jpg")]);
+        assert!(!a.exists());
+        assert!(b.exists());
+        assert!(moved[0].exists());
+    }
+
+    #[test]
+    fn test_move_duplicates_does_not_overwrite() {
+        // 別のサブフォルダから同名の写真が移動済み
+        let dir = tempfile::tempdir().unwrap();
+        let exclude = dir.path().join(DEFAULT_EXCLUDE_DIR);
+        std::fs::create_dir_all(&exclude).unwrap();
+        std::fs::write(exclude.join("IMG_0001.JPG"), b"old").unwrap();
+
+        let a = dir.path().join("IMG_0001.JPG");
+        let b = dir.path().join("IMG_0002.JPG");
+        std::fs::write(&a, b"new").unwrap();
+        std::fs::write(&b, b"b").unwrap();
+        let mut images = vec![image("IMG_0001.JPG", 0, 10.0, 0.5), image("IMG_0002.JPG", 0, 20.0, 0.5)];
+        images[0].path = a;
+        images[1].path = b;
+
+        let clusters = find_clusters(&images, DEFAULT_THRESHOLD);
+        let moved = move_duplicates(&images, &clusters, DEFAULT_EXCLUDE_DIR).unwrap();
+
+        assert_eq!(moved, vec![exclude.join("IMG_0001 (2).JPG")]);
+        assert_eq!(std::fs::read(exclude.join("IMG_0001.JPG")).unwrap(), b"old");
+        assert_eq!(std::fs::read(&moved[0]).unwrap(), b"new");
+    }
+}
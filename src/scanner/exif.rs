@@ -1,22 +1,199 @@
+//! EXIF 読み取り
+//!
+//! 撮影日時に加え、向き・GPS・カメラ・画素数・ソフトウェアを取得する。
+//! 画素数が EXIF にない場合は画像ヘッダーから補う。
+
+use ::exif::{Exif, In, Tag, Value};
+use ::image as image_crate;
+use photo_ai_common::ExifMetadata;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 
-pub fn extract_date(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+fn read_exif(path: &Path) -> Result<Exif, Box<dyn std::error::Error>> {
     let file = File::open(path)?;
     let mut bufreader = BufReader::new(file);
-    let exif_reader = exif::Reader::new();
-    let exif = exif_reader.read_from_container(&mut bufreader)?;
+    let exif_reader = ::exif::Reader::new();
+    Ok(exif_reader.read_from_container(&mut bufreader)?)
+}
+
+pub fn extract_date(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let exif = read_exif(path)?;
+    date_from(&exif).ok_or_else(|| "No date found in EXIF".into())
+}
 
-    // DateTimeOriginal を探す
-    if let Some(field) = exif.get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY) {
-        return Ok(field.display_value().to_string());
+/// 撮影日時と撮影メタデータをまとめて取得
+///
+/// EXIF がないファイルでも画素数は画像ヘッダーから埋める
+pub fn extract(path: &Path) -> (Option<String>, ExifMetadata) {
+    let (date, mut metadata) = match read_exif(path) {
+        Ok(exif) => (date_from(&exif), metadata_from(&exif)),
+        Err(_) => (None, ExifMetadata::default()),
+    };
+    if metadata.width == 0 || metadata.height == 0 {
+        if let Some((w, h)) = header_dimensions(path) {
+            metadata.width = w;
+            metadata.height = h;
+        }
     }
+    (date, metadata)
+}
 
-    // DateTime を探す
-    if let Some(field) = exif.get_field(exif::Tag::DateTime, exif::In::PRIMARY) {
-        return Ok(field.display_value().to_string());
+/// EXIF Orientation のみ取得（未設定・読み取り不可は 0）
+pub fn read_orientation(path: &Path) -> u16 {
+    read_exif(path)
+        .ok()
+        .and_then(|exif| uint_field(&exif, Tag::Orientation))
+        .map(|v| v as u16)
+        .unwrap_or(0)
+}
+
+fn date_from(exif: &Exif) -> Option<String> {
+    // DateTimeOriginal → DateTime の順に探す
+    [Tag::DateTimeOriginal, Tag::DateTime]
+        .iter()
+        .find_map(|&tag| exif.get_field(tag, In::PRIMARY))
+        .map(|field| field.display_value().to_string())
+}
+
+fn metadata_from(exif: &Exif) -> ExifMetadata {
+    ExifMetadata {
+        orientation: uint_field(exif, Tag::Orientation).unwrap_or(0) as u16,
+        gps_latitude: gps_coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, b'S'),
+        gps_longitude: gps_coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, b'W'),
+        gps_altitude: gps_altitude(exif),
+        camera_make: ascii_field(exif, Tag::Make),
+        camera_model: ascii_field(exif, Tag::Model),
+        width: uint_field(exif, Tag::PixelXDimension).unwrap_or(0),
+        height: uint_field(exif, Tag::PixelYDimension).unwrap_or(0),
+        software: ascii_field(exif, Tag::Software),
     }
+}
 
-    Err("No date found in EXIF".into())
+fn uint_field(exif: &Exif, tag: Tag) -> Option<u32> {
+    exif.get_field(tag, In::PRIMARY)?.value.get_uint(0)
+}
+
+fn ascii_field(exif: &Exif, tag: Tag) -> String {
+    match exif.get_field(tag, In::PRIMARY).map(|f| &f.value) {
+        Some(Value::Ascii(values)) => values
+            .iter()
+            .map(|v| String::from_utf8_lossy(v).trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string())
+            .find(|s| !s.is_empty())
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// 度分秒（Rational×3）を10進度に変換し、南緯・西経は負にする
+fn gps_coordinate(exif: &Exif, tag: Tag, ref_tag: Tag, negative_ref: u8) -> Option<f64> {
+    let Value::Rational(ref dms) = exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    if dms.is_empty() {
+        return None;
+    }
+    let degrees = dms
+        .iter()
+        .take(3)
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(r, div)| r.to_f64() / div)
+        .sum::<f64>();
+    if !degrees.is_finite() {
+        return None;
+    }
+
+    let negative = matches!(
+        exif.get_field(ref_tag, In::PRIMARY).map(|f| &f.value),
+        Some(Value::Ascii(v)) if v.first().and_then(|s| s.first()) == Some(&negative_ref)
+    );
+    Some(if negative { -degrees } else { degrees })
+}
+
+/// 高度（GPSAltitudeRef=1 は海面下）
+fn gps_altitude(exif: &Exif) -> Option<f64> {
+    let Value::Rational(ref alt) = exif.get_field(Tag::GPSAltitude, In::PRIMARY)?.value else {
+        return None;
+    };
+    let altitude = alt.first()?.to_f64();
+    if !altitude.is_finite() {
+        return None;
+    }
+    let below_sea = uint_field(exif, Tag::GPSAltitudeRef) == Some(1);
+    Some(if below_sea { -altitude } else { altitude })
+}
+
+fn header_dimensions(path: &Path) -> Option<(u32, u32)> {
+    image_crate::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::exif::{experimental::Writer, Field, Rational};
+    use std::io::Cursor;
+
+    fn field(tag: Tag, value: Value) -> Field {
+        Field { tag, ifd_num: In::PRIMARY, value }
+    }
+
+    fn rational(num: u32, denom: u32) -> Rational {
+        Rational { num, denom }
+    }
+
+    #[test]
+    fn test_metadata_from_exif() {
+        let fields = [
+            field(Tag::Orientation, Value::Short(vec![6])),
+            field(Tag::Make, Value::Ascii(vec![b"OLYMPUS".to_vec()])),
+            field(Tag::Model, Value::Ascii(vec![b"TG-6\0".to_vec()])),
+            field(Tag::Software, Value::Ascii(vec![b"Version 1.0".to_vec()])),
+            field(Tag::PixelXDimension, Value::Long(vec![4000])),
+            field(Tag::PixelYDimension, Value::Long(vec![3000])),
+            // 35°30'36" S, 139°45'0" E, 海面下 12.5m
+            field(Tag::GPSLatitudeRef, Value::Ascii(vec![b"S".to_vec()])),
+            field(Tag::GPSLatitude, Value::Rational(vec![rational(35, 1), rational(30, 1), rational(36, 1)])),
+            field(Tag::GPSLongitudeRef, Value::Ascii(vec![b"E".to_vec()])),
+            field(Tag::GPSLongitude, Value::Rational(vec![rational(139, 1), rational(45, 1), rational(0, 1)])),
+            field(Tag::GPSAltitudeRef, Value::Byte(vec![1])),
+            field(Tag::GPSAltitude, Value::Rational(vec![rational(25, 2)])),
+            field(Tag::DateTimeOriginal, Value::Ascii(vec![b"2025:01:18 09:30:00".to_vec()])),
+        ];
+        let mut writer = Writer::new();
+        for f in &fields {
+            writer.push_field(f);
+        }
+        let mut buf = Cursor::new(Vec::new());
+        writer.write(&mut buf, false).unwrap();
+        let exif = ::exif::Reader::new().read_raw(buf.into_inner()).unwrap();
+
+        let metadata = metadata_from(&exif);
+        assert_eq!(metadata.orientation, 6);
+        assert_eq!(metadata.camera_make, "OLYMPUS");
+        assert_eq!(metadata.camera_model, "TG-6");
+        assert_eq!(metadata.software, "Version 1.0");
+        assert_eq!((metadata.width, metadata.height), (4000, 3000));
+        assert!((metadata.gps_latitude.unwrap() + 35.51).abs() < 1e-9);
+        assert!((metadata.gps_longitude.unwrap() - 139.75).abs() < 1e-9);
+        assert_eq!(metadata.gps_altitude, Some(-12.5));
+        assert!(date_from(&exif).unwrap().starts_with("2025-01-18"));
+    }
+
+    #[test]
+    fn test_extract_without_exif_uses_header_dimensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plain.png");
+        image_crate::RgbImage::new(40, 30).save(&path).unwrap();
+
+        let (date, metadata) = extract(&path);
+        assert_eq!(date, None);
+        assert_eq!((metadata.width, metadata.height), (40, 30));
+        assert_eq!(metadata.orientation, 0);
+        assert_eq!(metadata.gps(), None);
+        assert_eq!(read_orientation(&path), 0);
+    }
 }
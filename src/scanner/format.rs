@@ -0,0 +1,189 @@
+//! 画像形式の判定（マジックバイト）
+//!
+//! 拡張子ではなくファイル先頭のバイト列から形式を判定する。
+//! `image` クレートがデコードできる形式はすべて対象とし、
+//! HEIC/HEIF は別途 JPEG 変換（[`super::heic`]）で扱う。
+
+use ::image as image_crate;
+use image_crate::metadata::Orientation;
+use image_crate::{DynamicImage, ImageFormat};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// 判定に読み込む先頭バイト数
+const SNIFF_LEN: usize = 32;
+
+/// 画像らしい拡張子（小文字）。内容が画像でない場合の警告に使う
+const IMAGE_LIKE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "jpe", "jfif", "png", "gif", "webp", "tif", "tiff", "bmp",
+    "heic", "heif", "hif", "avif", "tga", "ico", "qoi", "pnm", "ppm", "pgm", "exr", "hdr",
+];
+
+/// HEIF コンテナの ftyp ブランド
+const HEIF_BRANDS: &[&[u8]] = &[b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1", b"msf1"];
+
+/// 判定結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedFormat {
+    /// `image` クレートが認識した形式
+    Image(ImageFormat),
+    /// HEIC/HEIF（iPhone等）
+    Heic,
+    /// 画像として認識できない
+    Unknown,
+}
+
+/// ファイル先頭のマジックバイトから形式を判定
+pub fn sniff_format(path: &Path) -> std::io::Result<DetectedFormat> {
+    let mut buf = [0u8; SNIFF_LEN];
+    let mut file = File::open(path)?;
+    let mut len = 0;
+    while len < SNIFF_LEN {
+        let n = file.read(&mut buf[len..])?;
+        if n == 0 {
+            break;
+        }
+        len += n;
+    }
+    Ok(sniff_bytes(&buf[..len]))
+}
+
+/// バイト列から形式を判定
+pub fn sniff_bytes(bytes: &[u8]) -> DetectedFormat {
+    if is_heif(bytes) {
+        return DetectedFormat::Heic;
+    }
+    match image_crate::guess_format(bytes) {
+        Ok(format) => DetectedFormat::Image(format),
+        Err(_) => DetectedFormat::Unknown,
+    }
+}
+
+/// ISO BMFF の ftyp ボックスが HEIF ブランドか
+fn is_heif(bytes: &[u8]) -> bool {
+    if bytes.len() < 12 || &bytes[4..8] != b"ftyp" {
+        return false;
+    }
+    HEIF_BRANDS.iter().any(|brand| &bytes[8..12] == *brand)
+}
+
+/// このビルドでデコード可能な形式か
+pub fn is_decodable(format: ImageFormat) -> bool {
+    format.reading_enabled()
+}
+
+/// AI解析・台帳出力にそのまま渡せる形式か（それ以外は JPEG に変換する）
+pub fn is_portable(format: ImageFormat) -> bool {
+    matches!(format, ImageFormat::Jpeg | ImageFormat::Png)
+}
+
+/// 拡張子が画像らしいか（大文字小文字を区別しない）
+pub fn has_image_like_extension(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| {
+            let ext = ext.to_string_lossy().to_lowercase();
+            IMAGE_LIKE_EXTENSIONS.contains(&ext.as_str())
+        })
+        .unwrap_or(false)
+}
+
+/// 形式の表示名
+pub fn format_name(format: ImageFormat) -> String {
+    format!("{:?}", format).to_uppercase()
+}
+
+/// EXIF Orientation（1〜8）に従って画像を正立させる（0・1・範囲外はそのまま）
+pub fn apply_orientation(mut img: DynamicImage, orientation: u16) -> DynamicImage {
+    if let Some(o) = u8::try_from(orientation).ok().and_then(Orientation::from_exif) {
+        img.apply_orientation(o);
+    }
+    img
+}
+
+/// 画像をデコードして JPEG として保存する
+///
+/// `orientation` に EXIF Orientation を渡すと正立させてから保存する（0 で回転なし）
+pub fn transcode_to_jpeg(src: &Path, dest: &Path, quality: u8, orientation: u16) -> Result<(), String> {
+    let img = image_crate::ImageReader::open(src)
+        .map_err(|e| e.to_string())?
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .decode()
+        .map_err(|e| e.to_string())?;
+    let img = apply_orientation(img, orientation);
+
+    let file = File::create(dest).map_err(|e| e.to_string())?;
+    let mut writer = std::io::BufWriter::new(file);
+    let encoder = image_crate::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, quality);
+    img.to_rgb8()
+        .write_with_encoder(encoder)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_jpeg_png() {
+        assert_eq!(sniff_bytes(&[0xFF, 0xD8, 0xFF, 0xE0]), DetectedFormat::Image(ImageFormat::Jpeg));
+        assert_eq!(
+            sniff_bytes(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            DetectedFormat::Image(ImageFormat::Png)
+        );
+    }
+
+    #[test]
+    fn test_sniff_webp_tiff() {
+        assert_eq!(sniff_bytes(b"RIFF\0\0\0\0WEBPVP8 "), DetectedFormat::Image(ImageFormat::WebP));
+        assert_eq!(sniff_bytes(b"II*\0\x08\0\0\0"), DetectedFormat::Image(ImageFormat::Tiff));
+        assert_eq!(sniff_bytes(b"MM\0*\0\0\0\x08"), DetectedFormat::Image(ImageFormat::Tiff));
+    }
+
+    #[test]
+    fn test_sniff_heic() {
+        assert_eq!(sniff_bytes(b"\0\0\0\x18ftypheic\0\0\0\0mif1heic"), DetectedFormat::Heic);
+        assert_eq!(sniff_bytes(b"\0\0\0\x18ftypmif1\0\0\0\0mif1heic"), DetectedFormat::Heic);
+        // MP4 は HEIC ではない
+        assert_ne!(sniff_bytes(b"\0\0\0\x18ftypisom\0\0\0\0isomiso2"), DetectedFormat::Heic);
+    }
+
+    #[test]
+    fn test_sniff_unknown() {
+        assert_eq!(sniff_bytes(b"dummy"), DetectedFormat::Unknown);
+        assert_eq!(sniff_bytes(b""), DetectedFormat::Unknown);
+    }
+
+    #[test]
+    fn test_has_image_like_extension_case_insensitive() {
+        assert!(has_image_like_extension(Path::new("a.Jpg")));
+        assert!(has_image_like_extension(Path::new("a.HEIC")));
+        assert!(has_image_like_extension(Path::new("a.tif")));
+        assert!(!has_image_like_extension(Path::new("a.txt")));
+        assert!(!has_image_like_extension(Path::new("noext")));
+    }
+
+    #[test]
+    fn test_apply_orientation() {
+        let img = DynamicImage::new_rgb8(40, 30);
+        assert_eq!(apply_orientation(img.clone(), 0).width(), 40);
+        assert_eq!(apply_orientation(img.clone(), 1).width(), 40);
+        assert_eq!(apply_orientation(img.clone(), 3).width(), 40);
+
+        // 6: 90°回転で縦横が入れ替わる
+        let rotated = apply_orientation(img.clone(), 6);
+        assert_eq!((rotated.width(), rotated.height()), (30, 40));
+        let rotated = apply_orientation(img, 8);
+        assert_eq!((rotated.width(), rotated.height()), (30, 40));
+    }
+
+    #[test]
+    fn test_decodable_formats() {
+        assert!(is_decodable(ImageFormat::Jpeg));
+        assert!(is_decodable(ImageFormat::WebP));
+        assert!(is_decodable(ImageFormat::Tiff));
+        assert!(is_portable(ImageFormat::Jpeg));
+        assert!(!is_portable(ImageFormat::Tiff));
+    }
+}
@@ -0,0 +1,107 @@
+//! HEIC/HEIF → JPEG 変換
+//!
+//! iPhone の HEIC 写真は `image` クレートでデコードできないため、
+//! 外部ツール（libheif の heif-convert / ImageMagick / macOS sips）で
+//! JPEG に変換してから解析・出力に使う。
+//! 変換結果はアプリのキャッシュフォルダ（`~/.cache/photo-ai/heic/` など）に保存し、再利用する。
+//! 写真フォルダには書き込まない。
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 変換済みJPEGの保存先
+pub fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("photo-ai")
+        .join("heic")
+}
+
+/// 変換済みJPEGのパス
+pub fn converted_path(src: &Path) -> PathBuf {
+    converted_path_in(&cache_dir(), src)
+}
+
+/// `cache` 内の変換済みJPEGのパス
+///
+/// 元ファイルのフルパス（拡張子を含む）のハッシュを名前に入れ、
+/// 別フォルダの同名ファイルや同じフォルダの `IMG.HEIC` と `IMG.heif` が重ならないようにする
+fn converted_path_in(cache: &Path, src: &Path) -> PathBuf {
+    let full = std::path::absolute(src).unwrap_or_else(|_| src.to_path_buf());
+    let digest = Sha256::digest(full.to_string_lossy().as_bytes());
+    let key: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    let stem = src
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "image".to_string());
+    cache.join(format!("{}_{}.jpg", stem, key))
+}
+
+/// HEIC を JPEG に変換し、変換後のパスを返す
+///
+/// 変換済みで元ファイルより新しければ再変換しない
+pub fn convert_to_jpeg(src: &Path) -> Result<PathBuf, String> {
+    let dest = converted_path(src);
+    if is_up_to_date(src, &dest) {
+        return Ok(dest);
+    }
+    if let Some(dir) = dest.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    let src_str = src.to_string_lossy().to_string();
+    let dest_str = dest.to_string_lossy().to_string();
+    let converters: [(&str, Vec<&str>); 3] = [
+        ("heif-convert", vec!["-q", "90", &src_str, &dest_str]),
+        ("magick", vec![&src_str, "-quality", "90", &dest_str]),
+        ("sips", vec!["-s", "format", "jpeg", &src_str, "--out", &dest_str]),
+    ];
+
+    let mut errors = Vec::new();
+    for (program, args) in &converters {
+        match Command::new(program).args(args).output() {
+            Ok(output) if output.status.success() && dest.exists() => return Ok(dest),
+            Ok(output) => errors.push(format!(
+                "{}: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+            // 未インストールは次のツールを試す
+            Err(_) => continue,
+        }
+    }
+
+    if errors.is_empty() {
+        Err("HEIC変換ツールが見つかりません（heif-convert / magick / sips）".to_string())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+fn is_up_to_date(src: &Path, dest: &Path) -> bool {
+    let modified = |p: &Path| p.metadata().and_then(|m| m.modified()).ok();
+    match (modified(src), modified(dest)) {
+        (Some(s), Some(d)) => d >= s,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_converted_path() {
+        let cache = Path::new("/cache/heic");
+        let path = converted_path_in(cache, Path::new("/photos/day1/IMG_0001.HEIC"));
+        assert_eq!(path.parent(), Some(cache));
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with("IMG_0001_") && name.ends_with(".jpg"), "{}", name);
+        assert_eq!(path, converted_path_in(cache, Path::new("/photos/day1/IMG_0001.HEIC")));
+
+        // 別フォルダの同名ファイル・拡張子違いは別のパス
+        assert_ne!(path, converted_path_in(cache, Path::new("/photos/day2/IMG_0001.HEIC")));
+        assert_ne!(path, converted_path_in(cache, Path::new("/photos/day1/IMG_0001.heif")));
+    }
+}
@@ -0,0 +1,332 @@
+//! `.photoaiignore` による除外ルール
+//!
+//! gitignore 形式のグロブでスキャン対象外のファイル・フォルダを指定する。
+//! スキャン対象フォルダ直下とサブフォルダに置け、深い階層のルールほど優先される。
+//!
+//! ```text
+//! # 非使用フォルダを除外
+//! 非使用/
+//! *_old.jpg
+//! /archive/**/*.png
+//! # 除外フォルダ内でも採用分は取り込む
+//! !非使用/採用/
+//! ```
+//!
+//! - `/` を含むパターンは ignore ファイルの場所からの相対パス、含まないものは名前で一致
+//! - 末尾 `/` はフォルダのみ、`!` は除外の取り消し（後勝ち）
+//! - git と異なり、除外したフォルダの中も `!` で取り込み直せる
+//! - 大文字小文字は区別しない
+
+use crate::error::{PhotoAiError, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// 除外ルールファイル名
+pub const IGNORE_FILE_NAME: &str = ".photoaiignore";
+
+/// 既定の除外ルール（フォルダ名の完全一致。`!不要/` 等で取り消せる）
+pub const DEFAULT_RULES: &[&str] = &["非使用/", "hisiyou/", "不要/", "excluded/"];
+
+/// 除外ルール1行
+#[derive(Debug, Clone)]
+pub struct IgnoreRule {
+    /// 元の記述
+    pub pattern: String,
+    /// 記述場所（`path:行番号` または `既定`）
+    pub source: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+    regex: Regex,
+}
+
+impl IgnoreRule {
+    /// 1行を解析（空行・コメントは `None`）
+    pub fn parse(line: &str, source: &str) -> Option<std::result::Result<Self, String>> {
+        let trimmed = line.trim_end_matches(['\r', '\n']).trim_end();
+        let trimmed = trimmed.trim_start_matches('\u{feff}');
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return None;
+        }
+
+        let (negated, body) = match trimmed.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('\\').unwrap_or(trimmed)),
+        };
+        let (dir_only, body) = match body.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, body),
+        };
+        let anchored = body.contains('/');
+        let body = body.trim_start_matches('/');
+        if body.is_empty() {
+            return None;
+        }
+
+        Some(
+            Regex::new(&glob_to_regex(body))
+                .map(|regex| Self {
+                    pattern: trimmed.to_string(),
+                    source: source.to_string(),
+                    negated,
+                    dir_only,
+                    anchored,
+                    regex,
+                })
+                .map_err(|e| format!("{}: {} ({})", source, trimmed, e)),
+        )
+    }
+
+    /// 除外の取り消し（`!`）か
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    /// ルールの基準フォルダからの相対パス（`/` 区切り）が一致するか
+    fn matches(&self, rel_path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let target = if self.anchored {
+            rel_path
+        } else {
+            rel_path.rsplit('/').next().unwrap_or(rel_path)
+        };
+        self.regex.is_match(target)
+    }
+}
+
+/// ルール一覧を解析
+pub fn parse_rules(content: &str, source: &str) -> std::result::Result<Vec<IgnoreRule>, String> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| IgnoreRule::parse(line, &format!("{}:{}", source, i + 1)))
+        .collect()
+}
+
+/// グロブを正規表現に変換
+fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("(?i)^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    re.push_str("(?:.*/)?");
+                } else {
+                    re.push_str(".*");
+                }
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            '[' => {
+                let class: String = chars.clone().take_while(|&c| c != ']').collect();
+                if chars.clone().nth(class.chars().count()) == Some(']') && !class.is_empty() {
+                    for _ in 0..=class.chars().count() {
+                        chars.next();
+                    }
+                    let class = class.strip_prefix('!').map(|r| format!("^{}", r)).unwrap_or(class);
+                    re.push('[');
+                    re.push_str(&class.replace('\\', "\\\\"));
+                    re.push(']');
+                } else {
+                    re.push_str("\\[");
+                }
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    re.push_str(&regex::escape(&next.to_string()));
+                }
+            }
+            _ => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+/// スキャン対象フォルダ配下の除外判定
+///
+/// 各フォルダの `.photoaiignore` は必要になった時点で読み込む
+#[derive(Debug)]
+pub struct IgnoreMatcher {
+    root: PathBuf,
+    defaults: Vec<IgnoreRule>,
+    /// フォルダ（root からの相対）→ そのフォルダの ignore ファイルのルール
+    loaded: HashMap<PathBuf, Vec<IgnoreRule>>,
+}
+
+impl IgnoreMatcher {
+    pub fn new(root: &Path) -> Self {
+        let defaults = DEFAULT_RULES
+            .iter()
+            .filter_map(|rule| IgnoreRule::parse(rule, "既定"))
+            .filter_map(|rule| rule.ok())
+            .collect();
+        Self {
+            root: root.to_path_buf(),
+            defaults,
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// 除外される場合、決め手になったルールを返す
+    pub fn excluded_by(&mut self, path: &Path) -> Result<Option<IgnoreRule>> {
+        let rel = path.strip_prefix(&self.root).unwrap_or(path);
+        let components: Vec<String> = rel
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        let is_dir = path.is_dir();
+
+        // 上位から順に判定し、一致したルールがなければ親の状態を引き継ぐ
+        let mut state: Option<IgnoreRule> = None;
+        for depth in 0..components.len() {
+            let entry_is_dir = depth + 1 < components.len() || is_dir;
+            let bases: Vec<PathBuf> = (0..=depth).map(|base| components[..base].iter().collect()).collect();
+            for dir in &bases {
+                self.load(dir)?;
+            }
+            let mut verdict: Option<&IgnoreRule> = None;
+
+            // 既定ルール → root の ignore → 各サブフォルダの ignore（深いほど優先）
+            for rule in &self.defaults {
+                if rule.matches(&components[..=depth].join("/"), entry_is_dir) {
+                    verdict = Some(rule);
+                }
+            }
+            for (base, dir) in bases.iter().enumerate() {
+                let rel_to_base = components[base..=depth].join("/");
+                for rule in &self.loaded[dir] {
+                    if rule.matches(&rel_to_base, entry_is_dir) {
+                        verdict = Some(rule);
+                    }
+                }
+            }
+
+            if let Some(rule) = verdict {
+                state = (!rule.negated).then(|| rule.clone());
+            }
+        }
+        Ok(state)
+    }
+
+    fn load(&mut self, dir: &Path) -> Result<()> {
+        if self.loaded.contains_key(dir) {
+            return Ok(());
+        }
+        let file = self.root.join(dir).join(IGNORE_FILE_NAME);
+        let rules = match std::fs::read_to_string(&file) {
+            Ok(content) => {
+                let source = dir.join(IGNORE_FILE_NAME).display().to_string();
+                parse_rules(&content, &source).map_err(PhotoAiError::Config)?
+            }
+            Err(_) => Vec::new(),
+        };
+        self.loaded.insert(dir.to_path_buf(), rules);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn rule(pattern: &str) -> IgnoreRule {
+        IgnoreRule::parse(pattern, "test").unwrap().unwrap()
+    }
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"").unwrap();
+    }
+
+    #[test]
+    fn test_parse_comments_and_flags() {
+        assert!(IgnoreRule::parse("", "t").is_none());
+        assert!(IgnoreRule::parse("# comment", "t").is_none());
+
+        let r = rule("!非使用/採用/");
+        assert!(r.is_negated());
+        assert!(r.dir_only);
+        assert!(r.anchored);
+
+        let r = rule("\\!literal.jpg");
+        assert!(!r.is_negated());
+        assert!(r.matches("!literal.jpg", false));
+    }
+
+    #[test]
+    fn test_glob_matching() {
+        assert!(rule("*.png").matches("a/b/photo.PNG", false));
+        assert!(!rule("*.png").matches("a/b/photo.jpg", false));
+        assert!(rule("IMG_00??.jpg").matches("IMG_0012.jpg", false));
+        assert!(rule("IMG_[0-4]*.jpg").matches("IMG_3.jpg", false));
+        assert!(!rule("IMG_[!0-4]*.jpg").matches("IMG_3.jpg", false));
+
+        // スラッシュを含むパターンは基準フォルダからの相対パス
+        assert!(rule("/archive/*.jpg").matches("archive/a.jpg", false));
+        assert!(!rule("/archive/*.jpg").matches("sub/archive/a.jpg", false));
+        assert!(rule("archive/**/*.jpg").matches("archive/x/y/a.jpg", false));
+        assert!(rule("**/tmp").matches("a/b/tmp", true));
+
+        // 末尾 / はフォルダのみ
+        assert!(rule("不要/").matches("不要", true));
+        assert!(!rule("不要/").matches("不要", false));
+    }
+
+    #[test]
+    fn test_default_rules_match_whole_names_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        touch(&root.join("非使用/a.jpg"));
+        touch(&root.join("不要土処理/b.jpg"));
+
+        let mut matcher = IgnoreMatcher::new(root);
+        let rule = matcher.excluded_by(&root.join("非使用/a.jpg")).unwrap().unwrap();
+        assert_eq!(rule.source, "既定");
+        assert!(matcher.excluded_by(&root.join("不要土処理/b.jpg")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_ignore_files_and_include_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join(IGNORE_FILE_NAME), "*_old.jpg\n!非使用/採用/\n").unwrap();
+        fs::create_dir_all(root.join("day1")).unwrap();
+        fs::write(root.join("day1").join(IGNORE_FILE_NAME), "!keep_old.jpg\nblur*.jpg\n").unwrap();
+        for p in ["a_old.jpg", "day1/keep_old.jpg", "day1/blur1.jpg", "blur2.jpg", "非使用/採用/c.jpg", "非使用/d.jpg"] {
+            touch(&root.join(p));
+        }
+
+        let mut matcher = IgnoreMatcher::new(root);
+        let mut excluded = |p: &str| matcher.excluded_by(&root.join(p)).unwrap().is_some();
+        assert!(excluded("a_old.jpg"));
+        // サブフォルダのルールが優先
+        assert!(!excluded("day1/keep_old.jpg"));
+        assert!(excluded("day1/blur1.jpg"));
+        // サブフォルダのルールは他のフォルダに影響しない
+        assert!(!excluded("blur2.jpg"));
+        // 除外フォルダ内の取り込み直し
+        assert!(!excluded("非使用/採用/c.jpg"));
+        assert!(excluded("非使用/d.jpg"));
+    }
+
+    #[test]
+    fn test_invalid_ignore_file_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(IGNORE_FILE_NAME), "ok.jpg\n[z-a].jpg\n").unwrap();
+        touch(&dir.path().join("a.jpg"));
+        let mut matcher = IgnoreMatcher::new(dir.path());
+        let err = matcher.excluded_by(&dir.path().join("a.jpg")).unwrap_err();
+        assert!(err.to_string().contains(":2"));
+    }
+}
@@ -1,20 +1,126 @@
-mod exif;
+pub mod dedupe;
+pub mod exif;
+pub mod format;
+pub mod heic;
+pub mod ignore;
+pub mod path_template;
+pub mod phash;
+
+pub use format::DetectedFormat;
+pub use path_template::PathTemplate;
+pub use phash::ImageFingerprint;
 
 use crate::error::{PhotoAiError, Result};
+use ::image::ImageFormat;
+use photo_ai_common::{ExifMetadata, FolderHints};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ImageInfo {
     pub path: PathBuf,
     pub file_name: String,
     pub date: Option<String>,
+    /// 撮影メタデータ（向き・GPS・カメラ・画素数）
+    pub exif: ExifMetadata,
+    /// フォルダ構成から得た既知情報（`path_template` 指定時）
+    pub hints: FolderHints,
+    /// 知覚ハッシュ・画質スコア（類似写真検出時に計算）
+    pub fingerprint: Option<ImageFingerprint>,
+    /// マジックバイトで判定した形式（`path` の実体の形式）
+    pub format: Option<ImageFormat>,
+    /// 変換元ファイル（HEIC を JPEG に変換した場合）
+    pub original_path: Option<PathBuf>,
+}
+
+impl ImageInfo {
+    /// 写真フォルダ内の元ファイル（HEIC は変換前のファイル。移動・フォルダ順に使う）
+    pub fn source_path(&self) -> &Path {
+        self.original_path.as_deref().unwrap_or(&self.path)
+    }
+
+    /// AI解析・台帳出力の前に JPEG 変換が必要か（TIFF/WebP/BMP 等）
+    pub fn needs_jpeg_conversion(&self) -> bool {
+        self.format.map(|f| !format::is_portable(f)).unwrap_or(false)
+    }
+}
+
+/// スキャンオプション
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// サブフォルダも再帰的にスキャン
+    pub recursive: bool,
+    /// 除外ルール（既定の非使用フォルダ等と `.photoaiignore`）を適用
+    pub exclude: bool,
+    /// HEIC/HEIF を JPEG に変換して取り込む
+    pub convert_heic: bool,
+    /// サブフォルダ名から日付・工種・測点等を読み取るテンプレート
+    pub path_template: Option<PathTemplate>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            recursive: false,
+            exclude: true,
+            convert_heic: false,
+            path_template: None,
+        }
+    }
 }
 
-const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "JPG", "JPEG", "PNG"];
+/// スキップしたファイル
+#[derive(Debug, Clone)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+}
 
-/// 除外フォルダパターン（デフォルトで除外）
-const EXCLUDE_PATTERNS: &[&str] = &["非使用", "hisiyou", "不要", "excluded"];
+/// スキップ理由
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkipReason {
+    /// 画像形式だがこのビルドではデコードできない
+    UnsupportedFormat(String),
+    /// HEIC（変換が無効）
+    HeicNotEnabled,
+    /// HEIC の JPEG 変換に失敗
+    HeicConversionFailed(String),
+    /// 画像の拡張子だが内容を画像として認識できない
+    NotAnImage,
+    /// ファイル読み込みエラー
+    ReadError(String),
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::UnsupportedFormat(name) => write!(f, "未対応の画像形式 ({})", name),
+            SkipReason::HeicNotEnabled => write!(f, "HEIC形式（--heic でJPEG変換して取り込み可能）"),
+            SkipReason::HeicConversionFailed(e) => write!(f, "HEIC変換失敗: {}", e),
+            SkipReason::NotAnImage => write!(f, "画像として認識できない（拡張子と内容が不一致）"),
+            SkipReason::ReadError(e) => write!(f, "読み込みエラー: {}", e),
+        }
+    }
+}
+
+/// 除外ルールで対象外にしたファイル
+#[derive(Debug, Clone)]
+pub struct ExcludedFile {
+    pub path: PathBuf,
+    /// 決め手になったルール
+    pub rule: ignore::IgnoreRule,
+}
+
+/// スキャン結果
+#[derive(Debug, Clone, Default)]
+pub struct ScanReport {
+    /// 取り込んだ画像（ファイル名順）
+    pub images: Vec<ImageInfo>,
+    /// スキップしたファイル
+    pub skipped: Vec<SkippedFile>,
+    /// 除外ルールで対象外にした画像ファイル
+    pub excluded: Vec<ExcludedFile>,
+}
 
 pub fn scan_folder(folder: &Path) -> Result<Vec<ImageInfo>> {
     scan_folder_full(folder, false, true)
@@ -28,73 +134,130 @@ pub fn scan_folder_with_options(folder: &Path, recursive: bool) -> Result<Vec<Im
     scan_folder_full(folder, recursive, true)
 }
 
-/// フォルダが除外パターンに一致するか
-fn is_excluded_folder(path: &Path) -> bool {
-    path.components().any(|c| {
-        if let std::path::Component::Normal(name) = c {
-            let name_str = name.to_string_lossy().to_lowercase();
-            EXCLUDE_PATTERNS.iter().any(|p| name_str.contains(&p.to_lowercase()))
-        } else {
-            false
-        }
-    })
+pub fn scan_folder_full(folder: &Path, recursive: bool, exclude: bool) -> Result<Vec<ImageInfo>> {
+    let options = ScanOptions {
+        recursive,
+        exclude,
+        ..Default::default()
+    };
+    scan(folder, &options).map(|report| report.images)
 }
 
-pub fn scan_folder_full(folder: &Path, recursive: bool, exclude: bool) -> Result<Vec<ImageInfo>> {
+/// フォルダをスキャンし、取り込んだ画像とスキップしたファイルを返す
+///
+/// 形式は拡張子ではなくマジックバイトで判定する
+pub fn scan(folder: &Path, options: &ScanOptions) -> Result<ScanReport> {
     if !folder.exists() {
         return Err(PhotoAiError::FolderNotFound(folder.display().to_string()));
     }
 
-    let mut images = Vec::new();
+    let mut report = ScanReport::default();
+    let mut ignore = options.exclude.then(|| ignore::IgnoreMatcher::new(folder));
 
-    let walker = if recursive {
+    let walker = if options.recursive {
         WalkDir::new(folder)
     } else {
         WalkDir::new(folder).max_depth(1)
     };
 
-    for entry in walker.into_iter().filter_map(|e| e.ok())
-    {
+    for entry in walker.into_iter().filter_map(|e| e.ok()) {
         let path = entry.path();
 
         if !path.is_file() {
             continue;
         }
 
-        // 除外フォルダチェック
-        if exclude && is_excluded_folder(path) {
-            continue;
+        // 除外ルールチェック
+        if let Some(matcher) = ignore.as_mut() {
+            if let Some(rule) = matcher.excluded_by(path)? {
+                if format::has_image_like_extension(path) {
+                    report.excluded.push(ExcludedFile { path: path.to_path_buf(), rule });
+                }
+                continue;
+            }
         }
 
-        if let Some(ext) = path.extension() {
-            let ext_str = ext.to_string_lossy();
-            if IMAGE_EXTENSIONS.iter().any(|&e| e == ext_str) {
-                let file_name = path
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_default();
-
-                let date = exif::extract_date(path).ok();
-
-                images.push(ImageInfo {
-                    path: path.to_path_buf(),
-                    file_name,
-                    date,
-                });
+        match inspect_file(path, options) {
+            Ok(Some(mut image)) => {
+                if let Some(template) = &options.path_template {
+                    image.hints = template.extract(&relative_dir(folder, path));
+                }
+                report.images.push(image);
             }
+            Ok(None) => {}
+            Err(reason) => report.skipped.push(SkippedFile {
+                path: path.to_path_buf(),
+                reason,
+            }),
         }
     }
 
-    // ファイル名でソート
-    images.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    // ファイル名の自然順でソート（IMG_9 → IMG_10）
+    report.images.sort_by(|a, b| crate::ordering::natural_cmp(&a.file_name, &b.file_name));
 
-    Ok(images)
+    Ok(report)
 }
 
-/// Check if a file extension is a supported image format
-#[cfg(test)]
-fn is_image_extension(ext: &str) -> bool {
-    IMAGE_EXTENSIONS.contains(&ext)
+/// スキャン対象フォルダから見たファイルの親フォルダ（`/` 区切り）
+fn relative_dir(root: &Path, file: &Path) -> String {
+    file.parent()
+        .and_then(|dir| dir.strip_prefix(root).ok())
+        .map(|rel| {
+            rel.components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .unwrap_or_default()
+}
+
+/// 1ファイルを判定して ImageInfo を作る
+///
+/// 画像でないファイル（拡張子も画像らしくない）は `Ok(None)` で黙ってスキップする
+fn inspect_file(path: &Path, options: &ScanOptions) -> std::result::Result<Option<ImageInfo>, SkipReason> {
+    let detected = format::sniff_format(path).map_err(|e| SkipReason::ReadError(e.to_string()))?;
+
+    let file_name = |p: &Path| {
+        p.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    match detected {
+        DetectedFormat::Image(fmt) if format::is_decodable(fmt) => {
+            let (date, exif) = exif::extract(path);
+            Ok(Some(ImageInfo {
+                path: path.to_path_buf(),
+                file_name: file_name(path),
+                date,
+                exif,
+                format: Some(fmt),
+                ..Default::default()
+            }))
+        }
+        DetectedFormat::Image(fmt) => Err(SkipReason::UnsupportedFormat(format::format_name(fmt))),
+        DetectedFormat::Heic if !options.convert_heic => Err(SkipReason::HeicNotEnabled),
+        DetectedFormat::Heic => {
+            let converted = heic::convert_to_jpeg(path).map_err(SkipReason::HeicConversionFailed)?;
+            // 日時・GPS・カメラは変換元から、向きと画素数は実際に出力する変換後JPEGから読む
+            let (date, mut exif) = exif::extract(path);
+            let (_, converted_exif) = exif::extract(&converted);
+            exif.orientation = converted_exif.orientation;
+            exif.width = converted_exif.width;
+            exif.height = converted_exif.height;
+            Ok(Some(ImageInfo {
+                file_name: file_name(path),
+                date,
+                exif,
+                path: converted,
+                format: Some(ImageFormat::Jpeg),
+                original_path: Some(path.to_path_buf()),
+                ..Default::default()
+            }))
+        }
+        DetectedFormat::Unknown if format::has_image_like_extension(path) => Err(SkipReason::NotAnImage),
+        DetectedFormat::Unknown => Ok(None),
+    }
 }
 
 #[cfg(test)]
@@ -122,17 +285,20 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_is_image_extension() {
-        assert!(is_image_extension("jpg"));
-        assert!(is_image_extension("JPG"));
-        assert!(is_image_extension("jpeg"));
-        assert!(is_image_extension("png"));
-        assert!(!is_image_extension("txt"));
-        assert!(!is_image_extension("pdf"));
-        assert!(!is_image_extension("gif"));
+    /// Cleanup helper for a single file outside the temp directory
+    struct TempFileGuard(PathBuf);
+
+    impl Drop for TempFileGuard {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
     }
 
+    const JPEG_HEADER: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F'];
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const WEBP_HEADER: &[u8] = b"RIFF\0\0\0\0WEBPVP8 ";
+    const HEIC_HEADER: &[u8] = b"\0\0\0\x18ftypheic\0\0\0\0mif1heic";
+
     #[test]
     fn test_scan_folder_not_found() {
         let result = scan_folder(Path::new("/nonexistent/folder/that/does/not/exist"));
@@ -155,10 +321,10 @@ mod tests {
         let _guard = TempDirGuard(temp_dir.clone());
         fs::create_dir_all(&temp_dir).unwrap();
 
-        // Create dummy image files
-        File::create(temp_dir.join("test1.jpg")).unwrap().write_all(b"dummy").unwrap();
-        File::create(temp_dir.join("test2.JPG")).unwrap().write_all(b"dummy").unwrap();
-        File::create(temp_dir.join("test3.png")).unwrap().write_all(b"dummy").unwrap();
+        // Create image files (format is sniffed from content)
+        File::create(temp_dir.join("test1.jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
+        File::create(temp_dir.join("test2.JPG")).unwrap().write_all(JPEG_HEADER).unwrap();
+        File::create(temp_dir.join("test3.png")).unwrap().write_all(PNG_HEADER).unwrap();
         File::create(temp_dir.join("readme.txt")).unwrap().write_all(b"text").unwrap();
 
         let result = scan_folder(&temp_dir).unwrap();
@@ -174,13 +340,136 @@ mod tests {
         let _guard = TempDirGuard(temp_dir.clone());
         fs::create_dir_all(&temp_dir).unwrap();
 
-        File::create(temp_dir.join("c.jpg")).unwrap();
-        File::create(temp_dir.join("a.jpg")).unwrap();
-        File::create(temp_dir.join("b.jpg")).unwrap();
+        File::create(temp_dir.join("c.jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
+        File::create(temp_dir.join("a.jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
+        File::create(temp_dir.join("b.jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
 
         let result = scan_folder(&temp_dir).unwrap();
         assert_eq!(result[0].file_name, "a.jpg");
         assert_eq!(result[1].file_name, "b.jpg");
         assert_eq!(result[2].file_name, "c.jpg");
+
+        File::create(temp_dir.join("a10.jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
+        File::create(temp_dir.join("a9.jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
+        let result = scan_folder(&temp_dir).unwrap();
+        let names: Vec<_> = result.iter().map(|i| i.file_name.as_str()).collect();
+        assert_eq!(names, vec!["a.jpg", "a9.jpg", "a10.jpg", "b.jpg", "c.jpg"]);
+    }
+
+    #[test]
+    fn test_scan_sniffs_content_not_extension() {
+        let temp_dir = unique_temp_dir("sniff");
+        let _guard = TempDirGuard(temp_dir.clone());
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        File::create(temp_dir.join("a.Jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
+        File::create(temp_dir.join("b.webp")).unwrap().write_all(WEBP_HEADER).unwrap();
+        // 拡張子なしでも内容がPNGなら取り込む
+        File::create(temp_dir.join("c")).unwrap().write_all(PNG_HEADER).unwrap();
+        // 拡張子はJPEGだが中身は画像でない
+        File::create(temp_dir.join("broken.jpg")).unwrap().write_all(b"dummy").unwrap();
+        File::create(temp_dir.join("notes.txt")).unwrap().write_all(b"text").unwrap();
+
+        let report = scan(&temp_dir, &ScanOptions::default()).unwrap();
+        let names: Vec<_> = report.images.iter().map(|i| i.file_name.as_str()).collect();
+        assert_eq!(names, vec!["a.Jpg", "b.webp", "c"]);
+        assert_eq!(report.images[1].format, Some(ImageFormat::WebP));
+
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.skipped[0].path.ends_with("broken.jpg"));
+        assert_eq!(report.skipped[0].reason, SkipReason::NotAnImage);
+    }
+
+    #[test]
+    fn test_scan_reports_heic_when_disabled() {
+        let temp_dir = unique_temp_dir("heic");
+        let _guard = TempDirGuard(temp_dir.clone());
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        File::create(temp_dir.join("IMG_0001.HEIC")).unwrap().write_all(HEIC_HEADER).unwrap();
+
+        let report = scan(&temp_dir, &ScanOptions::default()).unwrap();
+        assert!(report.images.is_empty());
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].reason, SkipReason::HeicNotEnabled);
+    }
+
+    #[test]
+    fn test_scan_heic_keeps_original_name_and_moves_original() {
+        let temp_dir = unique_temp_dir("heic-convert");
+        let _guard = TempDirGuard(temp_dir.clone());
+        fs::create_dir_all(&temp_dir).unwrap();
+        let original = temp_dir.join("IMG_0002.HEIC");
+        File::create(temp_dir.join("IMG_0001.jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
+        File::create(&original).unwrap().write_all(HEIC_HEADER).unwrap();
+
+        // 変換済みJPEGをキャッシュに置き、変換ツールなしで取り込む
+        let converted = heic::converted_path(&original);
+        fs::create_dir_all(converted.parent().unwrap()).unwrap();
+        File::create(&converted).unwrap().write_all(JPEG_HEADER).unwrap();
+        let _cache_guard = TempFileGuard(converted.clone());
+
+        let options = ScanOptions { convert_heic: true, ..Default::default() };
+        let report = scan(&temp_dir, &options).unwrap();
+        let names: Vec<&str> = report.images.iter().map(|i| i.file_name.as_str()).collect();
+        assert_eq!(names, vec!["IMG_0001.jpg", "IMG_0002.HEIC"]);
+        let heic_image = &report.images[1];
+        assert_eq!(heic_image.path, converted);
+        assert_eq!(heic_image.source_path(), original.as_path());
+
+        // 重複として除外するのは写真フォルダの HEIC（キャッシュは残す）
+        let clusters = vec![dedupe::DuplicateCluster { members: vec![0, 1], best: 0 }];
+        let moved = dedupe::move_duplicates(&report.images, &clusters, "重複").unwrap();
+        assert_eq!(moved, vec![temp_dir.join("重複").join("IMG_0002.HEIC")]);
+        assert!(!original.exists());
+        assert!(converted.exists());
+    }
+
+    #[test]
+    fn test_scan_applies_ignore_rules() {
+        let temp_dir = unique_temp_dir("ignore");
+        let _guard = TempDirGuard(temp_dir.clone());
+        for dir in ["非使用", "不要土処理", "day1"] {
+            fs::create_dir_all(temp_dir.join(dir)).unwrap();
+        }
+        for file in ["a.jpg", "非使用/b.jpg", "不要土処理/c.jpg", "day1/d_old.jpg", "day1/e.jpg"] {
+            File::create(temp_dir.join(file)).unwrap().write_all(JPEG_HEADER).unwrap();
+        }
+        fs::write(temp_dir.join(ignore::IGNORE_FILE_NAME), "*_old.jpg\n").unwrap();
+
+        let options = ScanOptions { recursive: true, ..Default::default() };
+        let report = scan(&temp_dir, &options).unwrap();
+        let names: Vec<_> = report.images.iter().map(|i| i.file_name.as_str()).collect();
+        // 「不要土処理」は既定ルール（不要/）に一致しない
+        assert_eq!(names, vec!["a.jpg", "c.jpg", "e.jpg"]);
+        assert_eq!(report.excluded.len(), 2);
+        assert!(report.excluded.iter().any(|e| e.path.ends_with("b.jpg") && e.rule.source == "既定"));
+
+        // --include-all 相当
+        let options = ScanOptions { recursive: true, exclude: false, ..Default::default() };
+        let report = scan(&temp_dir, &options).unwrap();
+        assert_eq!(report.images.len(), 5);
+        assert!(report.excluded.is_empty());
+    }
+
+    #[test]
+    fn test_scan_extracts_folder_hints() {
+        let temp_dir = unique_temp_dir("hints");
+        let _guard = TempDirGuard(temp_dir.clone());
+        let sub = temp_dir.join("2025-01-18").join("舗装工").join("No.5");
+        fs::create_dir_all(&sub).unwrap();
+        File::create(sub.join("a.jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
+        File::create(temp_dir.join("b.jpg")).unwrap().write_all(JPEG_HEADER).unwrap();
+
+        let options = ScanOptions {
+            recursive: true,
+            path_template: Some(PathTemplate::parse("{date}/{work_type}/{station}").unwrap()),
+            ..Default::default()
+        };
+        let report = scan(&temp_dir, &options).unwrap();
+        assert_eq!(report.images[0].file_name, "a.jpg");
+        assert_eq!(report.images[0].hints.work_type, "舗装工");
+        assert_eq!(report.images[0].hints.station, "No.5");
+        assert!(report.images[1].hints.is_empty());
     }
 }
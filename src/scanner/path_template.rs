@@ -0,0 +1,143 @@
+//! フォルダ構成テンプレート
+//!
+//! 再帰スキャン時、サブフォルダ名から日付・工種・測点等を読み取る。
+//!
+//! - テンプレート: `{date}/{work_type}/{station}/`（`{*}` は任意の1階層、`{date}_{station}` のような混在も可）
+//! - 正規表現: `regex:^(?P<date>\d{8})_(?P<work_type>[^/]+)`（名前付きキャプチャ）
+//!
+//! パスはスキャン対象フォルダからの相対（`/` 区切り）で照合する。
+//! テンプレートより深い階層があっても一致する。
+
+use crate::error::{PhotoAiError, Result};
+use photo_ai_common::FolderHints;
+use regex::Regex;
+
+/// 使用できるフィールド名
+pub const FIELDS: &[&str] = &["date", "work_type", "variety", "subphase", "station"];
+
+/// 正規表現指定の接頭辞
+const REGEX_PREFIX: &str = "regex:";
+
+/// フォルダ構成テンプレート
+#[derive(Debug, Clone)]
+pub struct PathTemplate {
+    regex: Regex,
+}
+
+impl PathTemplate {
+    /// テンプレート文字列（または `regex:` 付きの正規表現）を解析
+    pub fn parse(spec: &str) -> Result<Self> {
+        let pattern = match spec.strip_prefix(REGEX_PREFIX) {
+            Some(re) => re.to_string(),
+            None => template_to_regex(spec)?,
+        };
+        let regex = Regex::new(&pattern)
+            .map_err(|e| PhotoAiError::Config(format!("フォルダテンプレートが不正: {} ({})", spec, e)))?;
+
+        if let Some(name) = regex.capture_names().flatten().find(|n| !FIELDS.contains(n)) {
+            return Err(PhotoAiError::Config(format!(
+                "フォルダテンプレートの項目名が不正: {}（{} が使用可能）",
+                name,
+                FIELDS.join(", ")
+            )));
+        }
+        Ok(Self { regex })
+    }
+
+    /// 相対フォルダパスからヒントを取り出す（一致しなければ空）
+    pub fn extract(&self, rel_dir: &str) -> FolderHints {
+        let rel_dir = rel_dir.replace('\\', "/");
+        let Some(caps) = self.regex.captures(rel_dir.trim_matches('/')) else {
+            return FolderHints::default();
+        };
+        let get = |name: &str| {
+            caps.name(name)
+                .map(|m| m.as_str().trim().to_string())
+                .unwrap_or_default()
+        };
+        FolderHints {
+            date: normalize_date(&get("date")),
+            work_type: get("work_type"),
+            variety: get("variety"),
+            subphase: get("subphase"),
+            station: get("station"),
+        }
+    }
+}
+
+/// `{name}` 形式のテンプレートを正規表現に変換
+fn template_to_regex(template: &str) -> Result<String> {
+    let mut re = String::from("^");
+    let mut rest = template.trim_matches('/');
+    while let Some(start) = rest.find('{') {
+        re.push_str(&regex::escape(&rest[..start]));
+        let end = rest[start..].find('}').map(|i| start + i).ok_or_else(|| {
+            PhotoAiError::Config(format!("フォルダテンプレートの {{ が閉じていません: {}", template))
+        })?;
+        let name = rest[start + 1..end].trim();
+        if name == "*" || name == "_" {
+            re.push_str("[^/]+?");
+        } else if FIELDS.contains(&name) {
+            re.push_str(&format!("(?P<{}>[^/]+?)", name));
+        } else {
+            return Err(PhotoAiError::Config(format!(
+                "フォルダテンプレートの項目名が不正: {}（{} が使用可能）",
+                name,
+                FIELDS.join(", ")
+            )));
+        }
+        rest = &rest[end + 1..];
+    }
+    re.push_str(&regex::escape(rest));
+    re.push_str("(?:/|$)");
+    Ok(re)
+}
+
+/// フォルダ名の日付を `YYYY-MM-DD` に揃える（解釈できなければそのまま）
+fn normalize_date(s: &str) -> String {
+    crate::normalizer::dates::normalize_date(s).unwrap_or_else(|| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_extract() {
+        let t = PathTemplate::parse("{date}/{work_type}/{station}/").unwrap();
+        let hints = t.extract("2025-01-18/舗装工/No.5");
+        assert_eq!(hints.date, "2025-01-18");
+        assert_eq!(hints.work_type, "舗装工");
+        assert_eq!(hints.station, "No.5");
+
+        // 深い階層があっても一致、浅すぎれば空
+        assert_eq!(t.extract("2025-01-18/舗装工/No.5/予備").station, "No.5");
+        assert!(t.extract("2025-01-18/舗装工").is_empty());
+        assert!(t.extract("").is_empty());
+    }
+
+    #[test]
+    fn test_template_mixed_segment_and_wildcard() {
+        let t = PathTemplate::parse("{*}/{date}_{station}").unwrap();
+        let hints = t.extract("工区A\\20250118_No.12+5");
+        assert_eq!(hints.date, "2025-01-18");
+        assert_eq!(hints.station, "No.12+5");
+        assert_eq!(hints.work_type, "");
+    }
+
+    #[test]
+    fn test_regex_template() {
+        let t = PathTemplate::parse(r"regex:^(?P<date>\d{4}年\d{1,2}月\d{1,2}日)/(?P<work_type>[^/]+)").unwrap();
+        let hints = t.extract("2025年1月8日/区画線工/other");
+        assert_eq!(hints.date, "2025-01-08");
+        assert_eq!(hints.work_type, "区画線工");
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(PathTemplate::parse("{date}/{unknown}").is_err());
+        assert!(PathTemplate::parse("{date/{station}").is_err());
+        assert!(PathTemplate::parse("regex:(?P<foo>.*)").is_err());
+        assert!(PathTemplate::parse("regex:(").is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! 知覚ハッシュ（dHash）と画質スコア
+//!
+//! 連写・撮り直しの類似写真を検出するため、縮小グレースケール画像から
+//! 64bitの差分ハッシュを計算する。同時にベストショット選定用の
+//! シャープネス（ラプラシアン分散）と露出スコアも求める。
+
+use crate::error::{PhotoAiError, Result};
+use ::image as image_crate;
+use image_crate::imageops::FilterType;
+use image_crate::GrayImage;
+use std::path::Path;
+
+/// 画質評価に使う縮小サイズ（長辺px）
+const QUALITY_SAMPLE_SIZE: u32 = 512;
+
+/// 画像の指紋（知覚ハッシュ + 画質スコア）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageFingerprint {
+    /// 差分ハッシュ（dHash, 64bit）
+    pub phash: u64,
+    /// シャープネス（ラプラシアン分散、大きいほど鮮明）
+    pub sharpness: f64,
+    /// 平均輝度（0.0〜1.0）
+    pub brightness: f64,
+}
+
+impl ImageFingerprint {
+    /// 露出スコア（0.0〜1.0、中間輝度で最大）
+    pub fn exposure_score(&self) -> f64 {
+        (1.0 - (self.brightness - 0.5).abs() * 2.0).clamp(0.0, 1.0)
+    }
+
+    /// ベストショット選定用の総合スコア
+    ///
+    /// シャープネスを主とし、露出が極端な写真を減点する
+    pub fn quality_score(&self) -> f64 {
+        self.sharpness * (0.5 + 0.5 * self.exposure_score())
+    }
+}
+
+/// 画像ファイルから指紋を計算
+pub fn compute_fingerprint(path: &Path) -> Result<ImageFingerprint> {
+    let img = image_crate::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| PhotoAiError::ImageLoad(format!("{}: {}", path.display(), e)))?
+        .decode()
+        .map_err(|e| PhotoAiError::ImageLoad(format!("{}: {}", path.display(), e)))?;
+    let gray = img
+        .resize(QUALITY_SAMPLE_SIZE, QUALITY_SAMPLE_SIZE, FilterType::Triangle)
+        .to_luma8();
+
+    Ok(ImageFingerprint {
+        phash: dhash(&gray),
+        sharpness: laplacian_variance(&gray),
+        brightness: mean_brightness(&gray),
+    })
+}
+
+/// 差分ハッシュ（9x8に縮小し、横方向の隣接画素の大小を64bitに詰める）
+pub fn dhash(gray: &GrayImage) -> u64 {
+    let small = image_crate::imageops::resize(gray, 9, 8, FilterType::Triangle);
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash <<= 1;
+            if left > right {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// ハミング距離（異なるビット数）
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// ラプラシアン分散（ピントが合っているほど大きい）
+pub fn laplacian_variance(gray: &GrayImage) -> f64 {
+    let (w, h) = gray.dimensions();
+    if w < 3 || h < 3 {
+        return 0.0;
+    }
+
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    let mut count = 0.0;
+
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let center = gray.get_pixel(x, y)[0] as f64;
+            let lap = gray.get_pixel(x - 1, y)[0] as f64
+                + gray.get_pixel(x + 1, y)[0] as f64
+                + gray.get_pixel(x, y - 1)[0] as f64
+                + gray.get_pixel(x, y + 1)[0] as f64
+                - 4.0 * center;
+            sum += lap;
+            sum_sq += lap * lap;
+            count += 1.0;
+        }
+    }
+
+    let mean = sum / count;
+    sum_sq / count - mean * mean
+}
+
+/// 平均輝度（0.0〜1.0）
+pub fn mean_brightness(gray: &GrayImage) -> f64 {
+    let pixels = gray.as_raw();
+    if pixels.is_empty() {
+        return 0.0;
+    }
+    let total: u64 = pixels.iter().map(|&p| p as u64).sum();
+    total as f64 / pixels.len() as f64 / 255.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image_crate::Luma;
+
+    fn gradient(w: u32, h: u32) -> GrayImage {
+        GrayImage::from_fn(w, h, |x, _| Luma([(x * 255 / w.max(1)) as u8]))
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+        assert_eq!(hamming_distance(u64::MAX, 0), 64);
+    }
+
+    #[test]
+    fn test_dhash_similar_images() {
+        let a = gradient(64, 48);
+        let mut b = gradient(64, 48);
+        // わずかなノイズ
+        b.put_pixel(10, 10, Luma([0]));
+        assert!(hamming_distance(dhash(&a), dhash(&b)) <= 2);
+
+        let c = GrayImage::from_fn(64, 48, |x, _| Luma([255 - (x * 255 / 64) as u8]));
+        assert!(hamming_distance(dhash(&a), dhash(&c)) > 32);
+    }
+
+    #[test]
+    fn test_laplacian_variance_sharp_vs_flat() {
+        let flat = GrayImage::from_pixel(32, 32, Luma([128]));
+        let checker = GrayImage::from_fn(32, 32, |x, y| Luma([if (x + y) % 2 == 0 { 0 } else { 255 }]));
+        assert_eq!(laplacian_variance(&flat), 0.0);
+        assert!(laplacian_variance(&checker) > 1000.0);
+    }
+
+    #[test]
+    fn test_exposure_score() {
+        let mid = ImageFingerprint { phash: 0, sharpness: 100.0, brightness: 0.5 };
+        let dark = ImageFingerprint { phash: 0, sharpness: 100.0, brightness: 0.05 };
+        assert!((mid.exposure_score() - 1.0).abs() < 1e-9);
+        assert!(dark.exposure_score() < 0.2);
+        assert!(mid.quality_score() > dark.quality_score());
+    }
+}
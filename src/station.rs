@@ -3,10 +3,12 @@
 //! ## 変更履歴
 //! - 2026-01-18: 初期作成（Epic #21, Task #24-29）
 
+use crate::alignment::{assign_stations, Alignment};
 use crate::analyzer::AnalysisResult;
 use crate::error::{PhotoAiError, Result};
 use dialoguer::Input;
-use std::collections::HashSet;
+use photo_ai_common::StationRange;
+use std::collections::BTreeSet;
 use std::path::Path;
 
 /// 測点が空の写真を抽出
@@ -20,20 +22,21 @@ pub fn extract_empty_station_photos(results: &[AnalysisResult]) -> Vec<usize> {
 }
 
 /// 既存の測点リストを収集（重複除去）
+///
+/// 解釈できる測点は `No.10+5` の表記に揃えて距離順、解釈できないものはその後に出現順
 pub fn collect_existing_stations(results: &[AnalysisResult]) -> Vec<String> {
-    let mut seen = HashSet::new();
-    results
-        .iter()
-        .filter(|r| !r.station.trim().is_empty())
-        .filter_map(|r| {
-            let s = r.station.trim().to_string();
-            if seen.insert(s.clone()) {
-                Some(s)
-            } else {
-                None
+    let mut stations = BTreeSet::new();
+    let mut others: Vec<String> = Vec::new();
+    for station in results.iter().map(|r| r.station.trim()).filter(|s| !s.is_empty()) {
+        match StationRange::parse(station) {
+            Some(range) => {
+                stations.insert(range);
             }
-        })
-        .collect()
+            None if !others.iter().any(|s| s == station) => others.push(station.to_string()),
+            None => {}
+        }
+    }
+    stations.iter().map(StationRange::to_string).chain(others).collect()
 }
 
 /// 対話アクション
@@ -53,14 +56,27 @@ pub enum StationAction {
 }
 
 /// 対話式で測点を入力
+///
+/// `alignment` を指定すると、GPSのある写真は先に道路中心線から測点を推定し、
+/// 残った写真（GPSなし・中心線から離れすぎ）だけを対話で入力する
 pub fn run_interactive_station(
     input_path: &Path,
     output_path: Option<&Path>,
+    alignment: Option<(&Alignment, f64)>,
 ) -> Result<()> {
     // JSONファイル読み込み
     let content = std::fs::read_to_string(input_path)?;
     let mut results: Vec<AnalysisResult> = serde_json::from_str(&content)?;
 
+    // GPS位置から測点推定
+    if let Some((centerline, max_offset)) = alignment {
+        let report = assign_stations(&mut results, centerline, max_offset);
+        println!("🛰 GPSから測点を設定: {}枚", report.assigned.len());
+        for (name, p) in &report.too_far {
+            println!("  ⚠ {} は中心線から{:.1}m離れています（{} 付近）", name, p.offset, p.station());
+        }
+    }
+
     // 測点が空の写真を抽出
     let empty_indices = extract_empty_station_photos(&results);
 
@@ -214,5 +230,12 @@ mod tests {
         assert_eq!(stations.len(), 2);
         assert!(stations.contains(&"No.10".to_string()));
         assert!(stations.contains(&"No.20".to_string()));
+
+        // 表記違いは1つにまとめて距離順
+        let results: Vec<AnalysisResult> = ["No.20", "起点側", "NO.3", "No3+0.0m", "No.3〜No.5"]
+            .iter()
+            .map(|s| AnalysisResult { station: s.to_string(), ..Default::default() })
+            .collect();
+        assert_eq!(collect_existing_stations(&results), vec!["No.3", "No.3〜No.5", "No.20", "起点側"]);
     }
 }
@@ -0,0 +1,430 @@
+//! フォルダ監視モジュール
+//!
+//! 同期フォルダに届く写真をファイル変更通知で検出し、変更が一定時間止んだ時点で
+//! まとめて解析して result.json に追記する。
+//!
+//! - 解析はキャッシュ経由（同じ内容の写真は再解析しない）
+//! - 写真はパスと内容のハッシュで見分ける（別フォルダの同名写真も、同期し直して内容が変わった写真も解析する）。
+//!   解析済みのハッシュは result.json の隣の `*.watch.json` に残す
+//! - result.json に既にある写真は、内容が変わらない限り変更しない（手修正を保護）。追記前に毎回読み直す
+//! - PDF再生成を指定した場合、追加があれば一定間隔で作り直す
+
+use crate::ai_provider::AiProvider;
+use crate::analyzer::{self, cache::compute_file_hash, AnalysisResult};
+use crate::cli::{ExportFormat, PdfQuality};
+use crate::error::{PhotoAiError, Result};
+use crate::export;
+use crate::scanner::{self, format::has_image_like_extension, ImageInfo, ScanOptions};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 変更の確認間隔
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 監視設定
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    pub scan: ScanOptions,
+    /// 追記先の result.json
+    pub output: PathBuf,
+    pub batch_size: usize,
+    /// 最後の変更からこの時間、新しい変更がなければ解析を開始
+    pub debounce: Duration,
+    /// PDF再生成（省略時は生成しない）
+    pub pdf: Option<PdfSchedule>,
+    pub provider: AiProvider,
+    pub verbose: bool,
+}
+
+/// PDF再生成の設定
+#[derive(Debug, Clone)]
+pub struct PdfSchedule {
+    pub interval: Duration,
+    pub output: PathBuf,
+    pub title: String,
+    pub quality: PdfQuality,
+}
+
+/// 変更通知をまとめる
+///
+/// 同期中は同じファイルに何度も通知が来るため、最後の通知から `quiet` 経過した時点で
+/// それまでのファイルを1回分として取り出す
+#[derive(Debug)]
+pub struct Debouncer {
+    quiet: Duration,
+    pending: BTreeSet<PathBuf>,
+    last_event: Option<Instant>,
+}
+
+impl Debouncer {
+    pub fn new(quiet: Duration) -> Self {
+        Self {
+            quiet,
+            pending: BTreeSet::new(),
+            last_event: None,
+        }
+    }
+
+    pub fn push(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path);
+        self.last_event = Some(now);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// 通知が止んでいれば溜まったファイルを返す
+    pub fn take_ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        match self.last_event {
+            Some(last) if now.duration_since(last) >= self.quiet => {
+                self.last_event = None;
+                std::mem::take(&mut self.pending).into_iter().collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// 監視対象のファイルか（隠しファイル・隠しフォルダ配下と画像以外は無視）
+///
+/// result.json・キャッシュへの書き込みで再度解析が走らないようにする
+pub fn is_watch_target(root: &Path, path: &Path) -> bool {
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    let hidden = rel.components().any(|c| match c {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    });
+    !hidden && has_image_like_extension(path)
+}
+
+/// result.json を読み込む（なければ空）
+pub fn load_results(path: &Path) -> Result<Vec<AnalysisResult>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 解析済みの写真の内容（パス → 内容のハッシュ）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeenPhotos {
+    hashes: BTreeMap<String, String>,
+}
+
+impl SeenPhotos {
+    /// 保存先（`result.json` → `result.watch.json`）
+    pub fn state_path(output: &Path) -> PathBuf {
+        output.with_extension("watch.json")
+    }
+
+    /// 読み込む（なければ result.json にある写真を今の内容で解析済みとする）
+    pub fn load(output: &Path, results: &[AnalysisResult]) -> Result<Self> {
+        match std::fs::read_to_string(Self::state_path(output)) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let hashes = results
+                    .iter()
+                    .filter(|r| !r.file_path.is_empty())
+                    .filter_map(|r| Some((r.file_path.clone(), compute_file_hash(Path::new(&r.file_path)).ok()?)))
+                    .collect();
+                Ok(Self { hashes })
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, output: &Path) -> Result<()> {
+        std::fs::write(Self::state_path(output), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, path: &Path, hash: String) {
+        self.hashes.insert(path.display().to_string(), hash);
+    }
+}
+
+/// 新しい写真の結果を末尾に追加し、内容が変わった写真（同じパスの結果がある）は置き換える。件数を返す
+///
+/// パスのない結果はファイル名で照合し、既存の結果を残す
+pub fn merge_new_results(existing: &mut Vec<AnalysisResult>, new_results: Vec<AnalysisResult>) -> usize {
+    let mut known_names: HashSet<String> =
+        existing.iter().filter(|r| r.file_path.is_empty()).map(|r| r.file_name.clone()).collect();
+    let mut merged = 0;
+    for result in new_results {
+        if !result.file_path.is_empty() {
+            match existing.iter_mut().find(|r| r.file_path == result.file_path) {
+                Some(old) => *old = result,
+                None => existing.push(result),
+            }
+            merged += 1;
+        } else if known_names.insert(result.file_name.clone()) {
+            existing.push(result);
+            merged += 1;
+        }
+    }
+    merged
+}
+
+/// まだ解析していない写真（パスの初出、または内容が変わったもの）と内容のハッシュ
+fn unseen_images(images: Vec<ImageInfo>, results: &[AnalysisResult], seen: &SeenPhotos) -> Vec<(ImageInfo, String)> {
+    // パスのない古い結果はファイル名で照合する
+    let pathless: HashSet<&str> =
+        results.iter().filter(|r| r.file_path.is_empty()).map(|r| r.file_name.as_str()).collect();
+    images
+        .into_iter()
+        .filter_map(|img| {
+            let hash = compute_file_hash(&img.path).ok()?;
+            let unseen = match seen.hashes.get(&img.path.display().to_string()) {
+                Some(known) => *known != hash,
+                None => !pathless.contains(img.file_name.as_str()),
+            };
+            unseen.then_some((img, hash))
+        })
+        .collect()
+}
+
+/// 未解析の写真を解析して result.json に追記し、追加件数を返す
+async fn process_new_photos(folder: &Path, options: &WatchOptions) -> Result<usize> {
+    let report = scanner::scan(folder, &options.scan)?;
+    if options.verbose {
+        for skipped in &report.skipped {
+            println!("    スキップ: {} ({})", skipped.path.display(), skipped.reason);
+        }
+    }
+
+    let existing = load_results(&options.output)?;
+    let mut seen = SeenPhotos::load(&options.output, &existing)?;
+    let (images, hashes): (Vec<ImageInfo>, Vec<String>) = unseen_images(report.images, &existing, &seen).into_iter().unzip();
+    if images.is_empty() {
+        seen.save(&options.output)?;
+        return Ok(0);
+    }
+
+    println!("  新しい写真: {}枚 解析中...", images.len());
+    let new_results = analyzer::analyze_images_with_cache(
+        &images,
+        folder,
+        options.batch_size,
+        options.verbose,
+        options.provider,
+    )
+    .await?;
+
+    // 解析中に手修正された可能性があるため、書き込み直前に読み直す
+    let mut results = load_results(&options.output)?;
+    let added = merge_new_results(&mut results, new_results);
+    if added > 0 {
+        std::fs::write(&options.output, serde_json::to_string_pretty(&results)?)?;
+        println!("✔ {}件を追加・更新 (計{}件): {}", added, results.len(), options.output.display());
+    }
+    for (img, hash) in images.iter().zip(hashes) {
+        seen.record(&img.path, hash);
+    }
+    seen.save(&options.output)?;
+    Ok(added)
+}
+
+fn regenerate_pdf(schedule: &PdfSchedule, results_path: &Path) -> Result<()> {
+    let results = load_results(results_path)?;
+    if results.is_empty() {
+        return Ok(());
+    }
+    export::export_results(&results, &ExportFormat::Pdf, &schedule.output, 3, &schedule.title, schedule.quality)
+}
+
+/// フォルダを監視し、Ctrl+C で終了するまで新しい写真を解析し続ける
+pub async fn run(folder: &Path, options: &WatchOptions) -> Result<()> {
+    if !folder.is_dir() {
+        return Err(PhotoAiError::FolderNotFound(folder.display().to_string()));
+    }
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+        }
+    })
+    .map_err(|e| PhotoAiError::Watch(e.to_string()))?;
+    let mode = if options.scan.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+    watcher.watch(folder, mode).map_err(|e| PhotoAiError::Watch(e.to_string()))?;
+
+    println!("👀 監視中: {} (Ctrl+C で終了)", folder.display());
+
+    // 解析中にも Ctrl+C を受けられるよう、シグナルの待ち受けは1つだけ作って使い回す
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    // 起動前に届いていた写真を先に処理
+    let mut pdf_dirty = tokio::select! {
+        processed = process_new_photos(folder, options) => match processed {
+            Ok(added) => added > 0,
+            Err(e) => {
+                eprintln!("  ⚠ 解析エラー: {}（次の変更で再試行）", e);
+                false
+            }
+        },
+        _ = &mut ctrl_c => {
+            println!("\n  ⚠ 解析を中断しました（次回起動時に解析されます）");
+            println!("\n✅ 監視を終了");
+            return Ok(());
+        }
+    };
+
+    let mut debouncer = Debouncer::new(options.debounce);
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+    let mut last_pdf = Instant::now();
+
+    loop {
+        tokio::select! {
+            Some(path) = rx.recv() => {
+                if is_watch_target(folder, &path) {
+                    debouncer.push(path, Instant::now());
+                }
+            }
+            _ = tick.tick() => {
+                let ready = debouncer.take_ready(Instant::now());
+                if !ready.is_empty() {
+                    println!("\n- 変更を検出: {}件", ready.len());
+                    if options.verbose {
+                        for path in &ready {
+                            println!("    {}", path.display());
+                        }
+                    }
+                    tokio::select! {
+                        processed = process_new_photos(folder, options) => match processed {
+                            Ok(added) => pdf_dirty |= added > 0,
+                            Err(e) => eprintln!("  ⚠ 解析エラー: {}（次の変更で再試行）", e),
+                        },
+                        _ = &mut ctrl_c => {
+                            // 解析中のバッチは result.json に書き込まない
+                            println!("\n  ⚠ 解析を中断しました（次回起動時に解析されます）");
+                            break;
+                        }
+                    }
+                }
+
+                if let Some(schedule) = &options.pdf {
+                    if pdf_dirty && last_pdf.elapsed() >= schedule.interval {
+                        match regenerate_pdf(schedule, &options.output) {
+                            Ok(()) => pdf_dirty = false,
+                            Err(e) => eprintln!("  ⚠ PDF生成エラー: {}", e),
+                        }
+                        last_pdf = Instant::now();
+                    }
+                }
+            }
+            _ = &mut ctrl_c => break,
+        }
+    }
+
+    if !debouncer.is_empty() {
+        println!("\n  ⚠ 未処理の変更があります（次回起動時に解析されます）");
+    }
+    if let (Some(schedule), true) = (&options.pdf, pdf_dirty) {
+        regenerate_pdf(schedule, &options.output)?;
+    }
+    println!("\n✅ 監視を終了");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, station: &str) -> AnalysisResult {
+        AnalysisResult {
+            file_name: name.to_string(),
+            station: station.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_debouncer_waits_for_quiet_period() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_secs(5));
+        debouncer.push(PathBuf::from("a.jpg"), start);
+        debouncer.push(PathBuf::from("b.jpg"), start + Duration::from_secs(3));
+        debouncer.push(PathBuf::from("a.jpg"), start + Duration::from_secs(4));
+
+        assert!(debouncer.take_ready(start + Duration::from_secs(8)).is_empty());
+        let ready = debouncer.take_ready(start + Duration::from_secs(9));
+        assert_eq!(ready, vec![PathBuf::from("a.jpg"), PathBuf::from("b.jpg")]);
+        assert!(debouncer.is_empty());
+        assert!(debouncer.take_ready(start + Duration::from_secs(20)).is_empty());
+    }
+
+    #[test]
+    fn test_is_watch_target() {
+        let root = Path::new("/site/photos");
+        assert!(is_watch_target(root, Path::new("/site/photos/IMG_1.JPG")));
+        assert!(is_watch_target(root, Path::new("/site/photos/day1/IMG_2.heic")));
+        assert!(!is_watch_target(root, Path::new("/site/photos/result.json")));
+        assert!(!is_watch_target(root, Path::new("/site/photos/.IMG_3.jpg.tmp")));
+    }
+
+    #[test]
+    fn test_merge_keeps_existing_entries() {
+        let mut existing = vec![result("IMG_1.jpg", "No.5（手修正）")];
+        let added = merge_new_results(
+            &mut existing,
+            vec![result("IMG_1.jpg", "No.1"), result("IMG_2.jpg", "No.2"), result("IMG_2.jpg", "")],
+        );
+        assert_eq!(added, 1);
+        assert_eq!(existing.len(), 2);
+        assert_eq!(existing[0].station, "No.5（手修正）");
+        assert_eq!(existing[1].station, "No.2");
+    }
+
+    #[test]
+    fn test_same_name_in_subfolders_and_changed_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let day1 = dir.path().join("day1").join("IMG_0001.JPG");
+        let day2 = dir.path().join("day2").join("IMG_0001.JPG");
+        for (path, content) in [(&day1, "day1"), (&day2, "day2")] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let image = |path: &Path| ImageInfo { path: path.to_path_buf(), file_name: "IMG_0001.JPG".to_string(), ..Default::default() };
+        let analyzed = |path: &Path, station: &str| AnalysisResult {
+            file_path: path.display().to_string(),
+            ..result("IMG_0001.JPG", station)
+        };
+        let output = dir.path().join("result.json");
+        let mut results = vec![analyzed(&day1, "No.1（手修正）")];
+
+        // day1 は解析済み、別フォルダの同名写真は未解析
+        let seen = SeenPhotos::load(&output, &results).unwrap();
+        let unseen = unseen_images(vec![image(&day1), image(&day2)], &results, &seen);
+        assert_eq!(unseen.iter().map(|(img, _)| img.path.clone()).collect::<Vec<_>>(), vec![day2.clone()]);
+        assert_eq!(merge_new_results(&mut results, vec![analyzed(&day2, "No.2")]), 1);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].station, "No.1（手修正）");
+
+        // 内容が変わった写真は解析し直して置き換える
+        let mut seen = seen;
+        seen.record(&day2, unseen[0].1.clone());
+        seen.save(&output).unwrap();
+        std::fs::write(&day1, "day1 resynced").unwrap();
+        let seen = SeenPhotos::load(&output, &results).unwrap();
+        let unseen = unseen_images(vec![image(&day1), image(&day2)], &results, &seen);
+        assert_eq!(unseen.iter().map(|(img, _)| img.path.clone()).collect::<Vec<_>>(), vec![day1.clone()]);
+        assert_eq!(merge_new_results(&mut results, vec![analyzed(&day1, "No.1")]), 1);
+        assert_eq!(results.iter().map(|r| r.station.as_str()).collect::<Vec<_>>(), vec!["No.1", "No.2"]);
+    }
+
+    #[test]
+    fn test_load_results_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_results(&dir.path().join("result.json")).unwrap().is_empty());
+    }
+}
@@ -123,11 +123,13 @@ fn test_filter_cached_images() {
             file_name: "img1.jpg".to_string(),
             path: img1_path.clone(),
             date: None,
+            ..Default::default()
         },
         ImageInfo {
             file_name: "img2.jpg".to_string(),
             path: img2_path.clone(),
             date: Some("2026-01-18".to_string()),
+            ..Default::default()
         },
     ];
 
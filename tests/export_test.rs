@@ -28,6 +28,10 @@ fn create_test_result(index: usize) -> AnalysisResult {
         reasoning: String::new(),
         remarks_candidates: Vec::new(),
         focus_target: String::new(),
+        exif: Default::default(),
+        hint_conflict: String::new(),
+        warnings: Vec::new(),
+        qc_failures: Vec::new(),
     }
 }
 
@@ -37,7 +41,7 @@ fn test_pdf_generation_without_images() {
     let output_path = dir.path().join("test_output.pdf");
 
     let results: Vec<AnalysisResult> = (1..=3)
-        .map(|i| create_test_result(i))
+        .map(create_test_result)
         .collect();
 
     let result = pdf::generate_pdf(
@@ -83,7 +87,7 @@ fn test_excel_generation() {
     let output_path = dir.path().join("test_output.xlsx");
 
     let results: Vec<AnalysisResult> = (1..=5)
-        .map(|i| create_test_result(i))
+        .map(create_test_result)
         .collect();
 
     let result = excel::generate_excel(&results, &output_path, "テスト写真帳");
@@ -115,7 +119,7 @@ fn test_pdf_quality_options() {
     let dir = tempdir().expect("Failed to create temp dir");
 
     let results: Vec<AnalysisResult> = (1..=2)
-        .map(|i| create_test_result(i))
+        .map(create_test_result)
         .collect();
 
     for quality in [PdfQuality::Low, PdfQuality::Medium, PdfQuality::High] {
@@ -143,7 +147,7 @@ fn test_pdf_excel_both_generation() {
     let excel_path = dir.path().join("consistency.xlsx");
 
     let results: Vec<AnalysisResult> = (1..=3)
-        .map(|i| create_test_result(i))
+        .map(create_test_result)
         .collect();
 
     // PDF生成
@@ -197,6 +201,10 @@ fn test_excel_data_consistency() {
             reasoning: String::new(),
             remarks_candidates: Vec::new(),
             focus_target: String::new(),
+            exif: Default::default(),
+            hint_conflict: String::new(),
+            warnings: Vec::new(),
+            qc_failures: Vec::new(),
         },
     ];
 
@@ -263,6 +271,10 @@ fn test_excel_japanese_text() {
             reasoning: String::new(),
             remarks_candidates: Vec::new(),
             focus_target: String::new(),
+            exif: Default::default(),
+            hint_conflict: String::new(),
+            warnings: Vec::new(),
+            qc_failures: Vec::new(),
         },
     ];
 
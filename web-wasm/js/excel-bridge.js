@@ -33,6 +33,9 @@ import {
   LAYOUT_FIELDS_2UP
 } from './layout-constants.generated.js';
 
+/** 規格（qc）を満たさない計測値の前に付ける目印（pdf_core::QC_FAILURE_MARK と同じ） */
+const QC_FAILURE_MARK = '【規格外】';
+
 // ============================================
 // メイン関数
 // ============================================
@@ -146,7 +149,13 @@ export async function generateExcel(photosJson, optionsJson) {
           value = photo[field.key] || '';
         }
 
-        createFieldCell(sheet, fieldRow, field.label, value, field.rowSpan);
+        // 規格（qc）を満たさない計測値は赤字にし、判定内容をメモに残す
+        const qcFailures = field.key === 'measurements' ? (photo.qcFailures || []) : [];
+        if (qcFailures.length > 0) {
+          value = `${QC_FAILURE_MARK}${value || '-'}`;
+        }
+
+        createFieldCell(sheet, fieldRow, field.label, value, field.rowSpan, qcFailures);
         fieldRow += field.rowSpan;
       }
 
@@ -161,8 +170,9 @@ export async function generateExcel(photosJson, optionsJson) {
 
 /**
  * フィールドセルを作成
+ * @param {string[]} [qcFailures] - 規格外の内容（あれば値を赤字にしてメモを付ける）
  */
-function createFieldCell(sheet, row, label, value, rowSpan) {
+function createFieldCell(sheet, row, label, value, rowSpan, qcFailures = []) {
   // ラベルセル（列B）
   const labelCell = sheet.getCell(row, 2);
   labelCell.value = label;
@@ -181,6 +191,10 @@ function createFieldCell(sheet, row, label, value, rowSpan) {
   valueCell.alignment = { vertical: 'middle', horizontal: 'left', wrapText: true };
   valueCell.font = { name: FONT_NAME, size: FONT_SIZE };
   valueCell.border = BORDER_THIN;
+  if (qcFailures.length > 0) {
+    valueCell.font = { name: FONT_NAME, size: FONT_SIZE, bold: true, color: { argb: 'FFCC0000' } };
+    valueCell.note = qcFailures.join('\n');
+  }
 
   // 複数行の場合はマージ
   if (rowSpan > 1) {
@@ -169,7 +169,11 @@ export async function generatePdf(photosJson, layoutJson, optionsJson) {
         { label: '作業段階', value: photo.subphase || photo.detail || '' },
         { label: '測点', value: photo.station || '' },
         { label: '写真区分', value: photo.photoCategory || '' },
-        { label: '計測値', value: photo.measurements || '' },
+        {
+          label: '計測値',
+          value: photo.qcFailures?.length ? `【規格外】${photo.measurements || '-'}` : photo.measurements || '',
+          alert: Boolean(photo.qcFailures?.length),
+        },
         { label: '備考', value: photo.remarks || '' },
       ];
 
@@ -184,7 +188,7 @@ export async function generatePdf(photosJson, layoutJson, optionsJson) {
           y: textY,
           size: fontSize,
           font: font,
-          color: PDFLib.rgb(0, 0, 0),
+          color: info.alert ? PDFLib.rgb(0.8, 0, 0) : PDFLib.rgb(0, 0, 0),
         });
         textY -= lineHeight;
       }
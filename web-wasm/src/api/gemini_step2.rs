@@ -8,7 +8,7 @@ use crate::api::gemini::{
 use photo_ai_common::{
     RawImageData, Step2Result, AnalysisResult, HierarchyMaster,
     build_step2_prompt, parse_step2_response,
-    detect_work_types, merge_results, ImageMeta,
+    detect_work_types, merge_results, correct_results, fill_remarks_candidates, ImageMeta,
 };
 
 /// Step2実行（マスタ照合）
@@ -45,7 +45,7 @@ pub async fn analyze_step2(
 
 /// 2段階解析（マスタあり）
 ///
-/// Step1実行 -> 工種自動判定 -> マスタ絞込み -> Step2実行 -> 結果マージ
+/// Step1実行 -> 工種自動判定 -> マスタ絞込み -> Step2実行 -> 結果マージ -> 整合性チェック
 pub async fn analyze_with_master(
     api_key: &str,
     images: Vec<(String, Option<String>, String)>,  // (file_name, date, data_url)
@@ -76,11 +76,18 @@ pub async fn analyze_with_master(
             file_name: file_name.clone(),
             file_path: String::new(),
             date: date.clone().unwrap_or_default(),
+            ..Default::default()
         })
         .collect();
 
     // 結果マージ
-    let results = merge_results(&raw_data, &step2_results, &image_metas);
+    let mut results = merge_results(&raw_data, &step2_results, &image_metas);
+
+    // マスタとの整合性チェック（確度の高い候補は自動適用、問題は warnings に残る）
+    correct_results(&mut results, master);
+
+    // 検索パターンで照合した上位の備考を候補に加える（カードから1クリックで切り替え）
+    fill_remarks_candidates(&mut results, master);
 
     Ok(results)
-}
+}
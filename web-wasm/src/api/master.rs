@@ -0,0 +1,18 @@
+//! 工種マスタの読み込み（アップロードされたファイル）
+
+use std::path::Path;
+
+use wasm_bindgen::prelude::*;
+
+use photo_ai_common::{HierarchyMaster, MasterFormat};
+
+/// アップロードされたマスタ（CSV・Excel・JSON）を読み込む
+///
+/// 形式はファイル名の拡張子で判定する。不正な行があればエラー
+pub fn load_master_file(file_name: &str, bytes: &[u8]) -> Result<HierarchyMaster, JsValue> {
+    let format = MasterFormat::from_path(Path::new(file_name))
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    HierarchyMaster::load_bytes(bytes, format)
+        .and_then(|loaded| loaded.into_master())
+        .map_err(|e| JsValue::from_str(&format!("マスタ読み込みエラー: {}", e)))
+}
@@ -2,5 +2,7 @@
 
 pub mod gemini;
 pub mod gemini_step2;
+pub mod master;
 
 pub use gemini_step2::{analyze_step2, analyze_with_master};
+pub use master::load_master_file;
@@ -14,9 +14,11 @@ use crate::components::{
 use crate::export::{excel_wasm, pdf_wasm};
 use crate::export::js_bindings::{download_excel_js, download_pdf_js};
 use crate::secure_store::{clear_api_key, decrypt_api_key, encrypt_api_key};
-use photo_ai_common::AnalysisResult;
+use photo_ai_common::{AliasRegistry, AnalysisResult, HierarchyMaster};
 use std::collections::HashSet;
+use std::sync::Arc;
 use crate::api::gemini::analyze_batch;
+use crate::api::{analyze_with_master, load_master_file};
 
 /// アプリケーションの状態
 #[derive(Clone, Default)]
@@ -74,6 +76,25 @@ fn build_export_results(photos: &[PhotoItem]) -> Vec<AnalysisResult> {
         .collect()
 }
 
+/// エイリアスプリセット（カンマ区切り、後に指定したものが優先）を適用し、変換件数・不明な名前をログに出す
+///
+/// プリセットはCLIの `--preset` と同じ組み込みファイル（`common/aliases/*.json`）。不明な名前は飛ばして出力を続ける
+fn apply_alias_presets(results: Vec<AnalysisResult>, presets: &str, log: impl Fn(String)) -> Vec<AnalysisResult> {
+    let names: Vec<&str> = presets.split([',', '、']).map(str::trim).filter(|n| !n.is_empty()).collect();
+    if names.is_empty() {
+        return results;
+    }
+    let (config, warnings) = AliasRegistry::builtin().stack_known(&names);
+    for warning in warnings {
+        log(format!("警告: {}", warning));
+    }
+    let (results, report) = config.compile().apply_all(&results);
+    if !report.hits.is_empty() {
+        log(format!("エイリアス変換: {}件", report.hits.len()));
+    }
+    results
+}
+
 /// メインアプリケーションコンポーネント
 #[component]
 pub fn App() -> impl IntoView {
@@ -81,6 +102,7 @@ pub fn App() -> impl IntoView {
     let (api_key, set_api_key) = signal(String::new());
     let (title, set_title) = signal("工事写真台帳".to_string());
     let (photos_per_page, set_photos_per_page) = signal(3u8);
+    let (alias_presets, set_alias_presets) = signal(String::new());
     let (photos, set_photos) = signal(Vec::<PhotoItem>::new());
     let (is_analyzing, set_is_analyzing) = signal(false);
     let (progress, set_progress) = signal(0.0f32);
@@ -93,6 +115,8 @@ pub fn App() -> impl IntoView {
     let (logs, set_logs) = signal(Vec::<String>::new());
     let (passphrase, set_passphrase) = signal(String::new());
     let (api_key_status, set_api_key_status) = signal(String::new());
+    let (master, set_master) = signal(None::<Arc<HierarchyMaster>>);
+    let (master_status, set_master_status) = signal(String::new());
 
     let push_log = {
         let set_logs = set_logs.clone();
@@ -136,6 +160,57 @@ pub fn App() -> impl IntoView {
             set_is_analyzing.set(true);
             set_progress.set(0.0);
 
+            // マスタがあれば2段階解析（マスタ照合・整合性チェック込み）
+            if let Some(master) = master.get() {
+                let images: Vec<(String, Option<String>, String)> = photo_list
+                    .iter()
+                    .map(|p| (p.file_name.clone(), None, p.data_url.clone()))
+                    .collect();
+
+                spawn_local(async move {
+                    let outcome = analyze_with_master(&key, images, &master, move |step, steps, message| {
+                        set_progress.set(step as f32 / steps as f32 * 100.0);
+                        push_log(message.to_string());
+                    })
+                    .await;
+
+                    match outcome {
+                        Ok(mut results) => {
+                            let warned = results.iter().filter(|r| !r.warnings.is_empty()).count();
+                            set_photos.update(|photos| {
+                                for photo in photos.iter_mut() {
+                                    // 結果はファイル名で対応付ける（同名は先に並んだ写真から）
+                                    match results.iter().position(|r| r.file_name == photo.file_name) {
+                                        Some(index) => {
+                                            photo.status = crate::app::PhotoStatus::Done;
+                                            photo.analysis = Some(results.remove(index));
+                                        }
+                                        None => {
+                                            photo.status = crate::app::PhotoStatus::Error;
+                                            push_log(format!("解析結果なし: {}", photo.file_name));
+                                        }
+                                    }
+                                }
+                            });
+                            if warned > 0 {
+                                push_log(format!("マスタとの不整合: {}枚（各写真の警告を確認）", warned));
+                            }
+                            push_log("AI解析が完了しました".to_string());
+                        }
+                        Err(err) => {
+                            set_photos.update(|photos| {
+                                for photo in photos.iter_mut() {
+                                    photo.status = crate::app::PhotoStatus::Error;
+                                }
+                            });
+                            push_log(format!("解析失敗: {}", err.as_string().unwrap_or_else(|| format!("{:?}", err))));
+                        }
+                    }
+                    set_is_analyzing.set(false);
+                });
+                return;
+            }
+
             let batch_input: Vec<(String, String, String)> = photo_list
                 .iter()
                 .map(|p| (p.id.clone(), p.data_url.clone(), p.file_name.clone()))
@@ -226,6 +301,28 @@ pub fn App() -> impl IntoView {
         }
     };
 
+    let on_load_master = move |file_name: String, bytes: Vec<u8>| {
+        match load_master_file(&file_name, &bytes) {
+            Ok(loaded) => {
+                let message = format!("マスタを読み込み: {} ({}行)", file_name, loaded.rows().len());
+                set_master.set(Some(Arc::new(loaded)));
+                set_master_status.set(message.clone());
+                push_log(message);
+            }
+            Err(err) => {
+                let message = err.as_string().unwrap_or_else(|| format!("{:?}", err));
+                set_master_status.set(message.clone());
+                push_log(message);
+            }
+        }
+    };
+
+    let on_clear_master = move |_| {
+        set_master.set(None);
+        set_master_status.set(String::new());
+        push_log("マスタを解除".to_string());
+    };
+
     let on_clear_api_key = {
         let set_api_key_status = set_api_key_status.clone();
         let push_log = push_log.clone();
@@ -383,6 +480,23 @@ pub fn App() -> impl IntoView {
         }
     };
 
+    let on_pick_remarks = {
+        let set_photos = set_photos.clone();
+        move |photo_id: String, remarks: String| {
+            set_photos.update(|photos| {
+                if let Some(analysis) = photos
+                    .iter_mut()
+                    .find(|photo| photo.id == photo_id)
+                    .and_then(|photo| photo.analysis.as_mut())
+                {
+                    // マスタがあれば、切り替えた備考の行に合わせて上位の階層も直す
+                    photo_ai_common::switch_remarks(analysis, &remarks, master.get_untracked().as_deref());
+                }
+            });
+            push_log(format!("備考を切り替え: {}", remarks));
+        }
+    };
+
     let on_select_all = {
         let photos = photos.clone();
         let set_selected_ids = set_selected_ids.clone();
@@ -461,65 +575,73 @@ pub fn App() -> impl IntoView {
     };
 
     // PDF出力ハンドラ
-    let on_export_pdf = move |_| {
-        let photo_items = photos.get();
-        let title_value = title.get();
-        let per_page = photos_per_page.get();
-
-        let results = build_export_results(&photo_items);
-        if results.is_empty() {
-            console::warn_1(&"No analyzed photos available for PDF export.".into());
-            return;
-        }
+    let on_export_pdf = {
+        let push_log = push_log.clone();
+        move |_| {
+            let photo_items = photos.get();
+            let title_value = title.get();
+            let per_page = photos_per_page.get();
 
-        let export_title = if title_value.trim().is_empty() {
-            "工事写真台帳".to_string()
-        } else {
-            title_value
-        };
-
-        spawn_local(async move {
-            match pdf_wasm::generate_pdf(&results, &export_title, per_page).await {
-                Ok(data) => {
-                    let filename = format!("{}.pdf", export_title);
-                    download_pdf_js(&data, &filename);
-                }
-                Err(err) => {
-                    console::error_1(&format!("PDF export failed: {}", err).into());
-                }
+            let results = build_export_results(&photo_items);
+            if results.is_empty() {
+                console::warn_1(&"No analyzed photos available for PDF export.".into());
+                return;
             }
-        });
+            let results = apply_alias_presets(results, &alias_presets.get(), &push_log);
+
+            let export_title = if title_value.trim().is_empty() {
+                "工事写真台帳".to_string()
+            } else {
+                title_value
+            };
+
+            spawn_local(async move {
+                match pdf_wasm::generate_pdf(&results, &export_title, per_page).await {
+                    Ok(data) => {
+                        let filename = format!("{}.pdf", export_title);
+                        download_pdf_js(&data, &filename);
+                    }
+                    Err(err) => {
+                        console::error_1(&format!("PDF export failed: {}", err).into());
+                    }
+                }
+            });
+        }
     };
 
     // Excel出力ハンドラ
-    let on_export_excel = move |_| {
-        let photo_items = photos.get();
-        let title_value = title.get();
-        let per_page = photos_per_page.get();
-
-        let results = build_export_results(&photo_items);
-        if results.is_empty() {
-            console::warn_1(&"No analyzed photos available for Excel export.".into());
-            return;
-        }
+    let on_export_excel = {
+        let push_log = push_log.clone();
+        move |_| {
+            let photo_items = photos.get();
+            let title_value = title.get();
+            let per_page = photos_per_page.get();
 
-        let export_title = if title_value.trim().is_empty() {
-            "工事写真台帳".to_string()
-        } else {
-            title_value
-        };
-
-        spawn_local(async move {
-            match excel_wasm::generate_excel(&results, &export_title, per_page).await {
-                Ok(data) => {
-                    let filename = format!("{}.xlsx", export_title);
-                    download_excel_js(&data, &filename);
-                }
-                Err(err) => {
-                    console::error_1(&format!("Excel export failed: {}", err).into());
-                }
+            let results = build_export_results(&photo_items);
+            if results.is_empty() {
+                console::warn_1(&"No analyzed photos available for Excel export.".into());
+                return;
             }
-        });
+            let results = apply_alias_presets(results, &alias_presets.get(), &push_log);
+
+            let export_title = if title_value.trim().is_empty() {
+                "工事写真台帳".to_string()
+            } else {
+                title_value
+            };
+
+            spawn_local(async move {
+                match excel_wasm::generate_excel(&results, &export_title, per_page).await {
+                    Ok(data) => {
+                        let filename = format!("{}.xlsx", export_title);
+                        download_excel_js(&data, &filename);
+                    }
+                    Err(err) => {
+                        console::error_1(&format!("Excel export failed: {}", err).into());
+                    }
+                }
+            });
+        }
     };
 
     view! {
@@ -539,6 +661,11 @@ pub fn App() -> impl IntoView {
                 set_title=set_title
                 photos_per_page=photos_per_page
                 set_photos_per_page=set_photos_per_page
+                alias_presets=alias_presets
+                set_alias_presets=set_alias_presets
+                master_status=master_status
+                on_load_master=on_load_master
+                on_clear_master=on_clear_master
             />
 
             <UploadArea api_key=api_key on_photos_added=on_photos_added />
@@ -632,6 +759,7 @@ pub fn App() -> impl IntoView {
                     on_reorder=on_reorder
                     selected_ids=selected_ids
                     on_toggle_select=on_toggle_select
+                    on_pick_remarks=on_pick_remarks
                 />
             </Show>
 
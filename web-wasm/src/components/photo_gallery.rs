@@ -5,7 +5,7 @@ use crate::app::PhotoItem;
 use std::collections::HashSet;
 
 #[component]
-pub fn PhotoGallery<FP, FC, FR, FS>(
+pub fn PhotoGallery<FP, FC, FR, FS, FK>(
     photos: ReadSignal<Vec<PhotoItem>>,
     pairing_first: ReadSignal<Option<String>>,
     on_pair_select: FP,
@@ -13,12 +13,14 @@ pub fn PhotoGallery<FP, FC, FR, FS>(
     on_reorder: FR,
     selected_ids: ReadSignal<HashSet<String>>,
     on_toggle_select: FS,
+    on_pick_remarks: FK,
 ) -> impl IntoView
 where
     FP: Fn(String) + 'static + Clone + Send,
     FC: Fn(String) + 'static + Clone + Send,
     FR: Fn(String, String) + 'static + Clone + Send,
     FS: Fn(String) + 'static + Clone + Send,
+    FK: Fn(String, String) + 'static + Clone + Send,
 {
     let (dragging_id, set_dragging_id) = signal(None::<String>);
     let (drag_over_id, set_drag_over_id) = signal(None::<String>);
@@ -27,7 +29,8 @@ where
         <div class="photo-gallery">
             <For
                 each=move || photos.get()
-                key=|photo| photo.id.clone()
+                // 備考を切り替えたらカードを描き直す
+                key=|photo| (photo.id.clone(), photo.analysis.as_ref().map(|a| a.remarks.clone()))
                 children=move |photo| {
                     let on_pair_select = on_pair_select.clone();
                     let on_pair_clear = on_pair_clear.clone();
@@ -39,6 +42,7 @@ where
                     let set_drag_over_id = set_drag_over_id.clone();
                     let selected_ids = selected_ids.clone();
                     let on_toggle_select = on_toggle_select.clone();
+                    let on_pick_remarks = on_pick_remarks.clone();
                     view! {
                         <PhotoCard
                             photo=photo
@@ -52,6 +56,7 @@ where
                             set_drag_over_id=set_drag_over_id
                             selected_ids=selected_ids
                             on_toggle_select=on_toggle_select
+                            on_pick_remarks=on_pick_remarks
                         />
                     }
                 }
@@ -61,7 +66,7 @@ where
 }
 
 #[component]
-fn PhotoCard<FP, FC, FR, FS>(
+fn PhotoCard<FP, FC, FR, FS, FK>(
     photo: PhotoItem,
     pairing_first: ReadSignal<Option<String>>,
     on_pair_select: FP,
@@ -73,12 +78,14 @@ fn PhotoCard<FP, FC, FR, FS>(
     set_drag_over_id: WriteSignal<Option<String>>,
     selected_ids: ReadSignal<HashSet<String>>,
     on_toggle_select: FS,
+    on_pick_remarks: FK,
 ) -> impl IntoView
 where
     FP: Fn(String) + 'static + Clone + Send,
     FC: Fn(String) + 'static + Clone + Send,
     FR: Fn(String, String) + 'static + Clone + Send,
     FS: Fn(String) + 'static + Clone + Send,
+    FK: Fn(String, String) + 'static + Clone + Send,
 {
     let status_class = photo.status.as_str();
     let status_text = match photo.status {
@@ -188,6 +195,26 @@ where
                     view! {
                         <p>{a.work_type.clone()}" / "{a.variety.clone()}</p>
                         <p>{format!("測点: {}", if a.station.is_empty() { "-" } else { &a.station })}</p>
+                        <p>{format!("備考: {}", if a.remarks.is_empty() { "-" } else { &a.remarks })}</p>
+                        {(!a.remarks_candidates.is_empty()).then(|| view! {
+                            <div class="remarks-candidates">
+                                {a.remarks_candidates.iter().map(|candidate| {
+                                    let on_pick_remarks = on_pick_remarks.clone();
+                                    let photo_id = photo.id.clone();
+                                    let remarks = candidate.clone();
+                                    view! {
+                                        <button
+                                            class="btn btn-small btn-tertiary remarks-candidate"
+                                            title="この備考に切り替え"
+                                            on:click=move |_| on_pick_remarks(photo_id.clone(), remarks.clone())
+                                        >
+                                            {candidate.clone()}
+                                        </button>
+                                    }
+                                }).collect_view()}
+                            </div>
+                        })}
+                        {a.warnings.iter().map(|w| view! { <p class="photo-warning">"⚠ "{w.clone()}</p> }).collect_view()}
                     }
                 })}
                 <div class="photo-actions">
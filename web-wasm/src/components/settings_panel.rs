@@ -1,9 +1,12 @@
 //! 設定パネルコンポーネント
 
 use leptos::prelude::*;
+use photo_ai_common::AliasRegistry;
+use wasm_bindgen::prelude::*;
+use web_sys::{File, FileReader, HtmlInputElement};
 
 #[component]
-pub fn SettingsPanel<FS, FL, FC>(
+pub fn SettingsPanel<FS, FL, FC, FM, FX>(
     api_key: ReadSignal<String>,
     set_api_key: WriteSignal<String>,
     passphrase: ReadSignal<String>,
@@ -16,12 +19,27 @@ pub fn SettingsPanel<FS, FL, FC>(
     set_title: WriteSignal<String>,
     photos_per_page: ReadSignal<u8>,
     set_photos_per_page: WriteSignal<u8>,
+    alias_presets: ReadSignal<String>,
+    set_alias_presets: WriteSignal<String>,
+    master_status: ReadSignal<String>,
+    on_load_master: FM,
+    on_clear_master: FX,
 ) -> impl IntoView
 where
     FS: Fn(()) + 'static + Clone,
     FL: Fn(()) + 'static + Clone,
     FC: Fn(()) + 'static + Clone,
+    FM: Fn(String, Vec<u8>) + 'static + Clone,
+    FX: Fn(()) + 'static + Clone,
 {
+    // CLIの --preset と同じ組み込みプリセット
+    let preset_names = AliasRegistry::builtin()
+        .presets()
+        .iter()
+        .map(|preset| preset.name.clone())
+        .collect::<Vec<_>>()
+        .join(",");
+
     view! {
         <div class="settings-panel">
             <div class="settings-grid">
@@ -116,7 +134,77 @@ where
                         <option value="3" selected=move || photos_per_page.get() == 3>"3枚"</option>
                     </select>
                 </div>
+
+                <div class="form-group">
+                    <label for="alias-presets">"エイリアスプリセット"</label>
+                    <input
+                        type="text"
+                        id="alias-presets"
+                        placeholder=format!("カンマ区切り（{}）", preset_names)
+                        prop:value=move || alias_presets.get()
+                        on:input=move |ev| {
+                            set_alias_presets.set(event_target_value(&ev));
+                        }
+                    />
+                </div>
+
+                <div class="form-group">
+                    <label for="master-file">"工種マスタ（CSV・Excel・JSON）"</label>
+                    <input
+                        type="file"
+                        id="master-file"
+                        accept=".csv,.json,.xlsx,.xlsm,.xls"
+                        on:change={
+                            let on_load_master = on_load_master.clone();
+                            move |ev: web_sys::Event| {
+                                let Some(input) = ev.target().and_then(|t| t.dyn_into::<HtmlInputElement>().ok()) else {
+                                    return;
+                                };
+                                if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                                    read_master_file(file, on_load_master.clone());
+                                }
+                                // 同じファイルを選び直しても読み込めるようにする
+                                input.set_value("");
+                            }
+                        }
+                    />
+                    <div class="api-actions">
+                        <button
+                            class="btn btn-tertiary btn-small"
+                            on:click={
+                                let on_clear_master = on_clear_master.clone();
+                                move |_| on_clear_master(())
+                            }
+                        >
+                            "解除"
+                        </button>
+                    </div>
+                    <div class="api-key-status">
+                        {move || master_status.get()}
+                    </div>
+                </div>
             </div>
         </div>
     }
 }
+
+/// 選んだマスタファイルを読み込み、ファイル名と中身を渡す
+fn read_master_file<F>(file: File, on_loaded: F)
+where
+    F: Fn(String, Vec<u8>) + 'static,
+{
+    let file_name = file.name();
+    let reader = FileReader::new().unwrap();
+
+    let reader_clone = reader.clone();
+    let closure = Closure::wrap(Box::new(move |_: web_sys::ProgressEvent| {
+        if let Ok(result) = reader_clone.result() {
+            on_loaded(file_name.clone(), js_sys::Uint8Array::new(&result).to_vec());
+        }
+    }) as Box<dyn FnMut(_)>);
+
+    reader.set_onload(Some(closure.as_ref().unchecked_ref()));
+    closure.forget();
+
+    let _ = reader.read_as_array_buffer(&file);
+}
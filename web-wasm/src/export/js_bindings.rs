@@ -29,6 +29,9 @@ pub struct JsPhotoEntry {
     pub photo_category: String,
     pub has_board: bool,
     pub detected_text: String,
+    /// 規格（qc）を満たさない計測値（あれば計測値を強調表示）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub qc_failures: Vec<String>,
 }
 
 /// JavaScript側に渡すレイアウト設定
@@ -72,6 +75,7 @@ impl From<&photo_ai_common::AnalysisResult> for JsPhotoEntry {
             photo_category: result.photo_category.clone(),
             has_board: result.has_board,
             detected_text: result.detected_text.clone(),
+            qc_failures: result.qc_failures.clone(),
         }
     }
 }
@@ -264,6 +268,7 @@ mod tests {
             photo_category: "施工状況写真".to_string(),
             has_board: false,
             detected_text: "".to_string(),
+            qc_failures: Vec::new(),
         };
 
         let json = serde_json::to_string(&entry).expect("シリアライズ失敗");